@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Serializes appends so concurrent writers don't interleave lines
+static LOG_LOCK: Mutex<()> = Mutex::new(());
+
+/// One line of activity.jsonl in the data dir
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Activity {
+    /// A pane was opened in the UI
+    SessionOpened { ts: u64, session: String },
+    /// Text was sent to a pane; the text itself only with `activity.logInput`
+    CommandSent {
+        ts: u64,
+        target: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        text: Option<String>,
+    },
+    /// A Claude turn finished (thinking → done)
+    #[serde(rename_all = "camelCase")]
+    AgentTurn {
+        ts: u64,
+        project: String,
+        session_id: String,
+        duration_secs: Option<u64>,
+        summary: String,
+    },
+    /// A watched pattern matched pane output
+    #[serde(rename_all = "camelCase")]
+    Trigger {
+        ts: u64,
+        trigger_id: String,
+        target: String,
+        matched: String,
+    },
+    /// The UI had `target` focused for `secs`, ending at `ts`
+    Focus {
+        ts: u64,
+        project: String,
+        target: String,
+        secs: u64,
+    },
+}
+
+impl Activity {
+    pub fn ts(&self) -> u64 {
+        match self {
+            Activity::SessionOpened { ts, .. }
+            | Activity::CommandSent { ts, .. }
+            | Activity::AgentTurn { ts, .. }
+            | Activity::Trigger { ts, .. }
+            | Activity::Focus { ts, .. } => *ts,
+        }
+    }
+}
+
+fn activity_file() -> PathBuf {
+    super::paths::data_dir().join("activity.jsonl")
+}
+
+/// The previous log, kept after rotation
+fn rotated_file() -> PathBuf {
+    super::paths::data_dir().join("activity.jsonl.1")
+}
+
+pub fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Text of a command sent to a pane as it should be recorded
+pub fn input_text(text: &str) -> Option<String> {
+    let log_input = super::settings::get_settings()
+        .settings
+        .activity
+        .log_input;
+    log_input.then(|| text.to_string())
+}
+
+/// Append an entry to the activity log, rotating it past `activity.maxBytes`
+pub fn record(activity: Activity) {
+    let line = match serde_json::to_string(&activity) {
+        Ok(l) => l,
+        Err(_) => return,
+    };
+    let path = activity_file();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let max_bytes = super::settings::get_settings()
+        .settings
+        .activity
+        .max_bytes;
+
+    let _guard = LOG_LOCK.lock().unwrap();
+    let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    if max_bytes > 0 && size >= max_bytes {
+        if let Err(e) = fs::rename(&path, rotated_file()) {
+            log::warn!("[activity] Failed to rotate: {}", e);
+        }
+    }
+    let result = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut f| writeln!(f, "{}", line));
+    if let Err(e) = result {
+        log::error!("[activity] Failed to record: {}", e);
+    }
+}
+
+/// Entries with `since <= ts < until`, oldest first
+pub fn read_range(since: u64, until: u64) -> Vec<Activity> {
+    [rotated_file(), activity_file()]
+        .iter()
+        .filter_map(|path| fs::File::open(path).ok())
+        .flat_map(|file| BufReader::new(file).lines().map_while(Result::ok))
+        .filter_map(|line| serde_json::from_str::<Activity>(&line).ok())
+        .filter(|a| a.ts() >= since && a.ts() < until)
+        .collect()
+}
@@ -0,0 +1,184 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Prefixes where package managers install tools but GUI apps' PATH often misses
+const COMMON_PREFIXES: &[&str] = &[
+    "/opt/homebrew/bin",
+    "/usr/local/bin",
+    "/opt/local/bin",
+    "/run/current-system/sw/bin",
+    "/nix/var/nix/profiles/default/bin",
+    "/usr/bin",
+    "/bin",
+];
+
+static CAPABILITIES: once_cell::sync::Lazy<Mutex<Option<Capabilities>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(None));
+
+/// External programs MuxTunnel shells out to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    Tmux,
+    Ps,
+    Zoxide,
+    Git,
+    Claude,
+    Zellij,
+    Wezterm,
+}
+
+impl Tool {
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::Tmux => "tmux",
+            Tool::Ps => "ps",
+            Tool::Zoxide => "zoxide",
+            Tool::Git => "git",
+            Tool::Claude => "claude",
+            Tool::Zellij => "zellij",
+            Tool::Wezterm => "wezterm",
+        }
+    }
+
+    /// What stops working without it, and how to fix that
+    fn hint(self) -> &'static str {
+        match self {
+            Tool::Tmux => "sessions cannot be listed or attached; install tmux or set tmux.binary",
+            Tool::Ps => "pane process names fall back to tmux's current command",
+            Tool::Zoxide => "projects are resolved from MuxTunnel's own history",
+            Tool::Git => "project roots are detected from .git directories only",
+            Tool::Claude => "Claude status relies only on existing ~/.claude logs",
+            Tool::Zellij => {
+                "the zellij backend cannot be used; install zellij or set multiplexer.zellijBinary"
+            }
+            Tool::Wezterm => {
+                "WezTerm panes cannot be listed; install wezterm or set multiplexer.weztermBinary"
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolStatus {
+    pub available: bool,
+    pub path: Option<String>,
+    /// Effect of the tool being missing; `None` when available
+    pub degraded: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    pub tmux: ToolStatus,
+    pub ps: ToolStatus,
+    pub zoxide: ToolStatus,
+    pub git: ToolStatus,
+    pub claude: ToolStatus,
+    pub zellij: ToolStatus,
+    pub wezterm: ToolStatus,
+}
+
+impl Capabilities {
+    fn status(&self, tool: Tool) -> &ToolStatus {
+        match tool {
+            Tool::Tmux => &self.tmux,
+            Tool::Ps => &self.ps,
+            Tool::Zoxide => &self.zoxide,
+            Tool::Git => &self.git,
+            Tool::Claude => &self.claude,
+            Tool::Zellij => &self.zellij,
+            Tool::Wezterm => &self.wezterm,
+        }
+    }
+}
+
+/// Look a program up on `$PATH`, then in common install prefixes
+pub fn find_program(name: &str) -> Option<PathBuf> {
+    let on_path = std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect::<Vec<_>>())
+        .unwrap_or_default();
+    on_path
+        .into_iter()
+        .chain(COMMON_PREFIXES.iter().map(PathBuf::from))
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+fn detect_tool(tool: Tool) -> ToolStatus {
+    let path = match tool {
+        // The tmux module also honors tmux.binary and the login shell's PATH
+        Tool::Tmux => {
+            let binary = PathBuf::from(super::tmux::tmux_binary());
+            binary
+                .is_absolute()
+                .then_some(binary)
+                .filter(|p| p.is_file())
+        }
+        Tool::Zellij => {
+            let binary = PathBuf::from(super::zellij::zellij_binary());
+            binary
+                .is_absolute()
+                .then_some(binary)
+                .filter(|p| p.is_file())
+        }
+        Tool::Wezterm => {
+            let binary = PathBuf::from(super::wezterm::wezterm_binary());
+            binary
+                .is_absolute()
+                .then_some(binary)
+                .filter(|p| p.is_file())
+        }
+        _ => find_program(tool.name()),
+    };
+    let available = path.is_some();
+    // zellij and wezterm only matter when selected
+    let wanted = match tool {
+        Tool::Zellij => super::multiplexer::current().name() == "zellij",
+        Tool::Wezterm => super::settings::get_settings().settings.multiplexer.wezterm,
+        _ => true,
+    };
+    if !available && wanted {
+        log::warn!("[capabilities] {} not found: {}", tool.name(), tool.hint());
+    }
+    ToolStatus {
+        available,
+        path: path.map(|p| p.to_string_lossy().to_string()),
+        degraded: (!available).then(|| tool.hint().to_string()),
+    }
+}
+
+/// Re-detect every tool and cache the result
+pub fn refresh() -> Capabilities {
+    let capabilities = Capabilities {
+        tmux: detect_tool(Tool::Tmux),
+        ps: detect_tool(Tool::Ps),
+        zoxide: detect_tool(Tool::Zoxide),
+        git: detect_tool(Tool::Git),
+        claude: detect_tool(Tool::Claude),
+        zellij: detect_tool(Tool::Zellij),
+        wezterm: detect_tool(Tool::Wezterm),
+    };
+    *CAPABILITIES.lock().unwrap() = Some(capabilities.clone());
+    capabilities
+}
+
+/// Cached capabilities, detected on first use
+pub fn get() -> Capabilities {
+    let cached = CAPABILITIES.lock().unwrap().clone();
+    cached.unwrap_or_else(refresh)
+}
+
+pub fn has(tool: Tool) -> bool {
+    get().status(tool).available
+}
+
+/// Error for features that can't work without `tool`
+pub fn require(tool: Tool) -> Result<(), String> {
+    if has(tool) {
+        Ok(())
+    } else {
+        Err(format!("{} not found: {}", tool.name(), tool.hint()))
+    }
+}
@@ -3,12 +3,9 @@ use std::collections::HashMap;
 use std::fs;
 use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::time::SystemTime;
 
-static STATE: once_cell::sync::Lazy<Mutex<ClaudeState>> =
-    once_cell::sync::Lazy::new(|| Mutex::new(ClaudeState::default()));
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ClaudeSession {
@@ -24,55 +21,49 @@ struct ClaudeState {
     notification: HashMap<String, (bool, Option<SystemTime>)>,
     /// previous status for change detection
     previous_status: HashMap<String, String>,
+    /// when the current turn started thinking, for turn durations
+    turn_started: HashMap<String, SystemTime>,
 }
 
-fn claude_projects_dir() -> PathBuf {
-    dirs::home_dir()
-        .unwrap_or_default()
-        .join(".claude")
-        .join("projects")
+/// Claude session status and notification state, owned by `AppState`
+pub struct ClaudeTracker {
+    /// `paths::claude_projects_dir()` in the app
+    projects_dir: PathBuf,
+    state: Mutex<ClaudeState>,
 }
 
-/// Read the status of a Claude session from its JSONL file
-fn get_session_status(jsonl_path: &Path) -> &'static str {
-    let meta = match fs::metadata(jsonl_path) {
-        Ok(m) => m,
-        Err(_) => return "idle",
-    };
+/// Parse the last message of a Claude session JSONL file (reads only the tail)
+fn read_last_message(jsonl_path: &Path) -> Option<(serde_json::Value, fs::Metadata)> {
+    let meta = fs::metadata(jsonl_path).ok()?;
 
     let file_size = meta.len();
     if file_size == 0 {
-        return "idle";
+        return None;
     }
 
     // Read last 10KB of file
     let read_size = file_size.min(10000) as usize;
-    let mut file = match fs::File::open(jsonl_path) {
-        Ok(f) => f,
-        Err(_) => return "idle",
-    };
+    let mut file = fs::File::open(jsonl_path).ok()?;
 
     if file_size > read_size as u64 {
         let _ = file.seek(SeekFrom::Start(file_size - read_size as u64));
     }
 
     let mut buffer = vec![0u8; read_size];
-    let bytes_read = match file.read(&mut buffer) {
-        Ok(n) => n,
-        Err(_) => return "idle",
-    };
+    let bytes_read = file.read(&mut buffer).ok()?;
     buffer.truncate(bytes_read);
 
     let content = String::from_utf8_lossy(&buffer);
-    let lines: Vec<&str> = content.lines().filter(|l| !l.is_empty()).collect();
-    if lines.is_empty() {
-        return "idle";
-    }
+    let last_line = content.lines().rev().find(|l| !l.is_empty())?;
+    let msg = serde_json::from_str(last_line).ok()?;
+    Some((msg, meta))
+}
 
-    let last_line = lines[lines.len() - 1];
-    let msg: serde_json::Value = match serde_json::from_str(last_line) {
-        Ok(v) => v,
-        Err(_) => return "idle",
+/// Read the status of a Claude session from its JSONL file
+fn get_session_status(jsonl_path: &Path) -> &'static str {
+    let (msg, meta) = match read_last_message(jsonl_path) {
+        Some(m) => m,
+        None => return "idle",
     };
 
     let msg_type = msg.get("type").and_then(|v| v.as_str()).unwrap_or("");
@@ -104,123 +95,6 @@ fn get_session_status(jsonl_path: &Path) -> &'static str {
     }
 }
 
-/// Get all Claude sessions for a project path
-pub fn get_sessions_for_project(project_path: &str) -> Vec<ClaudeSession> {
-    let project_slug = project_path.replace('/', "-");
-    let project_dir = claude_projects_dir().join(&project_slug);
-
-    if !project_dir.exists() {
-        return vec![];
-    }
-
-    // Try sessions-index.json first
-    let index_path = project_dir.join("sessions-index.json");
-
-    #[derive(Deserialize)]
-    struct IndexEntry {
-        #[serde(rename = "sessionId")]
-        session_id: String,
-        #[serde(rename = "fullPath")]
-        full_path: String,
-        summary: Option<String>,
-        #[serde(rename = "projectPath")]
-        project_path: Option<String>,
-    }
-
-    #[derive(Deserialize)]
-    struct SessionsIndex {
-        entries: Vec<IndexEntry>,
-    }
-
-    let entries: Vec<(String, PathBuf, String)> = if index_path.exists() {
-        match fs::read_to_string(&index_path).ok().and_then(|s| {
-            serde_json::from_str::<SessionsIndex>(&s).ok()
-        }) {
-            Some(index) => index
-                .entries
-                .into_iter()
-                .filter(|e| {
-                    e.project_path
-                        .as_deref()
-                        .map(|p| p == project_path)
-                        .unwrap_or(true)
-                })
-                .map(|e| {
-                    (
-                        e.session_id,
-                        PathBuf::from(e.full_path),
-                        e.summary.unwrap_or_default(),
-                    )
-                })
-                .collect(),
-            None => vec![],
-        }
-    } else {
-        // Fallback: scan .jsonl files directly
-        match fs::read_dir(&project_dir) {
-            Ok(entries) => entries
-                .filter_map(|e| e.ok())
-                .filter(|e| {
-                    e.path()
-                        .extension()
-                        .map(|ext| ext == "jsonl")
-                        .unwrap_or(false)
-                })
-                .map(|e| {
-                    let path = e.path();
-                    let session_id = path
-                        .file_stem()
-                        .unwrap_or_default()
-                        .to_string_lossy()
-                        .to_string();
-                    (session_id, path, String::new())
-                })
-                .collect(),
-            Err(_) => vec![],
-        }
-    };
-
-    let mut state = STATE.lock().unwrap();
-    let mut results: Vec<ClaudeSession> = entries
-        .into_iter()
-        .map(|(session_id, full_path, summary)| {
-            check_and_notify(&mut state, &session_id, &full_path);
-            let status = get_session_status(&full_path).to_string();
-            let (notified, _) = state
-                .notification
-                .get(&session_id)
-                .copied()
-                .unwrap_or((false, None));
-
-            ClaudeSession {
-                session_id,
-                summary,
-                status,
-                notified,
-            }
-        })
-        .collect();
-
-    // Sort by most recent first (we can't easily sort by modified time here,
-    // but the index entries are already in a reasonable order)
-    results.reverse();
-    results
-}
-
-/// Get the most recent active Claude session for a project
-pub fn get_active_session(project_path: &str) -> Option<ClaudeSession> {
-    let sessions = get_sessions_for_project(project_path);
-    sessions.into_iter().next()
-}
-
-/// Mark a session as viewed (clears notification)
-pub fn mark_session_viewed(session_id: &str) {
-    let mut state = STATE.lock().unwrap();
-    state
-        .notification
-        .insert(session_id.to_string(), (false, Some(SystemTime::now())));
-}
-
 /// Check if a session should trigger a notification
 fn check_and_notify(state: &mut ClaudeState, session_id: &str, full_path: &Path) {
     let status = get_session_status(full_path).to_string();
@@ -236,10 +110,26 @@ fn check_and_notify(state: &mut ClaudeState, session_id: &str, full_path: &Path)
         viewed_at = None;
     }
 
+    if status == "thinking" && prev_status.as_deref() != Some("thinking") {
+        state
+            .turn_started
+            .insert(session_id.to_string(), SystemTime::now());
+    }
+
     // Detect thinking → done transition
     if prev_status.as_deref() == Some("thinking") && status == "done" {
         log::info!("Claude session {} completed", session_id);
         notified = true;
+        let duration_secs = state
+            .turn_started
+            .remove(session_id)
+            .and_then(|t| t.elapsed().ok())
+            .map(|d| d.as_secs());
+        let project = record_turn(session_id, full_path, duration_secs);
+        super::notifications::play_sound(
+            super::notifications::SoundEvent::TurnFinished,
+            Some(project),
+        );
     }
 
     // Also notify if done and hasn't been notified yet (and not viewed)
@@ -248,6 +138,20 @@ fn check_and_notify(state: &mut ClaudeState, session_id: &str, full_path: &Path)
         notified = true;
     }
 
+    let previously_notified = state
+        .notification
+        .get(session_id)
+        .map(|(n, _)| *n)
+        .unwrap_or(false);
+    if prev_status.as_deref() != Some(status.as_str()) || previously_notified != notified {
+        super::events::emit(super::events::BackendEvent::ClaudeSessionUpdated {
+            session_id: session_id.to_string(),
+            status: status.clone(),
+            notified,
+        });
+        super::window_title::set_attention(session_id, notified);
+    }
+
     state
         .notification
         .insert(session_id.to_string(), (notified, viewed_at));
@@ -256,42 +160,210 @@ fn check_and_notify(state: &mut ClaudeState, session_id: &str, full_path: &Path)
         .insert(session_id.to_string(), status);
 }
 
-/// Start watching Claude session files for changes
-pub async fn start_watching(app_handle: tauri::AppHandle) {
-    let projects_dir = claude_projects_dir();
-    if !projects_dir.exists() {
-        log::info!("Claude projects directory not found, skipping session watching");
-        return;
+/// Log a completed turn with its project (the session's cwd) and a short
+/// summary; returns the project
+fn record_turn(session_id: &str, full_path: &Path, duration_secs: Option<u64>) -> String {
+    let msg = read_last_message(full_path).map(|(m, _)| m);
+    let project = msg
+        .as_ref()
+        .and_then(|m| m.get("cwd"))
+        .and_then(|v| v.as_str())
+        .map(String::from)
+        .unwrap_or_else(|| {
+            full_path
+                .parent()
+                .and_then(|p| p.file_name())
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        });
+    let summary: String = msg
+        .as_ref()
+        .and_then(|m| m.pointer("/message/content"))
+        .and_then(|c| c.as_array())
+        .and_then(|parts| {
+            parts
+                .iter()
+                .rev()
+                .find_map(|p| p.get("text").and_then(|t| t.as_str()))
+        })
+        .unwrap_or("")
+        .chars()
+        .take(200)
+        .collect();
+
+    super::activity::record(super::activity::Activity::AgentTurn {
+        ts: super::activity::now_unix(),
+        project: project.clone(),
+        session_id: session_id.to_string(),
+        duration_secs,
+        summary,
+    });
+    project
+}
+
+impl ClaudeTracker {
+    pub fn new(projects_dir: PathBuf) -> Self {
+        Self {
+            projects_dir,
+            state: Mutex::new(ClaudeState::default()),
+        }
     }
 
-    log::info!("Watching Claude sessions at: {:?}", projects_dir);
+    /// Get all Claude sessions for a project path
+    pub fn sessions_for_project(&self, project_path: &str) -> Vec<ClaudeSession> {
+        let project_slug = project_path.replace('/', "-");
+        let project_dir = self.projects_dir.join(&project_slug);
 
-    // Use notify crate for file watching
-    use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
-    use std::sync::mpsc;
+        if !project_dir.exists() {
+            return vec![];
+        }
 
-    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+        // Try sessions-index.json first
+        let index_path = project_dir.join("sessions-index.json");
+
+        #[derive(Deserialize)]
+        struct IndexEntry {
+            #[serde(rename = "sessionId")]
+            session_id: String,
+            #[serde(rename = "fullPath")]
+            full_path: String,
+            summary: Option<String>,
+            #[serde(rename = "projectPath")]
+            project_path: Option<String>,
+        }
 
-    let mut watcher = match RecommendedWatcher::new(tx, Config::default()) {
-        Ok(w) => w,
-        Err(e) => {
-            log::warn!("Failed to create file watcher: {}", e);
-            return;
+        #[derive(Deserialize)]
+        struct SessionsIndex {
+            entries: Vec<IndexEntry>,
         }
-    };
 
-    if let Err(e) = watcher.watch(&projects_dir, RecursiveMode::Recursive) {
-        log::warn!("Failed to watch Claude projects dir: {}", e);
-        return;
+        let entries: Vec<(String, PathBuf, String)> = if index_path.exists() {
+            match fs::read_to_string(&index_path).ok().and_then(|s| {
+                serde_json::from_str::<SessionsIndex>(&s).ok()
+            }) {
+                Some(index) => index
+                    .entries
+                    .into_iter()
+                    .filter(|e| {
+                        e.project_path
+                            .as_deref()
+                            .map(|p| p == project_path)
+                            .unwrap_or(true)
+                    })
+                    .map(|e| {
+                        (
+                            e.session_id,
+                            PathBuf::from(e.full_path),
+                            e.summary.unwrap_or_default(),
+                        )
+                    })
+                    .collect(),
+                None => vec![],
+            }
+        } else {
+            // Fallback: scan .jsonl files directly
+            match fs::read_dir(&project_dir) {
+                Ok(entries) => entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| {
+                        e.path()
+                            .extension()
+                            .map(|ext| ext == "jsonl")
+                            .unwrap_or(false)
+                    })
+                    .map(|e| {
+                        let path = e.path();
+                        let session_id = path
+                            .file_stem()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .to_string();
+                        (session_id, path, String::new())
+                    })
+                    .collect(),
+                Err(_) => vec![],
+            }
+        };
+
+        let mut state = self.state.lock().unwrap();
+        let mut results: Vec<ClaudeSession> = entries
+            .into_iter()
+            .map(|(session_id, full_path, summary)| {
+                check_and_notify(&mut state, &session_id, &full_path);
+                let status = get_session_status(&full_path).to_string();
+                let (notified, _) = state
+                    .notification
+                    .get(&session_id)
+                    .copied()
+                    .unwrap_or((false, None));
+
+                ClaudeSession {
+                    session_id,
+                    summary,
+                    status,
+                    notified,
+                }
+            })
+            .collect();
+
+        // Sort by most recent first (we can't easily sort by modified time here,
+        // but the index entries are already in a reasonable order)
+        results.reverse();
+        results
     }
 
-    // Process file change events
-    // watcher must be moved into the closure to keep it alive
-    tokio::task::spawn_blocking(move || {
-        let _watcher = watcher; // prevent drop — keeps file watching active
-        let _app_handle = app_handle;
-        for result in rx {
-            if let Ok(event) = result {
+    /// Get the most recent active Claude session for a project
+    pub fn active_session(&self, project_path: &str) -> Option<ClaudeSession> {
+        let sessions = self.sessions_for_project(project_path);
+        sessions.into_iter().next()
+    }
+
+    /// Mark a session as viewed (clears notification)
+    pub fn mark_viewed(&self, session_id: &str) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .notification
+            .insert(session_id.to_string(), (false, Some(SystemTime::now())));
+        super::window_title::set_attention(session_id, false);
+    }
+
+    /// Start watching Claude session files for changes
+    pub async fn start_watching(self: Arc<Self>, app_handle: tauri::AppHandle) {
+        let projects_dir = self.projects_dir.clone();
+        if !projects_dir.exists() {
+            log::info!("Claude projects directory not found, skipping session watching");
+            return;
+        }
+
+        log::info!("Watching Claude sessions at: {:?}", projects_dir);
+
+        // Use notify crate for file watching
+        use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+        use std::sync::mpsc;
+
+        let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+
+        let mut watcher = match RecommendedWatcher::new(tx, Config::default()) {
+            Ok(w) => w,
+            Err(e) => {
+                log::warn!("Failed to create file watcher: {}", e);
+                return;
+            }
+        };
+
+        if let Err(e) = watcher.watch(&projects_dir, RecursiveMode::Recursive) {
+            log::warn!("Failed to watch Claude projects dir: {}", e);
+            return;
+        }
+
+        // Process file change events
+        // watcher must be moved into the closure to keep it alive
+        tokio::task::spawn_blocking(move || {
+            let _watcher = watcher; // prevent drop — keeps file watching active
+            let _task = super::diagnostics::track("claude-watcher");
+            let _app_handle = app_handle;
+            for event in rx.into_iter().flatten() {
                 for path in &event.paths {
                     if let Some(ext) = path.extension() {
                         if ext == "jsonl" {
@@ -300,12 +372,12 @@ pub async fn start_watching(app_handle: tauri::AppHandle) {
                                 .unwrap_or_default()
                                 .to_string_lossy()
                                 .to_string();
-                            let mut state = STATE.lock().unwrap();
+                            let mut state = self.state.lock().unwrap();
                             check_and_notify(&mut state, &session_id, path);
                         }
                     }
                 }
             }
-        }
-    });
+        });
+    }
 }
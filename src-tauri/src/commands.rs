@@ -1,28 +1,107 @@
+use crate::activity;
+use crate::capabilities;
 use crate::claude_sessions;
-use crate::pty_manager::{self, PtyMessage};
+use crate::clipboard;
+use crate::diagnostics;
+use crate::events;
+use crate::export;
+use crate::features;
+use crate::files;
+use crate::focus;
+use crate::follow;
+use crate::fonts;
+use crate::groups;
+use crate::hooks;
+use crate::input_lock;
+use crate::layouts;
+use crate::links;
+use crate::memory;
+use crate::multiplexer;
+use crate::notifications;
+use crate::pane_activity;
+use crate::pane_env;
+use crate::pane_log;
+use crate::pane_meta;
+use crate::pairs;
+use crate::previews;
+use crate::probes;
+use crate::prompt_queue;
+use crate::pty_manager::{self, ConnectOptions, PtyChannel, ShellOptions};
+use crate::recently_closed;
+use crate::recording;
+use crate::report;
 use crate::resolver;
-use crate::session_order;
+use crate::search;
 use crate::settings;
+use crate::shell_integration;
+use crate::state_check;
+use crate::templates;
 use crate::tmux;
+use crate::tmux_control;
+use crate::tmux_config;
+use crate::visibility;
+use crate::watches;
+use crate::window_title;
+use crate::wire;
+use crate::workspace;
 use crate::AppState;
 use tauri::ipc::Channel;
 use tauri::State;
 
 /// GET /api/sessions — list all sessions with dimensions and Claude metadata
+///
+/// Sessions from every registered server in `tmux.servers` (and WezTerm, with
+/// `multiplexer.wezterm`) are aggregated and tagged with their `server`.
+/// `group_by` is one of "none" (default), "prefix" or "auto"; see
+/// `groups::infer`. Panes carry `lastOutput` and `outputRate` once the
+/// activity sampler has seen them. `encoding` "msgpack" returns MessagePack
+/// bytes instead of JSON; see `wire`.
 #[tauri::command]
-pub async fn sessions_list(state: State<'_, AppState>) -> Result<Vec<tmux::TmuxSession>, String> {
-    let _ = state;
-    let mut sessions = tmux::list_sessions().await;
+pub async fn sessions_list(
+    group_by: Option<String>,
+    encoding: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<tauri::ipc::Response, String> {
+    let mode = groups::GroupMode::parse(group_by.as_deref())?;
+    let encoding = wire::Encoding::parse(encoding.as_deref())?;
+    multiplexer::current().require()?;
 
-    // Fetch all dimensions in parallel
-    let dim_futures: Vec<_> = sessions
-        .iter()
-        .map(|s| tmux::get_session_dimensions(&s.name))
-        .collect();
+    let per_server = futures::future::join_all(
+        multiplexer::servers()
+            .into_iter()
+            .map(|server| tmux::with_server(server, list_enriched_sessions(&state.claude))),
+    )
+    .await;
+    let mut sessions: Vec<tmux::TmuxSession> = per_server.into_iter().flatten().collect();
+    // Both read and rewrite their state files, which safe mode leaves alone
+    if !crate::safe_mode() {
+        recently_closed::observe(&sessions);
+        pairs::annotate(&mut sessions);
+    }
+    pane_activity::annotate(&mut sessions);
+
+    if mode != groups::GroupMode::None {
+        let prefixes = state.settings.get().settings.groups.prefixes;
+        for session in sessions.iter_mut() {
+            session.inferred_group = session
+                .path
+                .as_deref()
+                .and_then(|p| groups::infer(p, &prefixes, mode));
+        }
+    }
 
-    let dimensions = futures::future::join_all(dim_futures).await;
-    for (session, dim) in sessions.iter_mut().zip(dimensions) {
-        session.dimensions = dim;
+    wire::respond(&sessions, encoding)
+}
+
+/// Sessions of the current server with dimensions and Claude metadata
+async fn list_enriched_sessions(
+    claude: &claude_sessions::ClaudeTracker,
+) -> Vec<tmux::TmuxSession> {
+    // Dimensions come from the same list-panes call as the panes
+    let mut sessions = multiplexer::current().list_sessions().await;
+    // Safe mode doesn't read Claude's session files; demo panes carry their own
+    if crate::safe_mode() || features::enabled(features::Feature::DemoData) {
+        return sessions;
     }
 
     // Enrich panes with Claude session info in parallel
@@ -34,21 +113,20 @@ pub async fn sessions_list(state: State<'_, AppState>) -> Result<Vec<tmux::TmuxS
             for (pi, pane) in window.panes.iter().enumerate() {
                 if pane.process == "claude" {
                     let target = pane.target.clone();
-                    pane_futures.push(async move {
+                    pane_futures.push(probes::limited(async move {
                         let cwd = tmux::get_pane_cwd(&target).await;
                         if let Some(cwd) = cwd {
-                            let mut claude_session = claude_sessions::get_active_session(&cwd);
-                            if claude_session.is_some() {
+                            let mut claude_session = claude.active_session(&cwd);
+                            if let Some(cs) = claude_session.as_mut() {
                                 if tmux::is_pane_processing(&target).await {
-                                    claude_session.as_mut().unwrap().status =
-                                        "thinking".to_string();
+                                    cs.status = "thinking".to_string();
                                 }
                             }
                             claude_session
                         } else {
                             None
                         }
-                    });
+                    }));
                     pane_indices.push((si, wi, pi));
                 }
             }
@@ -62,94 +140,1172 @@ pub async fn sessions_list(state: State<'_, AppState>) -> Result<Vec<tmux::TmuxS
         }
     }
 
-    Ok(sessions)
+    sessions
 }
 
-/// POST /api/sessions — create a new session
+/// POST /api/sessions — create a new session, returning the name of the session to open
+///
+/// With `reuse`, an existing session rooted in the same git repository as `cwd`
+/// is returned instead of creating a duplicate. A newly created session runs
+/// `command` in its first window and gets `windows` appended (same shape as
+/// template windows, cwds relative to `cwd`).
 #[tauri::command]
-pub async fn sessions_create(name: String, cwd: String) -> Result<(), String> {
-    tmux::create_session(&name, &cwd).await?;
-    resolver::record_selection(&cwd);
-    Ok(())
+pub async fn sessions_create(
+    name: String,
+    cwd: String,
+    reuse: Option<bool>,
+    command: Option<String>,
+    windows: Option<Vec<templates::TemplateWindow>>,
+    server: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let resolver = state.resolver.clone();
+    tmux::with_server(server, async move {
+        // Inside the scope, so the server picks the backend
+        let multiplexer = multiplexer::current();
+        if reuse.unwrap_or(false) {
+            let root = resolver::project_root(&cwd);
+            let existing = multiplexer.list_sessions().await.into_iter().find(|s| {
+                s.path
+                    .as_deref()
+                    .map(|p| resolver::project_root(p) == root)
+                    .unwrap_or(false)
+            });
+            if let Some(session) = existing {
+                resolver.record_selection(&cwd);
+                return Ok(session.name);
+            }
+        }
+
+        // An existing session is left as it is rather than given the command and windows
+        let command = command.as_deref().filter(|c| !c.is_empty());
+        if multiplexer.create_session(&name, &cwd, command).await? {
+            let project = settings::expand_tilde(&cwd);
+            for window in windows.iter().flatten() {
+                multiplexer.add_window(&name, window, &project).await?;
+            }
+        }
+        resolver.record_selection(&cwd);
+        emit_sessions_changed("created", std::slice::from_ref(&name));
+        Ok(name)
+    })
+    .await
+}
+
+/// POST /api/sessions/:group/grouped — create a session sharing `group`'s windows
+///
+/// Lets two app windows attach to the same windows at different sizes.
+/// `name` defaults to the first free `<group>-<n>`.
+#[tauri::command]
+pub async fn sessions_create_grouped(
+    group: String,
+    name: Option<String>,
+    server: Option<String>,
+) -> Result<String, String> {
+    tmux::with_server(server, async move {
+        let name = match name {
+            Some(name) => name,
+            None => {
+                let existing: Vec<String> = tmux::list_sessions()
+                    .await
+                    .into_iter()
+                    .map(|s| s.name)
+                    .collect();
+                (2..)
+                    .map(|n| format!("{}-{}", group, n))
+                    .find(|candidate| !existing.contains(candidate))
+                    .expect("unbounded range")
+            }
+        };
+        tmux::create_grouped_session(&group, &name).await?;
+        emit_sessions_changed("created", std::slice::from_ref(&name));
+        Ok(name)
+    })
+    .await
 }
 
 /// DELETE /api/sessions/:name
 #[tauri::command]
-pub async fn sessions_delete(name: String) -> Result<(), String> {
-    tmux::kill_session(&name).await
+pub async fn sessions_delete(name: String, server: Option<String>) -> Result<(), String> {
+    tmux::with_server(server, async move {
+        multiplexer::current().kill_session(&name).await?;
+        emit_sessions_changed("deleted", std::slice::from_ref(&name));
+        Ok(())
+    })
+    .await
+}
+
+fn emit_sessions_changed(change: &str, names: &[String]) {
+    events::emit(events::BackendEvent::SessionsChanged {
+        change: change.to_string(),
+        names: names.to_vec(),
+    });
+}
+
+/// GET /api/templates — session template names from the config dir's templates/
+#[tauri::command]
+pub async fn templates_list() -> Result<Vec<String>, String> {
+    Ok(templates::list())
+}
+
+/// POST /api/sessions/from-template — build a session's windows, splits and commands from a template
+///
+/// `name` defaults to the project directory's basename.
+#[tauri::command]
+pub async fn sessions_create_from_template(
+    template: String,
+    project_path: String,
+    name: Option<String>,
+    server: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let session_template = templates::load(&template)?;
+    let name = name.unwrap_or_else(|| templates::session_name_for(&project_path));
+    tmux::with_server(server, async move {
+        templates::create_session(&session_template, &name, &project_path).await?;
+        state.resolver.record_selection(&project_path);
+        emit_sessions_changed("created", std::slice::from_ref(&name));
+        Ok(name)
+    })
+    .await
+}
+
+/// GET /api/sessions/recently-closed — sessions that disappeared, newest first
+#[tauri::command]
+pub async fn sessions_recently_closed() -> Result<Vec<recently_closed::ClosedSession>, String> {
+    Ok(recently_closed::list())
+}
+
+/// POST /api/sessions/recently-closed/:name/reopen — recreate with its cwd and windows,
+/// or with the windows of `template` when given
+#[tauri::command]
+pub async fn sessions_reopen(
+    name: String,
+    template: Option<String>,
+    server: Option<String>,
+) -> Result<String, String> {
+    let template = template.map(|t| templates::load(&t)).transpose()?;
+    tmux::with_server(server, async move {
+        let name = recently_closed::reopen(&name, template.as_ref()).await?;
+        emit_sessions_changed("created", std::slice::from_ref(&name));
+        Ok(name)
+    })
+    .await
+}
+
+/// POST /api/sessions/:name/windows/move — reorder a window and remember the order
+#[tauri::command]
+pub async fn windows_move(
+    session: String,
+    from_index: u32,
+    to_index: u32,
+    server: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    tmux::with_server(server, async move {
+        tmux::move_window(&session, from_index, to_index).await?;
+        let names = tmux::list_windows(&session)
+            .await?
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect();
+        state.session_order.save_windows(&session, names);
+        Ok(())
+    })
+    .await
+}
+
+/// GET /api/layouts — saved layout library
+#[tauri::command]
+pub fn layouts_list() -> std::collections::BTreeMap<String, String> {
+    layouts::list()
+}
+
+/// POST /api/layouts/:name — save the layout of `target`'s window
+#[tauri::command]
+pub async fn layouts_save(
+    target: String,
+    name: String,
+    server: Option<String>,
+) -> Result<String, String> {
+    tmux::with_server(server, layouts::save(&target, &name)).await
+}
+
+/// POST /api/layouts/:name/apply
+#[tauri::command]
+pub async fn layouts_apply(
+    target: String,
+    name: String,
+    server: Option<String>,
+) -> Result<(), String> {
+    tmux::with_server(server, layouts::apply(&target, &name)).await
+}
+
+/// DELETE /api/layouts/:name
+#[tauri::command]
+pub fn layouts_delete(name: String) -> Result<(), String> {
+    layouts::delete(&name)
+}
+
+/// DELETE /api/sessions/:name/windows/:index
+#[tauri::command]
+pub async fn windows_delete(
+    session: String,
+    index: u32,
+    server: Option<String>,
+) -> Result<(), String> {
+    tmux::with_server(server, tmux::kill_window(&session, index)).await
 }
 
 /// DELETE /api/panes/:target
 #[tauri::command]
-pub async fn panes_delete(target: String) -> Result<(), String> {
-    tmux::kill_pane(&target).await
+pub async fn panes_delete(target: String, server: Option<String>) -> Result<(), String> {
+    tmux::with_server(server, tmux::kill_pane(&target)).await
+}
+
+/// PUT /api/windows/:target/layout
+#[tauri::command]
+pub async fn windows_set_layout(
+    target: String,
+    layout: String,
+    server: Option<String>,
+) -> Result<(), String> {
+    tmux::with_server(server, tmux::select_layout(&target, &layout)).await
+}
+
+/// POST /api/panes/:target/break — move a pane into its own window
+#[tauri::command]
+pub async fn panes_break(target: String, server: Option<String>) -> Result<(), String> {
+    tmux::with_server(server, tmux::break_pane(&target)).await
+}
+
+/// POST /api/panes/:source/join — move a pane into the window containing `target`
+#[tauri::command]
+pub async fn panes_join(
+    source: String,
+    target: String,
+    horizontal: Option<bool>,
+    server: Option<String>,
+) -> Result<(), String> {
+    let horizontal = horizontal.unwrap_or(false);
+    tmux::with_server(server, tmux::join_pane(&source, &target, horizontal)).await
+}
+
+/// POST /api/panes/:source/swap
+#[tauri::command]
+pub async fn panes_swap(
+    source: String,
+    target: String,
+    server: Option<String>,
+) -> Result<(), String> {
+    tmux::with_server(server, tmux::swap_pane(&source, &target)).await
+}
+
+/// POST /api/sessions/:name/identify — flash pane numbers in attached terminals
+#[tauri::command]
+pub async fn panes_identify(
+    session: String,
+    duration_ms: Option<u32>,
+    server: Option<String>,
+) -> Result<Vec<tmux::PaneLabel>, String> {
+    let duration_ms = duration_ms.unwrap_or(5000);
+    tmux::with_server(server, tmux::identify_panes(&session, duration_ms)).await
+}
+
+/// GET /api/clients — terminals attached to `session` (or to any session)
+///
+/// Lets the UI show who is constraining a session's size; MuxTunnel's own
+/// attaches are flagged `muxtunnel`.
+#[tauri::command]
+pub async fn clients_list(
+    session: Option<String>,
+    server: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<tmux::TmuxClient>, String> {
+    let ours: Vec<u32> = state
+        .pty_sessions
+        .lock()
+        .await
+        .values()
+        .filter_map(|h| h.client_pid())
+        .chain(tmux_control::client_pids())
+        .collect();
+    let mut clients =
+        tmux::with_server(server, tmux::list_clients(session.as_deref())).await?;
+    for client in &mut clients {
+        client.muxtunnel = client.pid.map(|p| ours.contains(&p)).unwrap_or(false);
+    }
+    Ok(clients)
+}
+
+/// POST /api/sessions/:session/popup — run `command` in a tmux popup over the session
+///
+/// Shown on one of MuxTunnel's own clients of the session when there is one,
+/// else another attached client; closes when the command exits. Needs tmux 3.2+.
+#[tauri::command]
+pub async fn popups_open(
+    session: String,
+    command: String,
+    width: Option<String>,
+    height: Option<String>,
+    cwd: Option<String>,
+    server: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let clients = clients_list(Some(session.clone()), server.clone(), state).await?;
+    let client = clients
+        .iter()
+        .filter(|c| !c.read_only)
+        .max_by_key(|c| c.muxtunnel)
+        .ok_or_else(|| format!("No client attached to {}", session))?;
+    let cwd = cwd.map(|c| settings::expand_tilde(&c).to_string_lossy().to_string());
+    tmux::with_server(
+        server,
+        tmux::display_popup(
+            &client.name,
+            &session,
+            &command,
+            width.as_deref(),
+            height.as_deref(),
+            cwd.as_deref(),
+        ),
+    )
+    .await
+}
+
+/// DELETE /api/clients/:client — detach another terminal from its session
+#[tauri::command]
+pub async fn clients_detach(client: String, server: Option<String>) -> Result<(), String> {
+    tmux::with_server(server, tmux::detach_client(&client)).await
+}
+
+/// GET /api/panes/:target/capture — page through a pane's scrollback without a PTY
+/// (`encoding` as for `sessions_list`)
+#[tauri::command]
+pub async fn panes_capture(
+    target: String,
+    start_line: i32,
+    end_line: Option<i32>,
+    with_escapes: Option<bool>,
+    encoding: Option<String>,
+    server: Option<String>,
+) -> Result<tauri::ipc::Response, String> {
+    let with_escapes = with_escapes.unwrap_or(false);
+    let encoding = wire::Encoding::parse(encoding.as_deref())?;
+    let capture = tmux::with_server(
+        server,
+        tmux::capture_pane(&target, start_line, end_line, with_escapes),
+    )
+    .await?;
+    wire::respond(&capture, encoding)
+}
+
+/// GET /api/panes/search — grep pane scrollback across the tmux server
+#[tauri::command]
+pub async fn panes_search(
+    query: String,
+    targets: Option<Vec<String>>,
+    regex: Option<bool>,
+    case_sensitive: Option<bool>,
+    server: Option<String>,
+) -> Result<Vec<search::SearchMatch>, String> {
+    tmux::with_server(
+        server,
+        search::search_panes(
+            &query,
+            targets.unwrap_or_default(),
+            regex.unwrap_or(false),
+            case_sensitive.unwrap_or(false),
+        ),
+    )
+    .await
 }
 
 /// POST /api/panes/:target/input
 #[tauri::command]
-pub async fn panes_input(target: String, text: String) -> Result<(), String> {
-    tmux::send_keys_literal(&target, &text).await
+pub async fn panes_input(
+    target: String,
+    text: String,
+    holder: Option<String>,
+    server: Option<String>,
+) -> Result<(), String> {
+    tmux::with_server(server, async {
+        // Keep PTY keystrokes from landing between the text and its Enter
+        let _guard = input_lock::hold(&target, holder.as_deref()).await?;
+        multiplexer::current().send_input(&target, &text).await
+    })
+    .await?;
+    activity::record(activity::Activity::CommandSent {
+        ts: activity::now_unix(),
+        target,
+        text: activity::input_text(&text),
+    });
+    Ok(())
+}
+
+/// POST /api/panes/broadcast — type `text` followed by Enter into several panes at once
+///
+/// Each pane is sent to like `panes_input`; a failing pane doesn't stop the
+/// others and is reported in its result.
+#[tauri::command]
+pub async fn panes_broadcast(
+    targets: Vec<String>,
+    text: String,
+    holder: Option<String>,
+    server: Option<String>,
+) -> Result<Vec<multiplexer::BroadcastResult>, String> {
+    if targets.is_empty() {
+        return Err("No targets".to_string());
+    }
+    let sends = targets.into_iter().map(|target| {
+        let (text, holder, server) = (text.clone(), holder.clone(), server.clone());
+        async move {
+            let error = panes_input(target.clone(), text, holder, server).await.err();
+            multiplexer::BroadcastResult { target, error }
+        }
+    });
+    Ok(futures::future::join_all(sends).await)
+}
+
+/// PUT /api/windows/:target/synchronize — turn the window's `synchronize-panes` on
+/// or off (toggle when `enabled` is omitted), mirroring typed input to all its panes
+#[tauri::command]
+pub async fn windows_synchronize(
+    target: String,
+    enabled: Option<bool>,
+    server: Option<String>,
+) -> Result<bool, String> {
+    tmux::with_server(server, tmux::synchronize_panes(&target, enabled)).await
+}
+
+/// POST /api/panes/:target/keys — send tmux key names or literal text
+///
+/// `keys` are tmux key names (`C-d`, `Up`, `F5`, `Escape`) unless `literal`
+/// is set. Enter is only sent when `append_enter` is true.
+#[tauri::command]
+pub async fn panes_send_keys(
+    target: String,
+    keys: Vec<String>,
+    literal: Option<bool>,
+    append_enter: Option<bool>,
+    holder: Option<String>,
+    server: Option<String>,
+) -> Result<(), String> {
+    tmux::with_server(server, async {
+        let _guard = input_lock::hold(&target, holder.as_deref()).await?;
+        tmux::send_keys(
+            &target,
+            &keys,
+            literal.unwrap_or(false),
+            append_enter.unwrap_or(false),
+        )
+        .await
+    })
+    .await
+}
+
+/// GET /api/buffers — tmux paste buffers, most recent first
+#[tauri::command]
+pub async fn buffers_list(server: Option<String>) -> Result<Vec<tmux::TmuxBuffer>, String> {
+    tmux::with_server(server, tmux::list_buffers()).await
+}
+
+/// POST /api/clipboard — copy text to the system clipboard
+#[tauri::command]
+pub async fn clipboard_write(
+    text: String,
+    redact: Option<bool>,
+    app: tauri::AppHandle,
+) -> Result<clipboard::CopyOutcome, String> {
+    clipboard::write_system(&app, &text, redact)
+}
+
+/// GET /api/clipboard
+#[tauri::command]
+pub async fn clipboard_read(app: tauri::AppHandle) -> Result<String, String> {
+    clipboard::read_system(&app)
+}
+
+/// POST /api/panes/:target/copy — copy the pane's last `lines` lines (default 50)
+#[tauri::command]
+pub async fn panes_copy_output(
+    target: String,
+    lines: Option<u32>,
+    redact: Option<bool>,
+    server: Option<String>,
+    app: tauri::AppHandle,
+) -> Result<clipboard::CopyOutcome, String> {
+    let lines = lines.unwrap_or(50).max(1) as usize;
+    let content = tmux::with_server(
+        server,
+        tmux::capture_pane_range(&target, -(lines as i32), None, false),
+    )
+    .await?;
+    // Skip the blank rows below the cursor
+    let captured: Vec<&str> = content.trim_end().lines().collect();
+    let text = captured[captured.len().saturating_sub(lines)..].join("\n");
+    clipboard::write_system(&app, &text, redact)
+}
+
+/// POST /api/panes/:target/export — save the pane's whole scrollback to `path`
+///
+/// `format` is "text" (escapes stripped) or "html" (colors and attributes as
+/// styled spans); wrapped lines are joined.
+#[tauri::command]
+pub async fn panes_export(
+    target: String,
+    path: String,
+    format: Option<String>,
+    server: Option<String>,
+) -> Result<export::ExportOutcome, String> {
+    let format = format.unwrap_or_else(|| "text".to_string());
+    tmux::with_server(server, export::export(&target, &path, &format)).await
+}
+
+/// GET /api/previews/:session — the active window's panes, cached when pre-warmed
+///
+/// `zoom` (percent, default `previews.zoom`) also sets the level neighbors are warmed at.
+#[tauri::command]
+pub async fn previews_get(
+    session: String,
+    zoom: Option<u32>,
+    server: Option<String>,
+) -> Result<previews::SessionPreview, String> {
+    tmux::with_server(server, previews::get(&session, zoom)).await
+}
+
+/// GET /api/panes/:target/preview — the pane's current screen as plain text,
+/// its last `max_lines` rows (default: all); never attaches a PTY
+#[tauri::command]
+pub async fn panes_preview(
+    target: String,
+    max_lines: Option<u32>,
+    server: Option<String>,
+) -> Result<previews::PaneSnapshot, String> {
+    tmux::with_server(server, previews::pane(&target, max_lines)).await
+}
+
+/// DELETE /api/previews/:target — drop cached previews of the target's session
+#[tauri::command]
+pub async fn previews_invalidate(target: String, server: Option<String>) -> Result<(), String> {
+    tmux::with_server(server, previews::invalidate(&target)).await;
+    Ok(())
+}
+
+/// GET /api/buffers/:name
+#[tauri::command]
+pub async fn buffers_read(name: String, server: Option<String>) -> Result<String, String> {
+    tmux::with_server(server, tmux::show_buffer(&name)).await
+}
+
+/// POST /api/buffers — store `text`, or the system clipboard when omitted
+#[tauri::command]
+pub async fn buffers_set(
+    name: Option<String>,
+    text: Option<String>,
+    server: Option<String>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    let text = match text {
+        Some(text) => text,
+        None => clipboard::read_system(&app)?,
+    };
+    tmux::with_server(server, tmux::set_buffer(name.as_deref(), &text)).await
+}
+
+/// POST /api/panes/:target/paste — paste a tmux buffer into the pane
+#[tauri::command]
+pub async fn panes_paste_buffer(
+    target: String,
+    name: Option<String>,
+    bracketed: Option<bool>,
+    holder: Option<String>,
+    server: Option<String>,
+) -> Result<(), String> {
+    tmux::with_server(server, async {
+        let _guard = input_lock::hold(&target, holder.as_deref()).await?;
+        tmux::paste_buffer(&target, name.as_deref(), bracketed.unwrap_or(true)).await
+    })
+    .await
+}
+
+/// POST /api/sessions/:name/lock — refuse all input to the session
+///
+/// Unlike pane locks this has no holder or expiry: panes_input, keys, pastes
+/// and PTY writes all fail with a `session-locked:` error until unlocked.
+#[tauri::command]
+pub async fn sessions_lock(name: String, server: Option<String>) -> Result<(), String> {
+    tmux::with_server(server, async { input_lock::lock_session(&name) }).await
+}
+
+/// DELETE /api/sessions/:name/lock
+#[tauri::command]
+pub async fn sessions_unlock(name: String, server: Option<String>) -> Result<(), String> {
+    tmux::with_server(server, async { input_lock::unlock_session(&name) }).await
+}
+
+/// GET /api/sessions/locks
+#[tauri::command]
+pub async fn sessions_locks() -> Result<Vec<input_lock::SessionLock>, String> {
+    Ok(input_lock::locked_sessions())
+}
+
+/// POST /api/panes/:target/lock — take or renew the advisory input lock
+///
+/// While held, input to the pane from other holders (including PTYs sent
+/// without the same `holder`) is rejected. Locks expire after `ttl_ms`
+/// (default 30s) unless renewed.
+#[tauri::command]
+pub async fn panes_input_lock(
+    target: String,
+    holder: String,
+    ttl_ms: Option<u64>,
+    server: Option<String>,
+) -> Result<(), String> {
+    let ttl = ttl_ms.map(std::time::Duration::from_millis);
+    tmux::with_server(server, input_lock::acquire(&target, &holder, ttl)).await
+}
+
+/// DELETE /api/panes/:target/lock
+#[tauri::command]
+pub async fn panes_input_unlock(
+    target: String,
+    holder: String,
+    server: Option<String>,
+) -> Result<(), String> {
+    tmux::with_server(server, async { input_lock::release(&target, &holder) }).await;
+    Ok(())
+}
+
+/// GET /api/panes/:target/env — tmux's environment for the session plus the
+/// actual environment of the pane's shell and foreground process
+#[tauri::command]
+pub async fn panes_env(
+    target: String,
+    server: Option<String>,
+) -> Result<pane_env::PaneEnv, String> {
+    tmux::with_server(server, pane_env::inspect(&target)).await
+}
+
+/// GET /api/panes/:target/meta — tags, pin and watches of a pane
+#[tauri::command]
+pub async fn panes_meta_get(
+    target: String,
+    server: Option<String>,
+) -> Result<pane_meta::PaneMeta, String> {
+    tmux::with_server(server, pane_meta::get(&target)).await
+}
+
+/// PUT /api/panes/:target/meta — replace a pane's metadata
+///
+/// Stored in the pane's `@muxtunnel_meta` option (tmux 3.0+) so it survives
+/// restarts and is shared with other MuxTunnels on the same server, and in
+/// the data dir as a fallback.
+#[tauri::command]
+pub async fn panes_meta_set(
+    target: String,
+    meta: pane_meta::PaneMeta,
+    server: Option<String>,
+) -> Result<pane_meta::PaneMeta, String> {
+    tmux::with_server(server, async { pane_meta::set(&target, &meta).await }).await
+}
+
+/// PUT /api/panes/:target/title — label a pane (e.g. "dev server"); stored in tmux
+#[tauri::command]
+pub async fn panes_set_title(
+    target: String,
+    title: String,
+    server: Option<String>,
+) -> Result<(), String> {
+    tmux::with_server(server, tmux::set_pane_title(&target, &title)).await
+}
+
+/// POST /api/panes/:target/respawn — restart the pane's process (e.g. a crashed dev server)
+///
+/// Without `command`, the pane's original command is run again.
+#[tauri::command]
+pub async fn panes_respawn(
+    target: String,
+    command: Option<String>,
+    server: Option<String>,
+) -> Result<(), String> {
+    tmux::with_server(server, tmux::respawn_pane(&target, command.as_deref())).await
+}
+
+/// POST /api/panes/:target/copy-mode — `action` is "enter", "exit" or "scroll"
+///
+/// `scroll` enters copy mode if needed and moves `lines` (negative scrolls up
+/// into history). Pane info reports `inMode`, `alternateOn` and `scrollPosition`.
+#[tauri::command]
+pub async fn panes_copy_mode(
+    target: String,
+    action: String,
+    lines: Option<i32>,
+    server: Option<String>,
+) -> Result<(), String> {
+    let action = match action.as_str() {
+        "enter" => tmux::CopyMode::Enter,
+        "exit" => tmux::CopyMode::Exit,
+        "scroll" => tmux::CopyMode::Scroll(lines.unwrap_or(-1)),
+        other => return Err(format!("Unknown copy-mode action: {}", other)),
+    };
+    tmux::with_server(server, tmux::copy_mode(&target, action)).await
+}
+
+/// GET /api/panes/:target/commands — commands the pane's shell ran, with
+/// durations and exit statuses
+///
+/// Needs a shell emitting OSC 133 marks (most shell-integration scripts do).
+/// On tmux the pane is piped from the first call, so earlier commands of
+/// unattached panes aren't known.
+#[tauri::command]
+pub async fn panes_command_history(
+    target: String,
+    server: Option<String>,
+) -> Result<Vec<shell_integration::CommandRecord>, String> {
+    tmux::with_server(server, shell_integration::history(&target)).await
+}
+
+/// POST /api/panes/:target/log — append the pane's output to a file
+///
+/// `path` defaults to `<session>-<pane>.log` in `logs.dir`. The file rotates
+/// at `logs.maxBytes` and logging continues while the pane is detached,
+/// until `panes_log_stop`, the pane exits or MuxTunnel quits.
+#[tauri::command]
+pub async fn panes_log_start(
+    target: String,
+    path: Option<String>,
+    server: Option<String>,
+) -> Result<pane_log::LogStarted, String> {
+    tmux::with_server(server, pane_log::start(&target, path.as_deref())).await
+}
+
+/// DELETE /api/panes/:target/log
+#[tauri::command]
+pub async fn panes_log_stop(target: String, server: Option<String>) -> Result<(), String> {
+    tmux::with_server(server, pane_log::stop(&target)).await
+}
+
+/// POST /api/panes/:target/watches — extract a value from the pane's output
+///
+/// Every line the pane prints (including `\r`-redrawn progress lines) is
+/// matched against `regex`; when capture group `extract_group` (default 1)
+/// changes it's sent as a `watch-value` event. The returned value comes from
+/// the current screen.
+#[tauri::command]
+pub async fn panes_watch_expr(
+    target: String,
+    regex: String,
+    extract_group: Option<usize>,
+    server: Option<String>,
+) -> Result<watches::WatchInfo, String> {
+    let group = extract_group.unwrap_or(1);
+    tmux::with_server(server, watches::watch(&target, &regex, group)).await
+}
+
+/// DELETE /api/watches/:id
+#[tauri::command]
+pub async fn panes_unwatch(id: u64) -> Result<(), String> {
+    watches::unwatch(id)
+}
+
+/// GET /api/watches — active watches with their latest values, optionally of one pane
+#[tauri::command]
+pub async fn panes_watches(target: Option<String>) -> Result<Vec<watches::WatchInfo>, String> {
+    Ok(watches::list(target.as_deref()))
+}
+
+/// POST /api/pairs — pair two panes (e.g. an agent and its dev server) so
+/// the UI shows them side by side. Each pane's side is reported as `pair` in
+/// `sessions_list`; pairs are dropped when either pane exits.
+#[tauri::command]
+pub async fn pairs_create(
+    primary_target: String,
+    secondary_target: String,
+    server: Option<String>,
+) -> Result<pairs::Pair, String> {
+    tmux::with_server(server, pairs::create(&primary_target, &secondary_target)).await
+}
+
+/// DELETE /api/pairs/:id
+#[tauri::command]
+pub async fn pairs_delete(id: String) -> Result<(), String> {
+    pairs::delete(&id)
+}
+
+/// GET /api/pairs
+#[tauri::command]
+pub async fn pairs_list() -> Result<Vec<pairs::Pair>, String> {
+    Ok(pairs::list())
+}
+
+/// GET /api/panes/:target/links — URLs, file paths and issue references in the pane
+#[tauri::command]
+pub async fn panes_links(target: String, server: Option<String>) -> Result<Vec<links::Link>, String> {
+    tmux::with_server(server, links::pane_links(&target)).await
 }
 
 /// POST /api/panes/:target/interrupt
 #[tauri::command]
-pub async fn panes_interrupt(target: String) -> Result<(), String> {
-    tmux::send_interrupt(&target).await
+pub async fn panes_interrupt(target: String, server: Option<String>) -> Result<(), String> {
+    tmux::with_server(server, tmux::send_interrupt(&target)).await
 }
 
 /// GET /api/projects
 #[tauri::command]
-pub async fn projects_list(query: Option<String>) -> Result<Vec<resolver::ProjectResult>, String> {
+pub async fn projects_list(
+    query: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<resolver::ProjectResult>, String> {
     let q = query.unwrap_or_default();
-    Ok(resolver::resolve(&q).await)
+    Ok(state.resolver.resolve(&q).await)
 }
 
 /// GET /api/projects/resolve/:name
 #[tauri::command]
 pub async fn projects_resolve(
     name: String,
+    state: State<'_, AppState>,
 ) -> Result<resolver::ProjectResult, String> {
-    resolver::resolve_one(&name)
+    state
+        .resolver
+        .resolve_one(&name)
         .await
         .ok_or_else(|| "No match".to_string())
 }
 
+/// POST /api/projects/import — register every repo under a directory
+///
+/// With `template`, each newly imported repo also gets a session template of
+/// its own (named after the session it would get), copied from `template`
+/// and left alone when one by that name already exists.
+#[tauri::command]
+pub async fn projects_import_dir(
+    path: String,
+    template: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<resolver::ImportSummary, String> {
+    let template = template.map(|t| templates::load(&t)).transpose()?;
+    let mut summary = state.resolver.import_dir(&path).await?;
+    if let Some(template) = template {
+        for project in &summary.imported {
+            let name = templates::session_name_for(project);
+            if templates::create(&name, &template)? {
+                summary.templates.push(name);
+            }
+        }
+    }
+    Ok(summary)
+}
+
 /// POST /api/claude-sessions/:id/viewed
 #[tauri::command]
-pub fn claude_mark_viewed(id: String) -> Result<(), String> {
-    claude_sessions::mark_session_viewed(&id);
+pub fn claude_mark_viewed(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    state.claude.mark_viewed(&id);
     Ok(())
 }
 
+/// POST /api/claude/queue — type `text` into `target` once its agent finishes the current turn
+#[tauri::command]
+pub async fn claude_queue_prompt(
+    target: String,
+    text: String,
+    server: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<prompt_queue::QueuedPrompt, String> {
+    let claude = state.claude.clone();
+    tmux::with_server(server, async move {
+        if !tmux::has_target(&target).await {
+            return Err(format!("Pane not found: {}", target));
+        }
+        Ok(prompt_queue::enqueue(&target, &text, claude))
+    })
+    .await
+}
+
+/// GET /api/claude/queue — prompts waiting for delivery, optionally for one pane
+#[tauri::command]
+pub async fn claude_queue_list(
+    target: Option<String>,
+    server: Option<String>,
+) -> Result<Vec<prompt_queue::QueuedPrompt>, String> {
+    Ok(tmux::with_server(server, async { prompt_queue::list(target.as_deref()) }).await)
+}
+
+/// DELETE /api/claude/queue/:id
+#[tauri::command]
+pub fn claude_queue_cancel(id: u64) -> Result<(), String> {
+    prompt_queue::cancel(id)
+}
+
 /// GET /api/session-order
 #[tauri::command]
-pub fn session_order_get() -> Vec<String> {
-    session_order::get()
+pub fn session_order_get(state: State<'_, AppState>) -> Vec<String> {
+    state.session_order.get()
 }
 
 /// PUT /api/session-order
 #[tauri::command]
-pub fn session_order_save(order: Vec<String>) -> Result<(), String> {
-    session_order::save(order);
+pub fn session_order_save(order: Vec<String>, state: State<'_, AppState>) -> Result<(), String> {
+    state.session_order.save(order);
     Ok(())
 }
 
 /// GET /api/settings
 #[tauri::command]
-pub fn settings_get() -> settings::SettingsResponse {
-    settings::get_settings()
+pub fn settings_get(state: State<'_, AppState>) -> settings::SettingsResponse {
+    state.settings.get()
 }
 
-/// PTY connect — stream output via Tauri Channel
+/// GET /api/tmux/config-issues — tmux settings that conflict with MuxTunnel
+#[tauri::command]
+pub async fn tmux_config_issues(
+    server: Option<String>,
+) -> Result<Vec<tmux_config::ConfigIssue>, String> {
+    let options = tmux::with_server(server, tmux::global_options()).await?;
+    Ok(tmux_config::check_options(&options))
+}
+
+/// GET /api/safe-mode — whether the app was started with `--safe-mode` / `MUXTUNNEL_SAFE_MODE`
+///
+/// In safe mode only core commands are registered and settings are the defaults.
+#[tauri::command]
+pub fn safe_mode_get() -> bool {
+    crate::safe_mode()
+}
+
+/// GET /api/health — tmux version, server status, missing tools and config issues
+#[tauri::command]
+pub async fn health_check(server: Option<String>) -> Result<tmux_config::HealthReport, String> {
+    Ok(tmux::with_server(server, tmux_config::health_check()).await)
+}
+
+/// GET /api/diagnostics — MuxTunnel's own resource usage, for spotting leaks
+#[tauri::command]
+pub async fn diagnostics(state: State<'_, AppState>) -> Result<diagnostics::Diagnostics, String> {
+    Ok(diagnostics::collect(&state.pty_sessions).await)
+}
+
+/// POST /api/state/check — validate state files, migrate old schema versions
+/// and quarantine corrupt files as `.corrupt` (also run at startup)
+#[tauri::command]
+pub async fn state_check() -> Result<state_check::StateReport, String> {
+    Ok(state_check::check())
+}
+
+/// POST /api/sounds/:id/preview — play an event's sound ("turn-finished",
+/// "trigger-matched"), as configured for `project` if given
+#[tauri::command]
+pub async fn sounds_preview(id: String, project: Option<String>) -> Result<(), String> {
+    notifications::preview_sound(&id, project.as_deref()).await
+}
+
+/// POST /api/notify — raise a desktop notification through the backend dispatcher
+///
+/// Honors `notifications.enabled`, `notifications.quietHours` and
+/// `notifications.dedupSecs`; `kind` defaults to "info".
+#[tauri::command]
+pub async fn notify(
+    title: String,
+    body: String,
+    kind: Option<String>,
+    action: Option<String>,
+) -> Result<notifications::NotifyOutcome, String> {
+    let kind = kind.unwrap_or_else(|| "info".to_string());
+    Ok(notifications::notify(&title, &body, &kind, action).await)
+}
+
+/// POST /api/workspace/snapshots — save every session's windows, panes and commands
+#[tauri::command]
+pub async fn workspace_save(name: Option<String>, server: Option<String>) -> Result<String, String> {
+    tmux::with_server(server, workspace::save(name.as_deref())).await
+}
+
+/// GET /api/workspace/snapshots — saved snapshot names, newest first
+#[tauri::command]
+pub async fn workspace_list() -> Result<Vec<String>, String> {
+    Ok(workspace::list())
+}
+
+/// POST /api/workspace/snapshots/:name/restore — recreate sessions (newest snapshot by default)
+///
+/// `run_commands` also types the commands that were running back into their
+/// panes; off by default, since a snapshot can hold anything that was running.
+#[tauri::command]
+pub async fn workspace_restore(
+    name: Option<String>,
+    run_commands: Option<bool>,
+    server: Option<String>,
+) -> Result<workspace::RestoreSummary, String> {
+    tmux::with_server(server, async move {
+        let summary = workspace::restore(name.as_deref(), run_commands.unwrap_or(false)).await?;
+        emit_sessions_changed("created", &summary.restored);
+        Ok(summary)
+    })
+    .await
+}
+
+/// GET /api/files?path= — preview a file inside some session's path (default 256 KiB)
+#[tauri::command]
+pub async fn files_read(path: String, max_bytes: Option<u64>) -> Result<files::FileContent, String> {
+    files::read(&path, max_bytes).await
+}
+
+/// GET /api/features — experimental flags, as in effect and as configured
+#[tauri::command]
+pub fn features_get() -> Vec<features::FeatureStatus> {
+    features::get()
+}
+
+/// GET /api/capabilities — which external tools were found and what is degraded
+#[tauri::command]
+pub async fn capabilities_get(refresh: Option<bool>) -> Result<capabilities::Capabilities, String> {
+    if refresh.unwrap_or(false) {
+        Ok(capabilities::refresh())
+    } else {
+        Ok(capabilities::get())
+    }
+}
+
+/// Start emitting `follow-pane-changed` whenever the user's own terminal switches panes
+#[tauri::command]
+pub async fn follow_start(state: State<'_, AppState>) -> Result<(), String> {
+    follow::start(state.pty_sessions.clone()).await
+}
+
+/// POST /api/focus/external — switch the user's own tmux client to `target`
+/// (session, window or pane) and bring their terminal app forward
+///
+/// The client is the most recently active one not spawned by MuxTunnel;
+/// `app` defaults to `terminal.externalApp`. Returns the client's name.
+#[tauri::command]
+pub async fn focus_external_client(
+    target: String,
+    app: Option<String>,
+    server: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    tmux::with_server(
+        server,
+        follow::focus_external(&state.pty_sessions, &target, app),
+    )
+    .await
+}
+
+/// Stop follow mode
+#[tauri::command]
+pub async fn follow_stop() -> Result<(), String> {
+    follow::stop().await;
+    Ok(())
+}
+
+/// Shell PTY — run `options.command` (default: the login shell) in a PTY
+/// without tmux, streamed like `pty_connect`. Address it afterwards as target
+/// `shell:<name>`; connecting the same name again resumes it.
+#[tauri::command]
+pub async fn pty_shell(
+    name: String,
+    cols: u16,
+    rows: u16,
+    options: Option<ShellOptions>,
+    on_data: Channel,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let options = options.unwrap_or_default();
+    let channel = PtyChannel::new(on_data).negotiate(options.connect.compression.as_deref())?;
+    let sessions = state.pty_sessions.clone();
+    pty_manager::connect_direct(
+        &name,
+        options.command,
+        options.cwd,
+        cols,
+        rows,
+        channel,
+        sessions,
+    )
+    .await
+}
+
+/// PTY connect — stream output via Tauri Channel; `options.readOnly` attaches
+/// with `tmux attach -r`, `options.compression: "lz4"` compresses large output
+/// (`data-lz4` messages)
 #[tauri::command]
 pub async fn pty_connect(
     target: String,
     cols: u16,
     rows: u16,
-    on_data: Channel<PtyMessage>,
+    options: Option<ConnectOptions>,
+    on_data: Channel,
+    server: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    tmux::with_server(server.clone(), async { multiplexer::current().require() }).await?;
+    let options = options.unwrap_or_default();
+    let channel = PtyChannel::new(on_data).negotiate(options.compression.as_deref())?;
+    let sessions = state.pty_sessions.clone();
+    let read_only = options.read_only;
+    // Session hooks fire on the first attach, not on reconnects or extra panes
+    let session = tmux::with_server(server.clone(), tmux::session_of(&target)).await;
+    let prefix = pty_manager::session_key(server.as_deref(), &session);
+    let first_attach = {
+        let open = sessions.lock().await;
+        let attached = open.keys().any(|key| {
+            key.strip_prefix(prefix.as_str())
+                .map(|rest| rest.is_empty() || rest.starts_with(':'))
+                .unwrap_or(false)
+        });
+        hooks::claim_first_attach(&prefix, attached)
+    };
+    tmux::with_server(server, async move {
+        let cwd = if first_attach.is_some() {
+            let cwd = tmux::get_pane_cwd(&target).await;
+            let (scope, phase) = (hooks::Scope::Session, hooks::Phase::Pre);
+            hooks::run_within(scope, &session, phase, cwd.clone(), hooks::ATTACH_WAIT).await;
+            cwd
+        } else {
+            None
+        };
+        pty_manager::connect(target, cols, rows, read_only, channel, sessions).await?;
+        if first_attach.is_some() {
+            hooks::spawn(hooks::Scope::Session, &session, hooks::Phase::Post, cwd);
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// POST /api/pty/:target/focus — the terminal view showing `target` gained
+/// focus (omit `target` when no terminal view has it)
+///
+/// Sends focus-out to the previously focused PTY and focus-in to this one,
+/// for programs that enabled focus reporting (`terminal.focusEvents`).
+#[tauri::command]
+pub async fn pty_focus(
+    target: Option<String>,
+    server: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    pty_manager::connect(target, cols, rows, on_data, state.pty_sessions.clone()).await
+    let key = target.map(|t| pty_manager::session_key(server.as_deref(), &t));
+    pty_manager::set_focus(&state.pty_sessions, key).await;
+    Ok(())
+}
+
+/// POST /api/visibility — the panes on screen and whether the app is focused
+///
+/// Pane activity is sampled every `advanced.visiblePollMs` for servers with
+/// panes on screen, `hiddenPollMs` otherwise, and `unfocusedPollMs` while the
+/// app is in the background; previews are warmed at the slower two.
+#[tauri::command]
+pub async fn visibility_report(
+    visible_targets: Vec<String>,
+    app_focused: bool,
+    server: Option<String>,
+) -> Result<(), String> {
+    tmux::with_server(server, async {
+        visibility::report(&visible_targets, app_focused)
+    })
+    .await;
+    Ok(())
 }
 
 /// Send input/resize to an active PTY session
@@ -157,11 +1313,19 @@ pub async fn pty_connect(
 pub async fn pty_send(
     target: String,
     msg: serde_json::Value,
+    holder: Option<String>,
+    server: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
+    let is_resize = msg.get("type").and_then(|v| v.as_str()) == Some("resize");
+    if !is_resize {
+        let check = input_lock::check(&target, holder.as_deref());
+        tmux::with_server(server.clone(), check).await?;
+    }
+
     let sessions = state.pty_sessions.lock().await;
     let handle = sessions
-        .get(&target)
+        .get(&pty_manager::session_key(server.as_deref(), &target))
         .ok_or_else(|| format!("No PTY session for target: {}", target))?;
 
     if let Some(msg_type) = msg.get("type").and_then(|v| v.as_str()) {
@@ -198,20 +1362,233 @@ pub async fn pty_send(
     Ok(())
 }
 
+/// POST /api/pty/:target/paste — paste `text` as one block rather than
+/// typing it, so multi-line snippets aren't run line by line
+#[tauri::command]
+pub async fn pty_paste(
+    target: String,
+    text: String,
+    holder: Option<String>,
+    server: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let key = pty_manager::session_key(server.as_deref(), &target);
+    let sessions = state.pty_sessions.clone();
+    tmux::with_server(server, async move {
+        let _guard = input_lock::hold(&target, holder.as_deref()).await?;
+        pty_manager::paste(&key, &target, &text, &sessions).await
+    })
+    .await
+}
+
+/// Re-attach a new channel to a PTY whose webview went away (reload or
+/// renderer crash). Output from the gap is replayed first; PTYs nobody
+/// reclaims within 15s of their channel failing are closed.
+#[tauri::command]
+pub async fn pty_reclaim(
+    target: String,
+    on_data: Channel,
+    server: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let key = pty_manager::session_key(server.as_deref(), &target);
+    let sessions = state.pty_sessions.clone();
+    tmux::with_server(server, async move {
+        pty_manager::reclaim(&key, PtyChannel::new(on_data), &sessions).await
+    })
+    .await
+}
+
+/// POST /api/pty/:target/ack — the terminal view rendered `bytes` more of
+/// the PTY's output. Once a view acks, reading pauses while it is more than
+/// 512 KiB behind, so a flood of output can't freeze the UI.
+#[tauri::command]
+pub async fn pty_ack(
+    target: String,
+    bytes: usize,
+    server: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let key = pty_manager::session_key(server.as_deref(), &target);
+    let sessions = state.pty_sessions.lock().await;
+    let handle = sessions
+        .get(&key)
+        .ok_or_else(|| format!("No PTY session for target: {}", target))?;
+    handle.ack(bytes);
+    Ok(())
+}
+
+/// POST /api/pty/:target/pong — answer a `ping`. Once a view answers,
+/// going 30s without an answer orphans the PTY (see `pty_reclaim`).
+#[tauri::command]
+pub async fn pty_pong(
+    target: String,
+    server: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let key = pty_manager::session_key(server.as_deref(), &target);
+    let sessions = state.pty_sessions.lock().await;
+    let handle = sessions
+        .get(&key)
+        .ok_or_else(|| format!("No PTY session for target: {}", target))?;
+    handle.pong();
+    Ok(())
+}
+
+/// POST /api/pty/:target/detach — stop streaming a PTY but keep it (and
+/// its output) alive, so switching back with `pty_connect` is instant
+#[tauri::command]
+pub async fn pty_detach(
+    target: String,
+    server: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let key = pty_manager::session_key(server.as_deref(), &target);
+    pty_manager::detach(&key, &state.pty_sessions).await
+}
+
+/// POST /api/pty/:target/record — tee the PTY's output, with timing, into an
+/// asciinema v2 cast file at `path` until `pty_record_stop` or the PTY closes
+#[tauri::command]
+pub async fn pty_record_start(
+    target: String,
+    path: String,
+    server: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<recording::RecordingInfo, String> {
+    let key = pty_manager::session_key(server.as_deref(), &target);
+    pty_manager::record_start(&key, &path, &state.pty_sessions).await
+}
+
+/// DELETE /api/pty/:target/record
+#[tauri::command]
+pub async fn pty_record_stop(
+    target: String,
+    server: Option<String>,
+) -> Result<recording::RecordingInfo, String> {
+    recording::stop(&pty_manager::session_key(server.as_deref(), &target))
+}
+
+/// Play a cast file (from `pty_record_start` or asciinema) into a terminal
+/// view: its output arrives on `on_data` as with `pty_connect`, `speed`
+/// times as fast (default 1), followed by `exit`
+#[tauri::command]
+pub async fn pty_play(
+    path: String,
+    speed: Option<f64>,
+    on_data: Channel,
+) -> Result<recording::PlaybackInfo, String> {
+    recording::play(&path, speed.unwrap_or(1.0), PtyChannel::new(on_data)).await
+}
+
+/// GET /api/pty/transport-benchmark — encode `bytes` (default 1 MiB) of
+/// terminal output in each `terminal.dataTransport`
+#[tauri::command]
+pub async fn pty_transport_benchmark(
+    bytes: Option<usize>,
+) -> Result<Vec<wire::TransportBenchmark>, String> {
+    tokio::task::spawn_blocking(move || wire::benchmark(bytes.unwrap_or(1024 * 1024)))
+        .await
+        .map_err(|e| format!("Benchmark failed: {}", e))?
+}
+
 /// Close a PTY session
 #[tauri::command]
-pub async fn pty_close(target: String, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn pty_close(
+    target: String,
+    server: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
     let mut sessions = state.pty_sessions.lock().await;
-    if let Some(handle) = sessions.remove(&target) {
+    if let Some(handle) = sessions.remove(&pty_manager::session_key(server.as_deref(), &target)) {
         handle.close();
     }
     Ok(())
 }
 
+/// GET /api/pty — every PTY the backend holds, with its size, age and
+/// subscriber count; lets a reloaded frontend see what it left behind
+#[tauri::command]
+pub async fn pty_list(state: State<'_, AppState>) -> Result<Vec<pty_manager::PtyInfo>, String> {
+    Ok(pty_manager::list(&state.pty_sessions).await)
+}
+
+/// DELETE /api/pty — close every PTY; returns how many were closed
+#[tauri::command]
+pub async fn pty_close_all(state: State<'_, AppState>) -> Result<usize, String> {
+    Ok(pty_manager::close_all(&state.pty_sessions).await)
+}
+
+/// POST /api/pty/gc — close PTYs whose reader, client or target is gone
+#[tauri::command]
+pub async fn pty_gc(state: State<'_, AppState>) -> Result<Vec<pty_manager::DroppedPty>, String> {
+    Ok(pty_manager::gc(&state.pty_sessions).await)
+}
+
+/// Serve a terminal font file by family name or path, for @font-face loading
+#[tauri::command]
+pub async fn asset_font(name: String) -> Result<Vec<u8>, String> {
+    tokio::task::spawn_blocking(move || {
+        let path =
+            fonts::find_font_file(&name).ok_or_else(|| format!("Font not found: {}", name))?;
+        std::fs::read(&path).map_err(|e| format!("Failed to read font file: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Font lookup failed: {}", e))?
+}
+
 /// Serve background image bytes
 #[tauri::command]
-pub fn asset_background() -> Result<Vec<u8>, String> {
-    let path = settings::get_background_image_path()
+pub fn asset_background(state: State<'_, AppState>) -> Result<Vec<u8>, String> {
+    let path = state
+        .settings
+        .background_image_path()
         .ok_or_else(|| "No local background image configured".to_string())?;
     std::fs::read(&path).map_err(|e| format!("Failed to read background image: {}", e))
 }
+
+/// POST /api/focus — the pane the UI has focused (omit `target` on blur)
+///
+/// Send on every focus change and periodically while focused; spans without
+/// a report for five minutes are cut off as idle. Also retitles the
+/// reporting window (`window.titleFormat`).
+#[tauri::command]
+pub async fn focus_report(
+    target: Option<String>,
+    server: Option<String>,
+    window: tauri::WebviewWindow,
+) -> Result<(), String> {
+    tmux::with_server(server, async {
+        focus::report(target.as_deref()).await;
+        window_title::focus(window.label(), target.as_deref()).await;
+    })
+    .await;
+    Ok(())
+}
+
+/// GET /api/memory — bytes held by PTY backlogs and preview caches, against `memory.maxBufferBytes`
+#[tauri::command]
+pub fn memory_usage() -> memory::MemoryUsage {
+    memory::usage()
+}
+
+/// GET /api/stats/focus — focus time per project over `range` ("day", "week" or "<N>h")
+#[tauri::command]
+pub fn stats_focus(range: Option<String>) -> Result<focus::FocusStats, String> {
+    focus::stats(range.as_deref().unwrap_or("day"))
+}
+
+/// JSON schema of every event the backend emits
+#[tauri::command]
+pub fn events_schema() -> serde_json::Value {
+    events::schema()
+}
+
+/// Activity report over `range` ("day", "week" or "<N>h") as "markdown" or "json"
+#[tauri::command]
+pub fn report_generate(range: Option<String>, format: Option<String>) -> Result<String, String> {
+    report::generate(
+        range.as_deref().unwrap_or("day"),
+        format.as_deref().unwrap_or("markdown"),
+    )
+}
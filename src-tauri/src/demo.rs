@@ -0,0 +1,439 @@
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use super::capabilities::Tool;
+use super::claude_sessions::ClaudeSession;
+use super::multiplexer::{AttachCommand, Multiplexer};
+use super::resolver::ProjectResult;
+use super::templates::{resolve as resolve_cwd, TemplateWindow};
+use super::tmux::{SessionDimensions, TmuxPane, TmuxSession, TmuxWindow};
+
+/// Fixtures used when `demo.json` isn't in the config dir
+const BUILTIN: &str = r##"{
+  "sessions": [
+    {
+      "name": "api",
+      "path": "~/src/api",
+      "windows": [
+        {
+          "name": "agent",
+          "panes": [
+            {
+              "process": "claude",
+              "title": "Refactor auth middleware",
+              "claude": { "summary": "Refactor auth middleware", "status": "thinking" },
+              "screen": "> Refactor the auth middleware to use the new session store\r\n\r\n* Reading src/middleware/auth.ts…\r\n"
+            },
+            { "process": "zsh", "screen": "~/src/api $ cargo test\r\ntest result: ok. 142 passed\r\n~/src/api $ " }
+          ]
+        },
+        { "name": "server", "panes": [{ "process": "cargo", "screen": "Listening on http://127.0.0.1:8080\r\n" }] }
+      ]
+    },
+    {
+      "name": "web",
+      "path": "~/src/web",
+      "windows": [
+        {
+          "name": "agent",
+          "panes": [
+            {
+              "process": "claude",
+              "title": "Add dark mode toggle",
+              "claude": { "summary": "Add dark mode toggle", "status": "done", "notified": true },
+              "screen": "Done. The toggle is in Settings > Appearance.\r\n\r\n> "
+            }
+          ]
+        },
+        { "name": "dev", "panes": [{ "process": "node", "screen": "VITE ready in 312 ms\r\n\r\n  Local: http://localhost:5173/\r\n" }] }
+      ]
+    },
+    {
+      "name": "notes",
+      "path": "~/notes",
+      "windows": [{ "name": "edit", "panes": [{ "process": "nvim", "screen": "# Today\r\n\r\n- ship the demo mode\r\n" }] }]
+    }
+  ],
+  "projects": [
+    { "name": "api", "path": "~/src/api" },
+    { "name": "web", "path": "~/src/web" },
+    { "name": "notes", "path": "~/notes" },
+    { "name": "infra", "path": "~/src/infra" }
+  ]
+}"##;
+
+static STATE: once_cell::sync::Lazy<Mutex<DemoState>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(DemoState::load()));
+
+/// Sessions, projects and pane screens for `Feature::DemoData`, from
+/// `demo.json` in the config dir or the built-in set. Nothing touches tmux,
+/// and created or killed sessions only live until restart.
+pub struct Demo;
+
+#[derive(Debug, Deserialize)]
+struct Fixtures {
+    #[serde(default)]
+    sessions: Vec<FixtureSession>,
+    #[serde(default)]
+    projects: Vec<FixtureProject>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FixtureSession {
+    name: String,
+    path: Option<String>,
+    #[serde(default)]
+    windows: Vec<FixtureWindow>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FixtureWindow {
+    name: String,
+    #[serde(default)]
+    panes: Vec<FixturePane>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FixturePane {
+    process: String,
+    #[serde(default)]
+    title: String,
+    claude: Option<FixtureClaude>,
+    /// Printed when the pane is attached
+    #[serde(default)]
+    screen: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FixtureClaude {
+    summary: String,
+    status: String,
+    #[serde(default)]
+    notified: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct FixtureProject {
+    name: String,
+    path: String,
+}
+
+struct DemoState {
+    sessions: Vec<TmuxSession>,
+    projects: Vec<ProjectResult>,
+    /// Pane target → screen
+    screens: HashMap<String, String>,
+    next_id: u32,
+}
+
+impl DemoState {
+    fn load() -> Self {
+        let path = super::paths::config_dir().join("demo.json");
+        let fixtures = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|raw| match serde_json::from_str::<Fixtures>(&raw) {
+                Ok(fixtures) => Some(fixtures),
+                Err(e) => {
+                    log::warn!("[demo] Ignoring {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .unwrap_or_else(|| serde_json::from_str(BUILTIN).expect("built-in demo fixtures"));
+
+        let mut state = DemoState {
+            sessions: vec![],
+            projects: vec![],
+            screens: HashMap::new(),
+            next_id: 0,
+        };
+        for session in fixtures.sessions {
+            state.add_session(session);
+        }
+        let count = fixtures.projects.len();
+        state.projects = fixtures
+            .projects
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| ProjectResult {
+                name: p.name,
+                path: super::settings::expand_tilde(&p.path)
+                    .to_string_lossy()
+                    .to_string(),
+                score: (count - i) as f64,
+            })
+            .collect();
+        state
+    }
+
+    /// Window `window_index` of session `session` built from `window`
+    fn window(
+        &mut self,
+        session: &str,
+        session_id: &str,
+        window_index: u32,
+        window: FixtureWindow,
+    ) -> TmuxWindow {
+        let window_id = format!("@{}", self.next_id);
+        self.next_id += 1;
+        let count = window.panes.len().max(1) as u32;
+        let mut panes = vec![];
+        for (pane_index, pane) in window.panes.into_iter().enumerate() {
+            let pane_index = pane_index as u32;
+            let target = format!("{}:{}.{}", session, window_index, pane_index);
+            let cols = 160 / count;
+            self.screens.insert(target.clone(), pane.screen);
+            panes.push(TmuxPane {
+                session_name: session.to_string(),
+                window_index,
+                window_name: window.name.clone(),
+                pane_index,
+                pane_id: format!("%{}", self.next_id),
+                window_id: window_id.clone(),
+                session_id: session_id.to_string(),
+                target,
+                active: pane_index == 0,
+                cols,
+                rows: 48,
+                left: pane_index * (cols + 1),
+                top: 0,
+                pid: 0,
+                process: pane.process,
+                dead: false,
+                dead_status: None,
+                title: pane.title,
+                in_mode: false,
+                alternate_on: false,
+                scroll_position: None,
+                last_output: None,
+                output_rate: None,
+                cpu_percent: None,
+                rss_bytes: None,
+                meta: None,
+                claude_session: pane.claude.map(|c| ClaudeSession {
+                    session_id: format!("demo-{}", self.next_id),
+                    summary: c.summary,
+                    status: c.status,
+                    notified: c.notified,
+                }),
+                pair: None,
+            });
+            self.next_id += 1;
+        }
+        TmuxWindow {
+            index: window_index,
+            name: window.name,
+            panes,
+        }
+    }
+
+    fn add_session(&mut self, fixture: FixtureSession) {
+        let session_id = format!("${}", self.next_id);
+        self.next_id += 1;
+        let mut windows = vec![];
+        for (window_index, window) in fixture.windows.into_iter().enumerate() {
+            let window = self.window(&fixture.name, &session_id, window_index as u32, window);
+            windows.push(window);
+        }
+        self.sessions.push(TmuxSession {
+            name: fixture.name,
+            id: session_id,
+            windows,
+            dimensions: Some(SessionDimensions {
+                width: 160,
+                height: 48,
+            }),
+            activity: Some(super::activity::now_unix()),
+            path: fixture.path.map(|p| {
+                super::settings::expand_tilde(&p)
+                    .to_string_lossy()
+                    .to_string()
+            }),
+            inferred_group: None,
+            server: None,
+            session_group: None,
+        });
+    }
+}
+
+/// Fixture projects matching `query` (all of them when empty)
+pub fn resolve(query: &str) -> Vec<ProjectResult> {
+    let query = query.to_lowercase();
+    STATE
+        .lock()
+        .unwrap()
+        .projects
+        .iter()
+        .filter(|p| query.is_empty() || p.name.to_lowercase().contains(&query))
+        .cloned()
+        .collect()
+}
+
+impl Multiplexer for Demo {
+    fn name(&self) -> &'static str {
+        "demo"
+    }
+
+    fn tool(&self) -> Option<Tool> {
+        None
+    }
+
+    /// Fixture sessions; registered servers list none
+    fn list_sessions(&self) -> BoxFuture<'_, Vec<TmuxSession>> {
+        async {
+            if super::tmux::current_server().is_some() {
+                return vec![];
+            }
+            STATE.lock().unwrap().sessions.clone()
+        }
+        .boxed()
+    }
+
+    fn create_session<'a>(
+        &'a self,
+        name: &'a str,
+        cwd: &'a str,
+        _command: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<bool, String>> {
+        async move {
+            let mut state = STATE.lock().unwrap();
+            if state.sessions.iter().any(|s| s.name == name) {
+                return Ok(false);
+            }
+            state.add_session(FixtureSession {
+                name: name.to_string(),
+                path: Some(cwd.to_string()),
+                windows: vec![FixtureWindow {
+                    name: "zsh".to_string(),
+                    panes: vec![FixturePane {
+                        process: "zsh".to_string(),
+                        title: String::new(),
+                        claude: None,
+                        screen: format!("{} $ ", cwd),
+                    }],
+                }],
+            });
+            Ok(true)
+        }
+        .boxed()
+    }
+
+    /// A window of shells, one per template pane
+    fn add_window<'a>(
+        &'a self,
+        session: &'a str,
+        window: &'a TemplateWindow,
+        project: &'a Path,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        async move {
+            let cwd = resolve_cwd(project, window.cwd.as_deref());
+            let fixture = FixtureWindow {
+                name: window.name.clone(),
+                panes: window
+                    .panes
+                    .iter()
+                    .map(|pane| FixturePane {
+                        process: "zsh".to_string(),
+                        title: String::new(),
+                        claude: None,
+                        screen: format!("{} $ ", resolve_cwd(&cwd, pane.cwd.as_deref()).display()),
+                    })
+                    .collect(),
+            };
+            let mut state = STATE.lock().unwrap();
+            let (session_id, index) = state
+                .sessions
+                .iter()
+                .find(|s| s.name == session)
+                .map(|s| {
+                    let next = s.windows.iter().map(|w| w.index + 1).max().unwrap_or(0);
+                    (s.id.clone(), next)
+                })
+                .ok_or_else(|| format!("Session not found: {}", session))?;
+            let window = state.window(session, &session_id, index, fixture);
+            if let Some(s) = state.sessions.iter_mut().find(|s| s.name == session) {
+                s.windows.push(window);
+            }
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn kill_session<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<(), String>> {
+        async move {
+            let mut state = STATE.lock().unwrap();
+            let before = state.sessions.len();
+            state.sessions.retain(|s| s.name != name);
+            if state.sessions.len() == before {
+                return Err(format!("Session not found: {}", name));
+            }
+            let prefix = format!("{}:", name);
+            state
+                .screens
+                .retain(|target, _| !target.starts_with(&prefix));
+            Ok(())
+        }
+        .boxed()
+    }
+
+    /// Accepted and dropped
+    fn send_input<'a>(
+        &'a self,
+        target: &'a str,
+        _text: &'a str,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        async move {
+            match self.pane_info(target).await {
+                Some(_) => Ok(()),
+                None => Err(format!("Pane not found: {}", target)),
+            }
+        }
+        .boxed()
+    }
+
+    fn pane_info<'a>(&'a self, target: &'a str) -> BoxFuture<'a, Option<TmuxPane>> {
+        async move {
+            let state = STATE.lock().unwrap();
+            let panes = state
+                .sessions
+                .iter()
+                .flat_map(|s| &s.windows)
+                .flat_map(|w| &w.panes);
+            // A bare session name addresses its first pane
+            panes
+                .clone()
+                .find(|p| p.target == target || p.pane_id == target)
+                .or_else(|| panes.clone().find(|p| p.session_name == target))
+                .cloned()
+        }
+        .boxed()
+    }
+
+    /// A shell that prints the pane's fixture screen and swallows input
+    fn attach_command(&self, target: &str, _read_only: bool) -> Result<AttachCommand, String> {
+        let screen = {
+            let state = STATE.lock().unwrap();
+            let target = state
+                .sessions
+                .iter()
+                .flat_map(|s| &s.windows)
+                .flat_map(|w| &w.panes)
+                .find(|p| p.target == target || p.pane_id == target || p.session_name == target)
+                .map(|p| p.target.clone())
+                .ok_or_else(|| format!("Pane not found: {}", target))?;
+            state.screens.get(&target).cloned().unwrap_or_default()
+        };
+        Ok(AttachCommand {
+            program: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                "stty -echo 2>/dev/null; printf '%s' \"$MUXTUNNEL_DEMO_SCREEN\"; exec cat >/dev/null"
+                    .to_string(),
+            ],
+            env: vec![("MUXTUNNEL_DEMO_SCREEN".to_string(), screen)],
+        })
+    }
+}
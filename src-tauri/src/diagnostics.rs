@@ -0,0 +1,241 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::pty_manager::PtySessionMap;
+
+const MONITOR_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Above these, `start_monitor` logs a warning
+const MAX_PTYS: usize = 32;
+const MAX_CHILD_PROCESSES: usize = 64;
+const MAX_THREADS: usize = 256;
+const MAX_RSS_BYTES: u64 = 1024 * 1024 * 1024;
+const MAX_TASKS_PER_KIND: usize = 64;
+
+/// Errors kept for `collect`, oldest dropped first
+const MAX_ERRORS: usize = 50;
+
+/// Subcommands that fail as a matter of course (probing for a target)
+const QUIET_SUBCOMMANDS: &[&str] = &["has-session", "display-message"];
+
+/// Live background tasks per kind, maintained by `TaskGuard`
+static TASKS: once_cell::sync::Lazy<Mutex<BTreeMap<&'static str, usize>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+static ERRORS: once_cell::sync::Lazy<Mutex<VecDeque<ErrorEntry>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(VecDeque::new()));
+
+/// Counts a background task as alive until dropped
+pub struct TaskGuard(&'static str);
+
+/// Register a running background task of kind `name`; hold the guard for its lifetime
+pub fn track(name: &'static str) -> TaskGuard {
+    *TASKS.lock().unwrap().entry(name).or_insert(0) += 1;
+    TaskGuard(name)
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        let mut tasks = TASKS.lock().unwrap();
+        if let Some(count) = tasks.get_mut(self.0) {
+            *count = count.saturating_sub(1);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostics {
+    pub open_ptys: usize,
+    /// PTY entries whose `tmux attach` client is still running
+    pub pty_clients_alive: usize,
+    /// Direct child processes of MuxTunnel
+    pub child_processes: usize,
+    /// OS threads (Linux only)
+    pub threads: Option<usize>,
+    pub rss_bytes: Option<u64>,
+    /// Tasks alive on the async runtime
+    pub runtime_tasks: usize,
+    /// Background tasks by kind (PTY readers, pollers, watchers)
+    pub tasks: BTreeMap<String, usize>,
+    /// Thresholds currently exceeded
+    pub warnings: Vec<String>,
+    /// Recent tmux and PTY failures, oldest first
+    pub errors: Vec<ErrorEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorEntry {
+    /// Unix time
+    pub at: u64,
+    pub server: Option<String>,
+    pub target: String,
+    pub message: String,
+    /// The pane's last lines at the time, with `diagnostics.captureOutput`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recent_output: Option<Vec<String>>,
+}
+
+/// Remember a failed tmux command or PTY read for `target`. With
+/// `diagnostics.captureOutput` the pane's last lines are captured first.
+pub fn record_error(server: Option<String>, target: &str, message: &str) {
+    let mut entry = ErrorEntry {
+        at: super::activity::now_unix(),
+        server,
+        target: target.to_string(),
+        message: message.trim().to_string(),
+        recent_output: None,
+    };
+    let settings = super::settings::get_settings().settings.diagnostics;
+    if !settings.capture_output {
+        push_error(entry);
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        let server = entry.server.clone();
+        entry.recent_output = super::tmux::with_server(
+            server,
+            recent_output(&entry.target, settings.output_lines),
+        )
+        .await;
+        push_error(entry);
+    });
+}
+
+/// Record a failure of tmux `args` when it names a target
+pub(crate) fn record_tmux_error(args: &[&str], message: &str) {
+    if args.first().is_some_and(|s| QUIET_SUBCOMMANDS.contains(s)) {
+        return;
+    }
+    if let Some(target) = args.iter().position(|a| *a == "-t").and_then(|i| args.get(i + 1)) {
+        record_error(super::tmux::current_server(), target, message);
+    }
+}
+
+fn push_error(entry: ErrorEntry) {
+    let mut errors = ERRORS.lock().unwrap();
+    errors.push_back(entry);
+    if errors.len() > MAX_ERRORS {
+        errors.pop_front();
+    }
+}
+
+/// Last `lines` non-blank lines of a pane. Spawns tmux directly rather than
+/// through `tmux::run`, whose failures would be recorded in turn.
+async fn recent_output(target: &str, lines: usize) -> Option<Vec<String>> {
+    let start = format!("-{}", lines);
+    let output = super::tmux::tmux_command()
+        .args(["capture-pane", "-p", "-J", "-t", target, "-S", &start])
+        .output()
+        .await
+        .ok()
+        .filter(|o| o.status.success())?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let text = super::links::strip_ansi(&text);
+    let all: Vec<&str> = text.trim_end().lines().collect();
+    Some(
+        all[all.len().saturating_sub(lines)..]
+            .iter()
+            .map(|l| l.trim_end().to_string())
+            .collect(),
+    )
+}
+
+async fn rss_bytes() -> Option<u64> {
+    let pid = std::process::id().to_string();
+    let output = tokio::process::Command::new("ps")
+        .args(["-o", "rss=", "-p", &pid])
+        .output()
+        .await
+        .ok()?;
+    let kb: u64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    Some(kb * 1024)
+}
+
+fn thread_count() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|l| l.strip_prefix("Threads:"))
+        .and_then(|n| n.trim().parse().ok())
+}
+
+/// Snapshot of MuxTunnel's own resource usage
+pub async fn collect(sessions: &Arc<tokio::sync::Mutex<PtySessionMap>>) -> Diagnostics {
+    let (open_ptys, client_pids): (usize, Vec<u32>) = {
+        let map = sessions.lock().await;
+        (map.len(), map.values().filter_map(|h| h.client_pid()).collect())
+    };
+
+    let (table, rss_bytes) = tokio::join!(super::tmux::get_process_table(), rss_bytes());
+    let own_pid = std::process::id();
+    let child_processes = table.values().filter(|p| p.ppid == own_pid).count();
+    let pty_clients_alive = client_pids.iter().filter(|pid| table.contains_key(pid)).count();
+    let threads = thread_count();
+    let runtime_tasks = tokio::runtime::Handle::current()
+        .metrics()
+        .num_alive_tasks();
+    let tasks: BTreeMap<String, usize> = TASKS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, count)| (name.to_string(), *count))
+        .collect();
+
+    let mut warnings = Vec::new();
+    if open_ptys > MAX_PTYS {
+        warnings.push(format!("{} open PTYs (limit {})", open_ptys, MAX_PTYS));
+    }
+    // Without ps the table is empty and every client would look dead
+    if !table.is_empty() && pty_clients_alive < client_pids.len() {
+        warnings.push(format!(
+            "{} PTYs whose tmux client has exited",
+            client_pids.len() - pty_clients_alive
+        ));
+    }
+    if child_processes > MAX_CHILD_PROCESSES {
+        warnings.push(format!(
+            "{} child processes (limit {})",
+            child_processes, MAX_CHILD_PROCESSES
+        ));
+    }
+    if let Some(threads) = threads.filter(|t| *t > MAX_THREADS) {
+        warnings.push(format!("{} threads (limit {})", threads, MAX_THREADS));
+    }
+    if let Some(rss) = rss_bytes.filter(|r| *r > MAX_RSS_BYTES) {
+        warnings.push(format!("RSS {} MiB", rss / (1024 * 1024)));
+    }
+    for (name, count) in &tasks {
+        if *count > MAX_TASKS_PER_KIND {
+            warnings.push(format!("{} {} tasks (limit {})", count, name, MAX_TASKS_PER_KIND));
+        }
+    }
+
+    Diagnostics {
+        open_ptys,
+        pty_clients_alive,
+        child_processes,
+        threads,
+        rss_bytes,
+        runtime_tasks,
+        tasks,
+        warnings,
+        errors: ERRORS.lock().unwrap().iter().cloned().collect(),
+    }
+}
+
+/// Periodically collect diagnostics and log any exceeded thresholds
+pub fn start_monitor(sessions: Arc<tokio::sync::Mutex<PtySessionMap>>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(MONITOR_INTERVAL).await;
+            let diagnostics = collect(&sessions).await;
+            for warning in &diagnostics.warnings {
+                log::warn!("[diagnostics] {}", warning);
+            }
+        }
+    });
+}
@@ -0,0 +1,252 @@
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+/// Bumped whenever an event payload changes incompatibly
+pub const EVENTS_VERSION: u32 = 2;
+
+static APP_HANDLE: OnceCell<AppHandle> = OnceCell::new();
+
+/// Every event the backend emits to the frontend.
+///
+/// The Tauri event name is the kebab-case `type` tag, e.g. `settings-changed`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum BackendEvent {
+    /// Sessions were created (`change: "created"`) or killed (`"deleted"`)
+    #[serde(rename_all = "camelCase")]
+    SessionsChanged { change: String, names: Vec<String> },
+    /// A Claude session's status or notification state changed
+    #[serde(rename_all = "camelCase")]
+    ClaudeSessionUpdated {
+        session_id: String,
+        status: String,
+        notified: bool,
+    },
+    /// settings.json was reloaded
+    #[serde(rename_all = "camelCase")]
+    SettingsChanged { settings_version: u32 },
+    /// The user's own tmux client focused a different pane (follow mode)
+    #[serde(rename_all = "camelCase")]
+    FollowPaneChanged { target: String, pane_id: String },
+    /// A notification went through the dispatcher (shown in-app even when
+    /// desktop delivery was suppressed)
+    #[serde(rename_all = "camelCase")]
+    Notification {
+        id: u64,
+        title: String,
+        body: String,
+        kind: String,
+        action: Option<String>,
+        delivered: bool,
+    },
+    /// Links not seen before in a pane's output
+    #[serde(rename_all = "camelCase")]
+    LinksFound {
+        server: Option<String>,
+        target: String,
+        links: Vec<super::links::Link>,
+    },
+    /// A PTY entry was closed by the sweeper, or evicted past `terminal.maxPtys`
+    #[serde(rename_all = "camelCase")]
+    PtyDropped {
+        server: Option<String>,
+        target: String,
+        reason: String,
+    },
+    /// Throughput counters for an attached PTY
+    #[serde(rename_all = "camelCase")]
+    PtyStats {
+        target: String,
+        bytes_out: u64,
+        messages: u64,
+    },
+    /// A session or workspace hook exited non-zero, failed to start or timed out
+    #[serde(rename_all = "camelCase")]
+    HookFailed {
+        scope: String,
+        name: String,
+        phase: String,
+        command: String,
+        exit_code: Option<i32>,
+        /// Trailing output of the hook
+        output: String,
+    },
+    /// A prompt queued with `claude_queue_prompt` was typed into its pane
+    PromptDelivered { id: u64, target: String },
+    /// A `panes_watch_expr` watch extracted a new value from pane output
+    #[serde(rename_all = "camelCase")]
+    WatchValue {
+        watch_id: u64,
+        target: String,
+        value: String,
+    },
+    /// Pairs were created, deleted, or dropped because a pane exited
+    PairsChanged { pairs: Vec<super::pairs::Pair> },
+}
+
+impl BackendEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BackendEvent::SessionsChanged { .. } => "sessions-changed",
+            BackendEvent::ClaudeSessionUpdated { .. } => "claude-session-updated",
+            BackendEvent::SettingsChanged { .. } => "settings-changed",
+            BackendEvent::FollowPaneChanged { .. } => "follow-pane-changed",
+            BackendEvent::Notification { .. } => "notification",
+            BackendEvent::LinksFound { .. } => "links-found",
+            BackendEvent::PtyDropped { .. } => "pty-dropped",
+            BackendEvent::PtyStats { .. } => "pty-stats",
+            BackendEvent::HookFailed { .. } => "hook-failed",
+            BackendEvent::PromptDelivered { .. } => "prompt-delivered",
+            BackendEvent::WatchValue { .. } => "watch-value",
+            BackendEvent::PairsChanged { .. } => "pairs-changed",
+        }
+    }
+}
+
+/// Wire format: the event plus the catalog version
+#[derive(Clone, Serialize)]
+struct Envelope<'a> {
+    version: u32,
+    #[serde(flatten)]
+    event: &'a BackendEvent,
+}
+
+pub fn init(app_handle: AppHandle) {
+    let _ = APP_HANDLE.set(app_handle);
+}
+
+/// Emit an event to all webviews (no-op before `init`)
+pub fn emit(event: BackendEvent) {
+    let Some(app) = APP_HANDLE.get() else {
+        return;
+    };
+    let envelope = Envelope {
+        version: EVENTS_VERSION,
+        event: &event,
+    };
+    if let Err(e) = app.emit(event.name(), envelope) {
+        log::warn!("[events] Failed to emit {}: {}", event.name(), e);
+    }
+}
+
+fn event_schema(name: &str, properties: serde_json::Value) -> serde_json::Value {
+    let mut props = properties.as_object().cloned().unwrap_or_default();
+    let mut required: Vec<String> = props.keys().cloned().collect();
+    props.insert("type".into(), serde_json::json!({ "const": name }));
+    props.insert("version".into(), serde_json::json!({ "const": EVENTS_VERSION }));
+    required.push("type".into());
+    required.push("version".into());
+    serde_json::json!({
+        "type": "object",
+        "properties": props,
+        "required": required,
+    })
+}
+
+/// JSON schema describing every event payload
+pub fn schema() -> serde_json::Value {
+    use serde_json::json;
+
+    let string = json!({ "type": "string" });
+    let integer = json!({ "type": "integer", "minimum": 0 });
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "MuxTunnel backend events",
+        "version": EVENTS_VERSION,
+        "oneOf": [
+            event_schema("sessions-changed", json!({
+                "change": { "enum": ["created", "deleted"] },
+                "names": { "type": "array", "items": string },
+            })),
+            event_schema("claude-session-updated", json!({
+                "sessionId": string,
+                "status": { "enum": ["thinking", "done", "idle"] },
+                "notified": { "type": "boolean" },
+            })),
+            event_schema("settings-changed", json!({
+                "settingsVersion": integer,
+            })),
+            event_schema("follow-pane-changed", json!({
+                "target": string,
+                "paneId": string,
+            })),
+            event_schema("notification", json!({
+                "id": integer,
+                "title": string,
+                "body": string,
+                "kind": string,
+                "action": { "type": ["string", "null"] },
+                "delivered": { "type": "boolean" },
+            })),
+            event_schema("links-found", json!({
+                "server": { "type": ["string", "null"] },
+                "target": string,
+                "links": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "kind": string,
+                            "text": string,
+                            "url": { "type": ["string", "null"] },
+                        },
+                        "required": ["kind", "text", "url"],
+                    },
+                },
+            })),
+            event_schema("pty-dropped", json!({
+                "server": { "type": ["string", "null"] },
+                "target": string,
+                "reason": {
+                    "enum": [
+                        "reader-exited",
+                        "client-exited",
+                        "target-missing",
+                        "detach-expired",
+                        "evicted",
+                    ],
+                },
+            })),
+            event_schema("pty-stats", json!({
+                "target": string,
+                "bytesOut": integer,
+                "messages": integer,
+            })),
+            event_schema("hook-failed", json!({
+                "scope": { "enum": ["session", "workspace"] },
+                "name": string,
+                "phase": { "enum": ["pre", "post"] },
+                "command": string,
+                "exitCode": { "type": ["integer", "null"] },
+                "output": string,
+            })),
+            event_schema("prompt-delivered", json!({
+                "id": integer,
+                "target": string,
+            })),
+            event_schema("watch-value", json!({
+                "watchId": integer,
+                "target": string,
+                "value": string,
+            })),
+            event_schema("pairs-changed", json!({
+                "pairs": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "id": string,
+                            "server": string,
+                            "primary": string,
+                            "secondary": string,
+                            "createdAt": integer,
+                        },
+                        "required": ["id", "primary", "secondary", "createdAt"],
+                    },
+                },
+            })),
+        ],
+    })
+}
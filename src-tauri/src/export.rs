@@ -0,0 +1,242 @@
+use serde::Serialize;
+use std::fmt::Write;
+
+/// xterm's default 16 colors
+const PALETTE: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0xcd, 0x00, 0x00),
+    (0x00, 0xcd, 0x00),
+    (0xcd, 0xcd, 0x00),
+    (0x00, 0x00, 0xee),
+    (0xcd, 0x00, 0xcd),
+    (0x00, 0xcd, 0xcd),
+    (0xe5, 0xe5, 0xe5),
+    (0x7f, 0x7f, 0x7f),
+    (0xff, 0x00, 0x00),
+    (0x00, 0xff, 0x00),
+    (0xff, 0xff, 0x00),
+    (0x5c, 0x5c, 0xff),
+    (0xff, 0x00, 0xff),
+    (0x00, 0xff, 0xff),
+    (0xff, 0xff, 0xff),
+];
+
+static SGR: once_cell::sync::Lazy<regex::Regex> =
+    once_cell::sync::Lazy::new(|| regex::Regex::new(r"\x1b\[([0-9;:]*)m").unwrap());
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportOutcome {
+    pub path: String,
+    pub bytes: usize,
+    pub lines: usize,
+}
+
+/// Text attributes set by SGR sequences
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Style {
+    fg: Option<(u8, u8, u8)>,
+    bg: Option<(u8, u8, u8)>,
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    inverse: bool,
+    strike: bool,
+}
+
+/// RGB of a 256-color index
+fn indexed(n: u8) -> (u8, u8, u8) {
+    match n {
+        0..=15 => PALETTE[n as usize],
+        16..=231 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let n = n - 16;
+            (level(n / 36), level(n / 6 % 6), level(n % 6))
+        }
+        _ => {
+            let gray = 8 + (n - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
+
+/// An extended color (`38;5;n` or `38;2;r;g;b`) starting at `params[0]`,
+/// and how many params it used
+fn extended(params: &[u32]) -> (Option<(u8, u8, u8)>, usize) {
+    match params {
+        [5, n, ..] => (Some(indexed(*n as u8)), 2),
+        [2, r, g, b, ..] => (Some((*r as u8, *g as u8, *b as u8)), 4),
+        _ => (None, params.len()),
+    }
+}
+
+impl Style {
+    fn apply(&mut self, params: &str) {
+        // `:` separates sub-parameters of extended colors in newer terminals
+        let params: Vec<u32> = params
+            .split([';', ':'])
+            .map(|p| p.parse().unwrap_or(0))
+            .collect();
+        let mut i = 0;
+        while i < params.len() {
+            match params[i] {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                7 => self.inverse = true,
+                9 => self.strike = true,
+                22 => (self.bold, self.dim) = (false, false),
+                23 => self.italic = false,
+                24 => self.underline = false,
+                27 => self.inverse = false,
+                29 => self.strike = false,
+                n @ 30..=37 => self.fg = Some(PALETTE[(n - 30) as usize]),
+                n @ 90..=97 => self.fg = Some(PALETTE[(n - 90 + 8) as usize]),
+                n @ 40..=47 => self.bg = Some(PALETTE[(n - 40) as usize]),
+                n @ 100..=107 => self.bg = Some(PALETTE[(n - 100 + 8) as usize]),
+                39 => self.fg = None,
+                49 => self.bg = None,
+                n @ (38 | 48) => {
+                    let (color, used) = extended(&params[i + 1..]);
+                    if n == 38 {
+                        self.fg = color;
+                    } else {
+                        self.bg = color;
+                    }
+                    i += used;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+
+    fn css(&self) -> String {
+        let (mut fg, mut bg) = (self.fg, self.bg);
+        if self.inverse {
+            (fg, bg) = (bg.or(Some(DEFAULT_BG)), fg.or(Some(DEFAULT_FG)));
+        }
+        let mut css = String::new();
+        if let Some((r, g, b)) = fg {
+            let _ = write!(css, "color:#{:02x}{:02x}{:02x};", r, g, b);
+        }
+        if let Some((r, g, b)) = bg {
+            let _ = write!(css, "background:#{:02x}{:02x}{:02x};", r, g, b);
+        }
+        if self.bold {
+            css.push_str("font-weight:bold;");
+        }
+        if self.dim {
+            css.push_str("opacity:0.6;");
+        }
+        if self.italic {
+            css.push_str("font-style:italic;");
+        }
+        match (self.underline, self.strike) {
+            (true, true) => css.push_str("text-decoration:underline line-through;"),
+            (true, false) => css.push_str("text-decoration:underline;"),
+            (false, true) => css.push_str("text-decoration:line-through;"),
+            (false, false) => {}
+        }
+        css
+    }
+}
+
+const DEFAULT_FG: (u8, u8, u8) = (0xd4, 0xd4, 0xd4);
+const DEFAULT_BG: (u8, u8, u8) = (0x1e, 0x1e, 0x1e);
+
+fn escape_html(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Terminal output as an HTML page, SGR colors and attributes as styled
+/// spans; other escape sequences are dropped
+pub fn to_html(content: &str, title: &str) -> String {
+    let mut body = String::new();
+    let mut style = Style::default();
+    let mut open = false;
+    let mut last = 0;
+    for caps in SGR.captures_iter(content) {
+        let m = caps.get(0).unwrap();
+        escape_html(
+            &super::links::strip_ansi(&content[last..m.start()]),
+            &mut body,
+        );
+        last = m.end();
+        let before = style;
+        style.apply(&caps[1]);
+        if style == before {
+            continue;
+        }
+        if open {
+            body.push_str("</span>");
+            open = false;
+        }
+        let css = style.css();
+        if !css.is_empty() {
+            let _ = write!(body, "<span style=\"{}\">", css);
+            open = true;
+        }
+    }
+    escape_html(&super::links::strip_ansi(&content[last..]), &mut body);
+    if open {
+        body.push_str("</span>");
+    }
+
+    let mut page =
+        String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>");
+    escape_html(title, &mut page);
+    let _ = write!(
+        page,
+        "</title>\n<style>body {{ margin: 0; background: #{:02x}{:02x}{:02x}; color: #{:02x}{:02x}{:02x}; }} \
+         pre {{ margin: 1em; font-family: ui-monospace, Menlo, monospace; font-size: 13px; }}</style>\n\
+         </head>\n<body>\n<pre>",
+        DEFAULT_BG.0, DEFAULT_BG.1, DEFAULT_BG.2, DEFAULT_FG.0, DEFAULT_FG.1, DEFAULT_FG.2
+    );
+    page.push_str(&body);
+    page.push_str("</pre>\n</body>\n</html>\n");
+    page
+}
+
+/// Write a pane's whole scrollback to `path` as "text" (escapes stripped)
+/// or "html" (colors kept)
+pub async fn export(target: &str, path: &str, format: &str) -> Result<ExportOutcome, String> {
+    let html = match format {
+        "text" | "txt" => false,
+        "html" => true,
+        other => return Err(format!("Unknown export format: {}", other)),
+    };
+    let content = super::tmux::capture_all(target, html).await?;
+    // Skip the blank rows below the cursor
+    let content = content.trim_end();
+    let lines = content.lines().count();
+    let output = if html {
+        to_html(content, target)
+    } else {
+        format!("{}\n", content)
+    };
+
+    let path = super::settings::expand_tilde(path);
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    std::fs::write(&path, &output)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(ExportOutcome {
+        path: path.to_string_lossy().to_string(),
+        bytes: output.len(),
+        lines,
+    })
+}
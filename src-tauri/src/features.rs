@@ -0,0 +1,114 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Flags as configured at startup; changing `features` takes effect on restart
+static STARTUP: once_cell::sync::OnceCell<BTreeMap<String, bool>> =
+    once_cell::sync::OnceCell::new();
+
+/// Experimental subsystems that ship disabled and are switched on in `features`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    ControlMode,
+    McpServer,
+    RemoteHosts,
+    DemoData,
+}
+
+const ALL: &[Feature] = &[
+    Feature::ControlMode,
+    Feature::McpServer,
+    Feature::RemoteHosts,
+    Feature::DemoData,
+];
+
+impl Feature {
+    /// Key in the `features` settings section
+    pub fn key(self) -> &'static str {
+        match self {
+            Feature::ControlMode => "controlMode",
+            Feature::McpServer => "mcpServer",
+            Feature::RemoteHosts => "remoteHosts",
+            Feature::DemoData => "demoData",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Feature::ControlMode => "talk to tmux over one `tmux -C` connection",
+            Feature::McpServer => "expose sessions to agents over MCP",
+            Feature::RemoteHosts => "attach to tmux servers on other machines",
+            Feature::DemoData => "serve sessions, projects and Claude status from fixtures",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureStatus {
+    pub name: String,
+    /// In effect since startup
+    pub enabled: bool,
+    /// Current value in settings; differs from `enabled` until restart
+    pub configured: bool,
+    /// `None` for flags in settings that this build doesn't know
+    pub description: Option<String>,
+}
+
+/// Every known flag, off
+pub fn defaults() -> BTreeMap<String, bool> {
+    ALL.iter().map(|f| (f.key().to_string(), false)).collect()
+}
+
+fn configured() -> BTreeMap<String, bool> {
+    super::settings::get_settings().settings.features
+}
+
+/// Snapshot the flags; call once settings are installed
+pub fn init() {
+    let flags = configured();
+    let on: Vec<&str> = flags
+        .iter()
+        .filter(|(_, on)| **on)
+        .map(|(name, _)| name.as_str())
+        .collect();
+    if !on.is_empty() {
+        log::info!("[features] Enabled: {}", on.join(", "));
+    }
+    let _ = STARTUP.set(flags);
+}
+
+pub fn enabled(feature: Feature) -> bool {
+    STARTUP
+        .get()
+        .and_then(|flags| flags.get(feature.key()).copied())
+        .unwrap_or(false)
+}
+
+/// Every known flag plus any unknown ones found in settings
+pub fn get() -> Vec<FeatureStatus> {
+    let startup = STARTUP.get().cloned().unwrap_or_default();
+    let configured = configured();
+    let mut statuses: Vec<FeatureStatus> = ALL
+        .iter()
+        .map(|feature| FeatureStatus {
+            name: feature.key().to_string(),
+            enabled: enabled(*feature),
+            configured: configured.get(feature.key()).copied().unwrap_or(false),
+            description: Some(feature.description().to_string()),
+        })
+        .collect();
+    let unknown = startup
+        .keys()
+        .chain(configured.keys())
+        .filter(|name| !ALL.iter().any(|f| f.key() == name.as_str()))
+        .collect::<std::collections::BTreeSet<_>>();
+    for name in unknown {
+        statuses.push(FeatureStatus {
+            name: name.clone(),
+            enabled: startup.get(name).copied().unwrap_or(false),
+            configured: configured.get(name).copied().unwrap_or(false),
+            description: None,
+        });
+    }
+    statuses
+}
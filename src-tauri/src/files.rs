@@ -0,0 +1,93 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncReadExt;
+
+const DEFAULT_MAX_BYTES: u64 = 256 * 1024;
+const MAX_BYTES_LIMIT: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileContent {
+    /// Canonical path that was read
+    pub path: String,
+    pub size: u64,
+    /// Lossily decoded; empty when `binary`
+    pub content: String,
+    /// Only the first `max_bytes` were read
+    pub truncated: bool,
+    /// Contains NUL bytes, so not shown as text
+    pub binary: bool,
+}
+
+/// Canonical session paths of every session on every registered server
+async fn allowed_roots() -> Vec<PathBuf> {
+    let servers = std::iter::once(None).chain(super::tmux::server_names().into_iter().map(Some));
+    let per_server = futures::future::join_all(
+        servers.map(|server| super::tmux::with_server(server, super::tmux::list_sessions())),
+    )
+    .await;
+    let mut roots: Vec<PathBuf> = per_server
+        .into_iter()
+        .flatten()
+        .filter_map(|s| s.path)
+        .filter_map(|p| std::fs::canonicalize(p).ok())
+        // `/` or `$HOME` as a session path would allow-list everything under it
+        .filter(|p| p.parent().is_some() && Some(p.as_path()) != dirs::home_dir().as_deref())
+        .collect();
+    roots.sort();
+    roots.dedup();
+    roots
+}
+
+fn is_allowed(path: &Path, roots: &[PathBuf]) -> bool {
+    roots.iter().any(|root| path.starts_with(root))
+}
+
+/// Read up to `max_bytes` of a file inside some session's path.
+/// Symlinks are resolved first, so links can't escape the allow-list.
+pub async fn read(path: &str, max_bytes: Option<u64>) -> Result<FileContent, String> {
+    let requested = super::settings::expand_tilde(path);
+    if !requested.is_absolute() {
+        return Err(format!("Path must be absolute: {}", path));
+    }
+    let canonical = tokio::fs::canonicalize(&requested)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    if !is_allowed(&canonical, &allowed_roots().await) {
+        return Err(format!(
+            "Not inside a session path: {}",
+            canonical.display()
+        ));
+    }
+
+    let max_bytes = max_bytes.unwrap_or(DEFAULT_MAX_BYTES).min(MAX_BYTES_LIMIT);
+    // Before opening: opening a FIFO would block until something writes to it
+    let metadata = tokio::fs::metadata(&canonical)
+        .await
+        .map_err(|e| format!("Failed to stat {}: {}", canonical.display(), e))?;
+    if !metadata.is_file() {
+        return Err(format!("Not a file: {}", canonical.display()));
+    }
+
+    let file = tokio::fs::File::open(&canonical)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", canonical.display(), e))?;
+    let mut bytes = Vec::new();
+    file.take(max_bytes)
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", canonical.display(), e))?;
+    let binary = bytes.contains(&0);
+
+    Ok(FileContent {
+        path: canonical.to_string_lossy().to_string(),
+        size: metadata.len(),
+        truncated: metadata.len() > bytes.len() as u64,
+        content: if binary {
+            String::new()
+        } else {
+            String::from_utf8_lossy(&bytes).to_string()
+        },
+        binary,
+    })
+}
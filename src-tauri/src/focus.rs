@@ -0,0 +1,154 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use super::activity::{self, Activity};
+
+/// Without a report for this long the UI is treated as idle; the span ends at the last report
+const IDLE_GAP_SECS: u64 = 5 * 60;
+
+/// Long spans are written out in pieces so a crash loses at most this much
+const FLUSH_SECS: u64 = 10 * 60;
+
+static CURRENT: Mutex<Option<Focused>> = Mutex::new(None);
+
+struct Focused {
+    server: Option<String>,
+    target: String,
+    project: String,
+    since: u64,
+    last_seen: u64,
+}
+
+impl Focused {
+    /// End of the span if it were closed at `now`
+    fn end(&self, now: u64) -> u64 {
+        now.min(self.last_seen + IDLE_GAP_SECS)
+    }
+
+    fn record(&self, end: u64) {
+        let secs = end.saturating_sub(self.since);
+        if secs == 0 {
+            return;
+        }
+        activity::record(Activity::Focus {
+            ts: end,
+            project: self.project.clone(),
+            target: self.target.clone(),
+            secs,
+        });
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectFocus {
+    pub project: String,
+    pub secs: u64,
+    /// Target → seconds focused
+    pub targets: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusStats {
+    pub since: u64,
+    pub until: u64,
+    /// Most focused first
+    pub projects: Vec<ProjectFocus>,
+}
+
+/// Project a pane belongs to: the git root of its working directory
+async fn project_of(target: &str) -> String {
+    match super::tmux::get_pane_cwd(target).await {
+        Some(cwd) => super::resolver::project_root(&cwd)
+            .to_string_lossy()
+            .to_string(),
+        None => super::tmux::session_of(target).await,
+    }
+}
+
+/// The UI focused `target` on the current server (`None` when it lost focus).
+/// Also meant as a heartbeat: re-reporting the same target keeps its span open.
+pub async fn report(target: Option<&str>) {
+    let server = super::tmux::current_server();
+    let now = activity::now_unix();
+    {
+        let mut current = CURRENT.lock().unwrap();
+        if let Some(focused) = current.as_mut() {
+            let same = Some(focused.target.as_str()) == target && focused.server == server;
+            let idle = now > focused.last_seen + IDLE_GAP_SECS;
+            if same && !idle {
+                focused.last_seen = now;
+                if now.saturating_sub(focused.since) >= FLUSH_SECS {
+                    focused.record(now);
+                    focused.since = now;
+                }
+                return;
+            }
+            focused.record(focused.end(now));
+            *current = None;
+        }
+    }
+
+    let Some(target) = target else {
+        return;
+    };
+    let project = project_of(target).await;
+    *CURRENT.lock().unwrap() = Some(Focused {
+        server,
+        target: target.to_string(),
+        project,
+        since: now,
+        last_seen: now,
+    });
+}
+
+/// Focus time per project over `range` ("day", "week" or "<N>h"),
+/// including the span still in progress
+pub fn stats(range: &str) -> Result<FocusStats, String> {
+    let until = activity::now_unix();
+    let since = until.saturating_sub(super::report::range_secs(range)?);
+
+    let mut spans: Vec<(String, String, u64)> = activity::read_range(since, until + 1)
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Activity::Focus {
+                project,
+                target,
+                secs,
+                ts,
+            } => Some((project, target, secs.min(ts - since))),
+            _ => None,
+        })
+        .collect();
+    if let Some(focused) = CURRENT.lock().unwrap().as_ref() {
+        let start = focused.since.max(since);
+        spans.push((
+            focused.project.clone(),
+            focused.target.clone(),
+            focused.end(until).saturating_sub(start),
+        ));
+    }
+
+    let mut projects: BTreeMap<String, ProjectFocus> = BTreeMap::new();
+    for (project, target, secs) in spans {
+        let entry = projects
+            .entry(project.clone())
+            .or_insert_with(|| ProjectFocus {
+                project,
+                secs: 0,
+                targets: BTreeMap::new(),
+            });
+        entry.secs += secs;
+        *entry.targets.entry(target).or_insert(0) += secs;
+    }
+    let mut projects: Vec<ProjectFocus> = projects.into_values().filter(|p| p.secs > 0).collect();
+    projects.sort_by_key(|p| std::cmp::Reverse(p.secs));
+
+    Ok(FocusStats {
+        since,
+        until,
+        projects,
+    })
+}
@@ -0,0 +1,247 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use super::pty_manager::PtySessionMap;
+
+/// Hooks that fire when a real tmux client moves to another pane
+const HOOKS: &[&str] = &[
+    "pane-focus-in",
+    "after-select-pane",
+    "after-select-window",
+    "client-session-changed",
+];
+
+/// Hook array index, so user-defined hooks on the same events are left alone
+const HOOK_INDEX: u32 = 47;
+
+static FOLLOW: once_cell::sync::Lazy<Mutex<FollowState>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(FollowState::default()));
+
+#[derive(Default)]
+struct FollowState {
+    enabled: bool,
+    /// Bumped on every start so a stale reader from a previous run exits
+    generation: u64,
+    last_pane_id: Option<String>,
+}
+
+/// FIFO the tmux hooks write `<client_pid> <pane_id> <target>` lines into
+fn focus_fifo() -> PathBuf {
+    super::paths::data_dir().join("pipes").join("follow-focus.fifo")
+}
+
+fn hook_name(hook: &str) -> String {
+    format!("{}[{}]", hook, HOOK_INDEX)
+}
+
+async fn install_hooks() -> Result<(), String> {
+    super::tmux::require_feature(super::tmux::Feature::HookArrays)?;
+    let echo = format!(
+        "echo '#{{client_pid}} #{{pane_id}} #{{session_name}}:#{{window_index}}.#{{pane_index}}' > {}",
+        // run-shell expands formats in the whole command, path included
+        super::pane_pipe::shell_quote(&focus_fifo().to_string_lossy()).replace('#', "##")
+    );
+    let command = format!("run-shell -b {}", super::tmux_control::quote(&echo));
+    for hook in HOOKS {
+        super::tmux::set_hook(&hook_name(hook), Some(&command)).await?;
+    }
+    Ok(())
+}
+
+async fn remove_hooks() {
+    for hook in HOOKS {
+        if let Err(e) = super::tmux::set_hook(&hook_name(hook), None).await {
+            log::warn!("[follow] Failed to remove hook {}: {}", hook, e);
+        }
+    }
+}
+
+/// Remove the hooks of a run that crashed or quit while following, so tmux
+/// doesn't keep spawning writers for a FIFO nobody reads
+pub async fn remove_stale_hooks() {
+    super::tmux::server_info().await;
+    if super::tmux::supports(super::tmux::Feature::HookArrays) {
+        remove_hooks().await;
+    }
+    let _ = fs::remove_file(focus_fifo());
+}
+
+/// PIDs of the `tmux attach` and control clients MuxTunnel itself spawned
+async fn own_client_pids(sessions: &Arc<tokio::sync::Mutex<PtySessionMap>>) -> HashSet<u32> {
+    sessions
+        .lock()
+        .await
+        .values()
+        .filter_map(|handle| handle.client_pid())
+        .chain(super::tmux_control::client_pids())
+        .collect()
+}
+
+/// Bring a terminal app to the front: AppleScript on macOS, wmctrl elsewhere
+async fn activate_app(app: &str) -> Result<(), String> {
+    let output = if cfg!(target_os = "macos") {
+        let script = format!(
+            "tell application \"{}\" to activate",
+            app.replace('\\', "\\\\").replace('"', "\\\"")
+        );
+        tokio::process::Command::new("osascript")
+            .args(["-e", &script])
+            .output()
+            .await
+    } else {
+        tokio::process::Command::new("wmctrl")
+            .args(["-x", "-a", app])
+            .output()
+            .await
+    };
+    match output {
+        Ok(o) if o.status.success() => Ok(()),
+        Ok(o) => Err(String::from_utf8_lossy(&o.stderr).trim().to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Switch the user's own tmux client (the most recently active one that isn't
+/// MuxTunnel's) to `target`, then bring `app` (default `terminal.externalApp`)
+/// forward when given. Returns the client's name.
+pub async fn focus_external(
+    sessions: &Arc<tokio::sync::Mutex<PtySessionMap>>,
+    target: &str,
+    app: Option<String>,
+) -> Result<String, String> {
+    let own = own_client_pids(sessions).await;
+    let client = super::tmux::list_clients(None)
+        .await?
+        .into_iter()
+        .filter(|c| !c.read_only && !c.pid.is_some_and(|pid| own.contains(&pid)))
+        .max_by_key(|c| c.activity.unwrap_or(0))
+        .ok_or_else(|| "No terminal attached to tmux outside MuxTunnel".to_string())?;
+    super::tmux::switch_client(&client.name, target).await?;
+
+    let app = app.or_else(|| {
+        super::settings::get_settings()
+            .settings
+            .terminal
+            .external_app
+    });
+    if let Some(app) = app.filter(|a| !a.is_empty()) {
+        if let Err(e) = activate_app(&app).await {
+            log::warn!("[follow] Failed to activate {}: {}", app, e);
+        }
+    }
+    Ok(client.name)
+}
+
+/// Read hook lines off the FIFO until follow mode stops. Each hook's `echo`
+/// opens, writes and closes the FIFO, so it is reopened after every EOF.
+fn read_fifo(generation: u64, tx: tokio::sync::mpsc::UnboundedSender<String>) {
+    let _task = super::diagnostics::track("follow-reader");
+    loop {
+        {
+            let state = FOLLOW.lock().unwrap();
+            if !state.enabled || state.generation != generation {
+                break;
+            }
+        }
+        // Blocks until a hook (or `stop`) opens the other end
+        let file = match fs::File::open(focus_fifo()) {
+            Ok(file) => file,
+            Err(e) => {
+                log::warn!("[follow] Failed to open focus FIFO: {}", e);
+                break;
+            }
+        };
+        for line in BufReader::new(file).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if tx.send(line).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Start following the pane the user is focused on in their own terminal
+pub async fn start(sessions: Arc<tokio::sync::Mutex<PtySessionMap>>) -> Result<(), String> {
+    let generation = {
+        let mut state = FOLLOW.lock().unwrap();
+        if state.enabled {
+            return Ok(());
+        }
+        state.enabled = true;
+        state.generation += 1;
+        state.last_pane_id = None;
+        state.generation
+    };
+
+    let fifo = focus_fifo();
+    let created = match fifo.parent().map(fs::create_dir_all) {
+        Some(Err(e)) => Err(format!("Failed to create {}: {}", fifo.display(), e)),
+        _ => super::pane_pipe::make_fifo(&fifo).await,
+    };
+    if let Err(e) = created {
+        FOLLOW.lock().unwrap().enabled = false;
+        return Err(e);
+    }
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::task::spawn_blocking(move || read_fifo(generation, tx));
+
+    if let Err(e) = install_hooks().await {
+        stop().await;
+        return Err(e);
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let _task = super::diagnostics::track("follow-listener");
+        while let Some(line) = rx.recv().await {
+            let mut parts = line.trim().splitn(3, ' ');
+            let (Some(client_pid), Some(pane_id), Some(target)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                continue;
+            };
+
+            if let Ok(pid) = client_pid.parse::<u32>() {
+                if own_client_pids(&sessions).await.contains(&pid) {
+                    continue;
+                }
+            }
+
+            {
+                let mut state = FOLLOW.lock().unwrap();
+                if state.generation != generation
+                    || state.last_pane_id.as_deref() == Some(pane_id)
+                {
+                    continue;
+                }
+                state.last_pane_id = Some(pane_id.to_string());
+            }
+
+            super::events::emit(super::events::BackendEvent::FollowPaneChanged {
+                target: target.to_string(),
+                pane_id: pane_id.to_string(),
+            });
+        }
+    });
+
+    Ok(())
+}
+
+/// Stop following and remove the tmux hooks
+pub async fn stop() {
+    FOLLOW.lock().unwrap().enabled = false;
+    remove_hooks().await;
+    // Wake a reader blocked in open(); fails harmlessly when none is waiting
+    let fifo = focus_fifo();
+    let _ = fs::OpenOptions::new()
+        .write(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(&fifo);
+    let _ = fs::remove_file(&fifo);
+}
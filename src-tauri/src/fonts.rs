@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "ttc", "woff", "woff2"];
+
+/// Family lookups by normalized name, so the font directories are walked
+/// once per family rather than once per `@font-face` request
+static FOUND: once_cell::sync::Lazy<Mutex<HashMap<String, Option<PathBuf>>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// System and user font directories, most specific first
+fn font_dirs() -> Vec<PathBuf> {
+    let home = dirs::home_dir().unwrap_or_default();
+    vec![
+        home.join("Library/Fonts"),
+        PathBuf::from("/Library/Fonts"),
+        PathBuf::from("/System/Library/Fonts"),
+        home.join(".local/share/fonts"),
+        home.join(".fonts"),
+        PathBuf::from("/usr/local/share/fonts"),
+        PathBuf::from("/usr/share/fonts"),
+    ]
+}
+
+fn is_font_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| {
+            let ext = ext.to_string_lossy().to_lowercase();
+            FONT_EXTENSIONS.contains(&ext.as_str())
+        })
+        .unwrap_or(false)
+}
+
+/// Lowercase and drop separators so "JetBrains Mono" matches "JetBrainsMono-Regular"
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Rank a candidate file: exact family match first, then the regular face
+fn match_rank(stem: &str, wanted: &str) -> Option<u8> {
+    let stem = normalize(stem);
+    if stem == wanted {
+        Some(0)
+    } else if stem == format!("{}regular", wanted) {
+        Some(1)
+    } else if stem.starts_with(wanted) {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+fn search(dir: &Path, wanted: &str, depth: u32, best: &mut Option<(u8, PathBuf)>) {
+    if depth > 4 {
+        return;
+    }
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            search(&path, wanted, depth + 1, best);
+            continue;
+        }
+        if !is_font_file(&path) {
+            continue;
+        }
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        if let Some(rank) = match_rank(&stem, wanted) {
+            if best.as_ref().map(|(r, _)| rank < *r).unwrap_or(true) {
+                *best = Some((rank, path));
+            }
+        }
+    }
+}
+
+/// Resolve a font name or path to a font file on disk.
+///
+/// Paths (absolute or `~`-prefixed) are used as-is; family names are looked
+/// up in the system font directories.
+pub fn find_font_file(name: &str) -> Option<PathBuf> {
+    if name.starts_with('/') || name.starts_with('~') {
+        let path = super::settings::expand_tilde(name);
+        return (path.is_file() && is_font_file(&path)).then_some(path);
+    }
+
+    let wanted = normalize(name);
+    if wanted.is_empty() {
+        return None;
+    }
+
+    let cached = FOUND.lock().unwrap().get(&wanted).cloned();
+    match cached {
+        // A font removed since is looked up again
+        Some(Some(path)) if !path.is_file() => {}
+        Some(found) => return found,
+        None => {}
+    }
+
+    // Earlier directories win ties, so user fonts shadow system fonts
+    let mut best = None;
+    for dir in font_dirs() {
+        search(&dir, &wanted, 0, &mut best);
+    }
+    let found = best.map(|(_, path)| path);
+    FOUND.lock().unwrap().insert(wanted, found.clone());
+    found
+}
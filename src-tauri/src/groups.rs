@@ -0,0 +1,56 @@
+use std::path::Path;
+
+/// How `sessions_list` assigns `inferred_group` to sessions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupMode {
+    /// No grouping
+    None,
+    /// Group only by the configured `groups.prefixes`
+    Prefix,
+    /// Configured prefixes first, then fall back to the parent directory
+    Auto,
+}
+
+impl GroupMode {
+    pub fn parse(mode: Option<&str>) -> Result<GroupMode, String> {
+        match mode.unwrap_or("none") {
+            "none" => Ok(GroupMode::None),
+            "prefix" => Ok(GroupMode::Prefix),
+            "auto" => Ok(GroupMode::Auto),
+            other => Err(format!("Unknown grouping mode: {}", other)),
+        }
+    }
+}
+
+/// Infer a group name for a session path.
+///
+/// The longest matching configured prefix wins and is named after its last
+/// path component (`~/work` → `work`). In `Auto` mode, paths outside every
+/// prefix are grouped by their parent directory name.
+pub fn infer(path: &str, prefixes: &[String], mode: GroupMode) -> Option<String> {
+    if mode == GroupMode::None {
+        return None;
+    }
+
+    let path = Path::new(path);
+    let best = prefixes
+        .iter()
+        .map(|p| super::settings::expand_tilde(p))
+        .filter(|prefix| path.starts_with(prefix))
+        .max_by_key(|prefix| prefix.components().count());
+
+    if let Some(prefix) = best {
+        return prefix
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string());
+    }
+
+    if mode == GroupMode::Auto {
+        return path
+            .parent()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string());
+    }
+
+    None
+}
@@ -0,0 +1,236 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::process::Command;
+
+use super::settings::HookSet;
+
+/// tmux paste buffer holding the output of recent hook runs
+pub const LOG_BUFFER: &str = "muxtunnel-hooks";
+
+/// The log buffer keeps only this much of the most recent output
+const MAX_LOG_BYTES: usize = 64 * 1024;
+
+/// Output attached to a `hook-failed` event is cut to this many trailing bytes
+const MAX_EVENT_OUTPUT: usize = 4 * 1024;
+
+/// Longest a session's pre hooks hold up attaching to it; slower hooks keep
+/// running and report failure through `hook-failed` as usual
+pub const ATTACH_WAIT: Duration = Duration::from_secs(3);
+
+static LOG: Mutex<String> = Mutex::new(String::new());
+
+/// `session_key`s of sessions whose first attach is in progress
+static ATTACHING: once_cell::sync::Lazy<Mutex<HashSet<String>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Where hooks fire. There are no `workspaces_activate` / `sessions_open`
+/// commands: `Workspace` hooks run around `workspace_restore`, and `Session`
+/// hooks around the first `pty_connect` to a session that has no PTY open.
+#[derive(Debug, Clone, Copy)]
+pub enum Scope {
+    Session,
+    Workspace,
+}
+
+impl Scope {
+    fn as_str(self) -> &'static str {
+        match self {
+            Scope::Session => "session",
+            Scope::Workspace => "workspace",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Phase {
+    Pre,
+    Post,
+}
+
+impl Phase {
+    fn as_str(self) -> &'static str {
+        match self {
+            Phase::Pre => "pre",
+            Phase::Post => "post",
+        }
+    }
+}
+
+/// Hooks configured for `name` under `hooks.sessions` / `hooks.workspaces`
+fn configured(scope: Scope, name: &str, phase: Phase) -> Vec<String> {
+    let hooks = super::settings::get_settings().settings.hooks;
+    let sets = match scope {
+        Scope::Session => hooks.sessions,
+        Scope::Workspace => hooks.workspaces,
+    };
+    let Some(HookSet { pre, post }) = sets.get(name).cloned() else {
+        return vec![];
+    };
+    match phase {
+        Phase::Pre => pre,
+        Phase::Post => post,
+    }
+}
+
+fn tail(text: &str, max_bytes: usize) -> &str {
+    let mut start = text.len().saturating_sub(max_bytes);
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    &text[start..]
+}
+
+/// Append to the log and mirror it into the tmux buffer on the current server
+async fn log_output(header: &str, output: &str) {
+    let log = {
+        let mut log = LOG.lock().unwrap();
+        log.push_str(header);
+        log.push('\n');
+        log.push_str(output);
+        if !output.is_empty() && !output.ends_with('\n') {
+            log.push('\n');
+        }
+        *log = tail(&log, MAX_LOG_BYTES).to_string();
+        log.clone()
+    };
+    if let Err(e) = super::tmux::set_buffer(Some(LOG_BUFFER), &log).await {
+        log::debug!("[hooks] Failed to update log buffer: {}", e);
+    }
+}
+
+/// Run one hook command through `sh -c`; the exit code and combined output
+async fn run_one(
+    command: &str,
+    scope: Scope,
+    name: &str,
+    phase: Phase,
+    cwd: Option<&str>,
+) -> (Option<i32>, String) {
+    let timeout = Duration::from_secs(super::settings::get_settings().settings.hooks.timeout_secs);
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command])
+        .env("MUXTUNNEL_HOOK_SCOPE", scope.as_str())
+        .env("MUXTUNNEL_HOOK_NAME", name)
+        .env("MUXTUNNEL_HOOK_PHASE", phase.as_str())
+        .kill_on_drop(true);
+    if let Some(cwd) = cwd {
+        cmd.current_dir(cwd);
+    }
+    match tokio::time::timeout(timeout, cmd.output()).await {
+        Ok(Ok(output)) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            (output.status.code(), text)
+        }
+        Ok(Err(e)) => (None, format!("Failed to run hook: {}", e)),
+        Err(_) => (None, format!("Timed out after {}s", timeout.as_secs())),
+    }
+}
+
+/// Run the `phase` hooks for `name` in order on the current server.
+/// Failures are logged and emitted as `hook-failed`; later hooks still run.
+pub async fn run(scope: Scope, name: &str, phase: Phase, cwd: Option<&str>) {
+    for command in configured(scope, name, phase) {
+        let (code, output) = run_one(&command, scope, name, phase, cwd).await;
+        let status = match code {
+            Some(code) => format!("exit {}", code),
+            None => "failed".to_string(),
+        };
+        log_output(
+            &format!(
+                "== {} {} {}: {} ({})",
+                scope.as_str(),
+                name,
+                phase.as_str(),
+                command,
+                status
+            ),
+            &output,
+        )
+        .await;
+        if code == Some(0) {
+            continue;
+        }
+        log::warn!(
+            "[hooks] {} {} {} hook failed ({}): {}",
+            scope.as_str(),
+            name,
+            phase.as_str(),
+            status,
+            command
+        );
+        super::events::emit(super::events::BackendEvent::HookFailed {
+            scope: scope.as_str().to_string(),
+            name: name.to_string(),
+            phase: phase.as_str().to_string(),
+            command,
+            exit_code: code,
+            output: tail(&output, MAX_EVENT_OUTPUT).to_string(),
+        });
+    }
+}
+
+/// The first attach of a session, held until that attach has finished
+pub struct AttachClaim(String);
+
+impl Drop for AttachClaim {
+    fn drop(&mut self) {
+        ATTACHING.lock().unwrap().remove(&self.0);
+    }
+}
+
+/// Claim the first attach of the session at `key` unless it's `attached`
+/// already or another attach holds the claim. Call with the PTY map locked so
+/// the check and the claim happen together.
+pub fn claim_first_attach(key: &str, attached: bool) -> Option<AttachClaim> {
+    let mut attaching = ATTACHING.lock().unwrap();
+    if attached || !attaching.insert(key.to_string()) {
+        return None;
+    }
+    Some(AttachClaim(key.to_string()))
+}
+
+fn start(
+    scope: Scope,
+    name: &str,
+    phase: Phase,
+    cwd: Option<String>,
+) -> Option<tauri::async_runtime::JoinHandle<()>> {
+    if configured(scope, name, phase).is_empty() {
+        return None;
+    }
+    let server = super::tmux::current_server();
+    let name = name.to_string();
+    Some(tauri::async_runtime::spawn(async move {
+        let _task = super::diagnostics::track("hooks");
+        super::tmux::with_server(server, run(scope, &name, phase, cwd.as_deref())).await;
+    }))
+}
+
+/// `run` in the background, on the server current at the time of the call
+pub fn spawn(scope: Scope, name: &str, phase: Phase, cwd: Option<String>) {
+    start(scope, name, phase, cwd);
+}
+
+/// `spawn`, waiting at most `wait` for the hooks to finish
+pub async fn run_within(
+    scope: Scope,
+    name: &str,
+    phase: Phase,
+    cwd: Option<String>,
+    wait: Duration,
+) {
+    let Some(handle) = start(scope, name, phase, cwd) else {
+        return;
+    };
+    if tokio::time::timeout(wait, handle).await.is_err() {
+        log::info!(
+            "[hooks] {} {} {} hooks still running after {}s; not waiting for them",
+            scope.as_str(),
+            name,
+            phase.as_str(),
+            wait.as_secs()
+        );
+    }
+}
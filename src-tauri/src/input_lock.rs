@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::tmux::{pane_of, session_of};
+
+/// Locks not renewed within this long are dropped, so a crashed holder can't wedge a pane
+const DEFAULT_TTL: Duration = Duration::from_secs(30);
+
+/// Holder used for the automatic lock taken while `panes_input` plays text into a pane
+const PLAYBACK_HOLDER: &str = "muxtunnel:playback";
+
+static LOCKS: once_cell::sync::Lazy<Mutex<HashMap<String, InputLock>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Prefix of the error returned for input to a locked session, for clients to match on
+pub const SESSION_LOCKED: &str = "session-locked";
+
+/// Sessions locked against all input, persisted so a restart doesn't unlock them
+static SESSION_LOCKS: once_cell::sync::Lazy<Mutex<Vec<SessionLock>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(load_session_locks()));
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionLock {
+    pub server: Option<String>,
+    pub session: String,
+}
+
+fn session_locks_path() -> std::path::PathBuf {
+    super::paths::data_dir().join("session-locks.json")
+}
+
+fn load_session_locks() -> Vec<SessionLock> {
+    std::fs::read_to_string(session_locks_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_session_locks(locks: &[SessionLock]) -> Result<(), String> {
+    let path = session_locks_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(locks).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to save session locks: {}", e))
+}
+
+/// Lock `session` on the current server against all input until unlocked
+pub fn lock_session(session: &str) -> Result<(), String> {
+    let lock = SessionLock {
+        server: super::tmux::current_server(),
+        session: session.to_string(),
+    };
+    let mut locks = SESSION_LOCKS.lock().unwrap();
+    if !locks.contains(&lock) {
+        locks.push(lock);
+        save_session_locks(&locks)?;
+    }
+    Ok(())
+}
+
+pub fn unlock_session(session: &str) -> Result<(), String> {
+    let server = super::tmux::current_server();
+    let mut locks = SESSION_LOCKS.lock().unwrap();
+    let before = locks.len();
+    locks.retain(|l| !(l.server == server && l.session == session));
+    if locks.len() != before {
+        save_session_locks(&locks)?;
+    }
+    Ok(())
+}
+
+pub fn locked_sessions() -> Vec<SessionLock> {
+    SESSION_LOCKS.lock().unwrap().clone()
+}
+
+/// Error if `session`, the session of `target`, is locked on the current
+/// server; holders don't bypass this
+fn check_session(session: &str) -> Result<(), String> {
+    let server = super::tmux::current_server();
+    let locked = SESSION_LOCKS
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|l| l.server == server && l.session == session);
+    if locked {
+        return Err(format!(
+            "{}: Session {} is locked against input",
+            SESSION_LOCKED, session
+        ));
+    }
+    Ok(())
+}
+
+struct InputLock {
+    server: Option<String>,
+    target: String,
+    /// Session of `target`, resolved when the lock was taken
+    session: String,
+    holder: String,
+    expires_at: Instant,
+}
+
+fn lock_key(server: Option<&str>, target: &str) -> String {
+    super::pty_manager::session_key(server, target)
+}
+
+/// Whether a lock on `locked` covers input to `target`. Session-level targets
+/// (PTY attaches) overlap every pane in that session; pane targets only
+/// overlap themselves, whether addressed by name or by `%pane_id`.
+fn overlaps(locked: &InputLock, target: &str, session: &str) -> bool {
+    if locked.target == target {
+        return true;
+    }
+    if let (Some(a), Some(b)) = (pane_of(&locked.target), pane_of(target)) {
+        return a == b;
+    }
+    let session_level = |t: &str| !t.contains(':') && !t.starts_with(['%', '@']);
+    (session_level(&locked.target) || session_level(target)) && locked.session == session
+}
+
+/// Holder of a live lock that blocks `holder` from writing to `target` (in `session`)
+fn blocking_holder(
+    locks: &mut HashMap<String, InputLock>,
+    server: Option<&str>,
+    target: &str,
+    session: &str,
+    holder: Option<&str>,
+) -> Option<String> {
+    let now = Instant::now();
+    locks.retain(|_, lock| lock.expires_at > now);
+    locks
+        .values()
+        .find(|lock| {
+            lock.server.as_deref() == server
+                && overlaps(lock, target, session)
+                && Some(lock.holder.as_str()) != holder
+        })
+        .map(|lock| lock.holder.clone())
+}
+
+/// Take or renew the advisory input lock on `target` for `holder`
+pub async fn acquire(target: &str, holder: &str, ttl: Option<Duration>) -> Result<(), String> {
+    let session = session_of(target).await;
+    acquire_in(target, &session, holder, ttl)
+}
+
+fn acquire_in(
+    target: &str,
+    session: &str,
+    holder: &str,
+    ttl: Option<Duration>,
+) -> Result<(), String> {
+    let server = super::tmux::current_server();
+    let mut locks = LOCKS.lock().unwrap();
+    let blocking = blocking_holder(&mut locks, server.as_deref(), target, session, Some(holder));
+    if let Some(other) = blocking {
+        return Err(format!("Pane {} is locked by {}", target, other));
+    }
+    locks.insert(
+        lock_key(server.as_deref(), target),
+        InputLock {
+            server,
+            target: target.to_string(),
+            session: session.to_string(),
+            holder: holder.to_string(),
+            expires_at: Instant::now() + ttl.unwrap_or(DEFAULT_TTL),
+        },
+    );
+    Ok(())
+}
+
+/// Release `holder`'s lock on `target` (no-op if someone else holds it)
+pub fn release(target: &str, holder: &str) {
+    let key = lock_key(super::tmux::current_server().as_deref(), target);
+    let mut locks = LOCKS.lock().unwrap();
+    if locks.get(&key).map(|l| l.holder == holder).unwrap_or(false) {
+        locks.remove(&key);
+    }
+}
+
+/// Error if `target`'s session is locked or another holder has locked `target`
+pub async fn check(target: &str, holder: Option<&str>) -> Result<(), String> {
+    let session = session_of(target).await;
+    check_in(target, &session, holder)
+}
+
+fn check_in(target: &str, session: &str, holder: Option<&str>) -> Result<(), String> {
+    check_session(session)?;
+    let server = super::tmux::current_server();
+    let mut locks = LOCKS.lock().unwrap();
+    match blocking_holder(&mut locks, server.as_deref(), target, session, holder) {
+        Some(other) => Err(format!("Pane {} is locked by {}", target, other)),
+        None => Ok(()),
+    }
+}
+
+/// Lock held for the duration of a multi-step write, released on drop
+pub struct PlaybackGuard {
+    target: String,
+    holder: String,
+    /// False when the caller already held the lock; theirs is left in place
+    acquired: bool,
+    server: Option<String>,
+}
+
+/// Lock `target` while text is played into it, unless `holder` already holds it
+pub async fn hold(target: &str, holder: Option<&str>) -> Result<PlaybackGuard, String> {
+    let session = session_of(target).await;
+    check_in(target, &session, holder)?;
+    let server = super::tmux::current_server();
+    let key = lock_key(server.as_deref(), target);
+    let already_held = holder
+        .map(|h| {
+            LOCKS
+                .lock()
+                .unwrap()
+                .get(&key)
+                .map(|l| l.holder == h)
+                .unwrap_or(false)
+        })
+        .unwrap_or(false);
+    let holder = holder.unwrap_or(PLAYBACK_HOLDER).to_string();
+    if !already_held {
+        acquire_in(target, &session, &holder, None)?;
+    }
+    Ok(PlaybackGuard {
+        target: target.to_string(),
+        holder,
+        acquired: !already_held,
+        server,
+    })
+}
+
+impl Drop for PlaybackGuard {
+    fn drop(&mut self) {
+        if !self.acquired {
+            return;
+        }
+        let key = lock_key(self.server.as_deref(), &self.target);
+        let mut locks = LOCKS.lock().unwrap();
+        if locks
+            .get(&key)
+            .map(|l| l.holder == self.holder)
+            .unwrap_or(false)
+        {
+            locks.remove(&key);
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Layout name → tmux layout string (as printed by `#{window_layout}`)
+type LayoutLibrary = BTreeMap<String, String>;
+
+fn layouts_file() -> PathBuf {
+    super::paths::config_dir().join("layouts.json")
+}
+
+fn load_library() -> LayoutLibrary {
+    match fs::read_to_string(layouts_file()) {
+        Ok(raw) => serde_json::from_str(&raw).unwrap_or_default(),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+fn save_library(library: &LayoutLibrary) -> Result<(), String> {
+    let path = layouts_file();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let json = serde_json::to_string_pretty(library)
+        .map_err(|e| format!("Failed to serialize layouts: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to save layouts: {}", e))
+}
+
+pub fn list() -> LayoutLibrary {
+    load_library()
+}
+
+/// Save the current layout of `target`'s window under `name`
+pub async fn save(target: &str, name: &str) -> Result<String, String> {
+    let layout = super::tmux::window_layout(target).await?;
+    let mut library = load_library();
+    library.insert(name.to_string(), layout.clone());
+    save_library(&library)?;
+    Ok(layout)
+}
+
+/// Apply a saved layout to `target`'s window
+pub async fn apply(target: &str, name: &str) -> Result<(), String> {
+    let layout = load_library()
+        .remove(name)
+        .ok_or_else(|| format!("No saved layout named: {}", name))?;
+    super::tmux::select_layout(target, &layout).await
+}
+
+pub fn delete(name: &str) -> Result<(), String> {
+    let mut library = load_library();
+    if library.remove(name).is_none() {
+        return Err(format!("No saved layout named: {}", name));
+    }
+    save_library(&library)
+}
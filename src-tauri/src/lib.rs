@@ -1,67 +1,380 @@
+mod activity;
+mod capabilities;
 mod claude_sessions;
+mod clipboard;
 mod commands;
+mod demo;
+mod diagnostics;
+mod events;
+mod export;
+mod features;
+mod files;
+mod focus;
+mod follow;
+mod fonts;
+mod groups;
+mod hooks;
+mod input_lock;
+mod layouts;
+mod links;
+mod memory;
+mod multiplexer;
+mod notifications;
+mod osc;
+mod pane_activity;
+mod pane_env;
+mod pane_log;
+mod pane_meta;
+mod pane_pipe;
+mod pairs;
+mod paths;
+mod previews;
+mod probes;
+mod prompt_queue;
 mod pty_manager;
+mod recently_closed;
+mod recording;
+mod report;
 mod resolver;
+mod screen;
+mod search;
 mod session_order;
 mod settings;
+mod shell_integration;
+mod state_check;
+mod templates;
 mod tmux;
+mod tmux_config;
+mod tmux_control;
+mod visibility;
+mod watches;
+mod wezterm;
+mod window_title;
+mod wire;
+mod workspace;
+mod zellij;
 
+use std::path::PathBuf;
 use std::sync::Arc;
+use tauri::Manager;
 use tokio::sync::Mutex;
 
-/// Shared application state managed by Tauri
+/// Shared application state managed by Tauri.
+///
+/// The resolver, Claude tracker and session order are built around the
+/// settings store and directories passed in, so tests can give them their
+/// own. The rest of the backend still reads settings through the store
+/// `settings::install` registers, and keeps its state in module statics.
 pub struct AppState {
     pub pty_sessions: Arc<Mutex<pty_manager::PtySessionMap>>,
+    pub settings: Arc<settings::SettingsStore>,
+    pub resolver: Arc<resolver::Resolver>,
+    pub claude: Arc<claude_sessions::ClaudeTracker>,
+    pub session_order: Arc<session_order::SessionOrder>,
+}
+
+impl AppState {
+    /// Services keeping history and session order in `data_dir`,
+    /// tracking Claude sessions under `claude_projects_dir`. In safe mode the
+    /// saved session order isn't read.
+    pub fn new(
+        settings: Arc<settings::SettingsStore>,
+        data_dir: PathBuf,
+        claude_projects_dir: PathBuf,
+        safe_mode: bool,
+    ) -> Self {
+        let session_order = if safe_mode {
+            session_order::SessionOrder::empty(data_dir.clone())
+        } else {
+            session_order::SessionOrder::load(data_dir.clone())
+        };
+        Self {
+            pty_sessions: Arc::new(Mutex::new(pty_manager::PtySessionMap::new())),
+            resolver: Arc::new(resolver::Resolver::new(data_dir.clone(), settings.clone())),
+            claude: Arc::new(claude_sessions::ClaudeTracker::new(claude_projects_dir)),
+            session_order: Arc::new(session_order),
+            settings,
+        }
+    }
+}
+
+/// Started with `--safe-mode` or `MUXTUNNEL_SAFE_MODE=1`: default settings, no
+/// state files, watchers or resolver, and only the core commands, so a
+/// corrupt file or pathological home directory can't keep the app from opening
+pub(crate) fn safe_mode() -> bool {
+    std::env::var("MUXTUNNEL_SAFE_MODE").is_ok_and(|v| !v.is_empty() && v != "0")
+        || std::env::args().any(|a| a == "--safe-mode")
 }
 
 pub fn run() {
     env_logger::init();
+    let safe_mode = safe_mode();
+    if safe_mode {
+        log::warn!("Starting in safe mode");
+    }
 
-    let state = AppState {
-        pty_sessions: Arc::new(Mutex::new(pty_manager::PtySessionMap::new())),
-    };
+    // Copy ~/.muxtunnel into the XDG directories before anything reads them
+    if !safe_mode {
+        paths::migrate_legacy_dir();
+    }
+
+    // Settings come first: `paths.dataDir` / `paths.claudeDir` decide where the rest lives
+    let settings = Arc::new(settings::SettingsStore::new(paths::config_dir()));
+    if !safe_mode {
+        settings.load();
+    }
+    settings::install(settings.clone());
+    features::init();
 
-    tauri::Builder::default()
+    // Before any module loads its state file
+    if !safe_mode {
+        state_check::check();
+    }
+
+    let state = AppState::new(
+        settings.clone(),
+        paths::data_dir(),
+        paths::claude_projects_dir(),
+        safe_mode,
+    );
+    let pty_sessions = state.pty_sessions.clone();
+    let resolver = state.resolver.clone();
+    let claude = state.claude.clone();
+    let session_order = state.session_order.clone();
+
+    let builder = tauri::Builder::default()
+        .plugin(tauri_plugin_clipboard_manager::init())
         .manage(state)
-        .setup(|app| {
+        .on_window_event(|window, event| {
+            // Focus in/out for the focused terminal view as the app is switched to and away from
+            if let tauri::WindowEvent::Focused(focused) = event {
+                let sessions = window.state::<AppState>().pty_sessions.clone();
+                let focused = *focused;
+                visibility::set_app_focused(focused);
+                tauri::async_runtime::spawn(async move {
+                    pty_manager::window_focus(&sessions, focused).await;
+                });
+            }
+        })
+        .setup(move |app| {
             let app_handle = app.handle().clone();
+            events::init(app_handle.clone());
+            window_title::init(app_handle.clone());
+
+            // Detect external tools so missing ones are logged up front
+            capabilities::refresh();
+
+            // Ask the login shell where tmux is, detect again with its answer,
+            // then cache the tmux version so version-specific flags can be gated
+            tauri::async_runtime::spawn(async move {
+                tmux::resolve_shell_tmux().await;
+                capabilities::refresh();
+                tmux::server_info().await;
+                // Pane logs that were running when the app last quit
+                if !safe_mode {
+                    pane_log::restore().await;
+                }
+            });
+
+            // Drop PTY entries whose reader, client or target died
+            pty_manager::start_sweeper(pty_sessions.clone());
+            // Orphan PTYs whose views stopped answering pings
+            pty_manager::start_heartbeat(pty_sessions.clone());
+            // Resize views whose tmux window was sized for another client
+            pty_manager::start_size_sync(pty_sessions.clone());
+
+            if safe_mode {
+                log::info!("MuxTunnel native app initialized (safe mode)");
+                return Ok(());
+            }
 
             // Start Claude session watching in background
             tauri::async_runtime::spawn(async move {
-                claude_sessions::start_watching(app_handle).await;
+                claude.start_watching(app_handle).await;
             });
 
             // Start settings watching
-            settings::start_watching();
+            settings.start_watching();
 
-            // Load session order
-            session_order::load();
+            // Follow mode hooks left behind by a previous run
+            tauri::async_runtime::spawn(follow::remove_stale_hooks());
+
+            // Pre-capture previews of sessions next to the viewed one
+            previews::start_warmer(session_order.clone());
+
+            // Optionally push saved window order into tmux
+            if settings.get().settings.tmux.apply_window_order {
+                tauri::async_runtime::spawn(async move {
+                    session_order.apply_window_order().await;
+                });
+            }
 
             // Init resolvers
-            let resolver_setting = settings::get_settings().settings.resolver.clone();
-            resolver::init(&resolver_setting);
+            let resolver_setting = settings.get().settings.resolver;
+            resolver.init(&resolver_setting);
+
+            // Log resource usage that crosses leak thresholds
+            diagnostics::start_monitor(pty_sessions);
+
+            // Track which panes are producing output
+            pane_activity::start_sampler();
 
             log::info!("MuxTunnel native app initialized");
             Ok(())
-        })
-        .invoke_handler(tauri::generate_handler![
-            commands::sessions_list,
-            commands::sessions_create,
-            commands::sessions_delete,
-            commands::panes_delete,
-            commands::panes_input,
-            commands::panes_interrupt,
-            commands::projects_list,
-            commands::projects_resolve,
-            commands::claude_mark_viewed,
-            commands::session_order_get,
-            commands::session_order_save,
-            commands::settings_get,
-            commands::pty_connect,
-            commands::pty_send,
-            commands::pty_close,
-            commands::asset_background,
-        ])
+        });
+
+    // Safe mode registers only what's needed to see sessions and diagnose
+    let builder = if safe_mode {
+        builder
+            .invoke_handler(tauri::generate_handler![
+                commands::sessions_list,
+                commands::sessions_create,
+                commands::sessions_delete,
+                commands::panes_input,
+                commands::panes_send_keys,
+                commands::panes_interrupt,
+                commands::settings_get,
+                commands::tmux_config_issues,
+                commands::capabilities_get,
+                commands::features_get,
+                commands::health_check,
+                commands::diagnostics,
+                commands::safe_mode_get,
+                commands::pty_connect,
+                commands::pty_shell,
+                commands::pty_send,
+                commands::pty_paste,
+                commands::pty_focus,
+                commands::pty_ack,
+                commands::pty_pong,
+                commands::pty_detach,
+                commands::pty_close,
+                commands::pty_close_all,
+                commands::pty_list,
+                commands::pty_gc,
+                commands::events_schema,
+                commands::memory_usage,
+            ])
+    } else {
+        builder
+            .invoke_handler(tauri::generate_handler![
+                commands::sessions_list,
+                commands::sessions_create,
+                commands::sessions_delete,
+                commands::sessions_create_grouped,
+                commands::sessions_recently_closed,
+                commands::sessions_reopen,
+                commands::templates_list,
+                commands::sessions_create_from_template,
+                commands::windows_delete,
+                commands::windows_move,
+                commands::panes_delete,
+                commands::windows_set_layout,
+                commands::layouts_list,
+                commands::layouts_save,
+                commands::layouts_apply,
+                commands::layouts_delete,
+                commands::panes_identify,
+                commands::clients_list,
+                commands::clients_detach,
+                commands::popups_open,
+                commands::panes_break,
+                commands::panes_join,
+                commands::panes_swap,
+                commands::panes_capture,
+                commands::panes_search,
+                commands::panes_links,
+                commands::panes_input,
+                commands::panes_send_keys,
+                commands::panes_broadcast,
+                commands::windows_synchronize,
+                commands::sessions_lock,
+                commands::sessions_unlock,
+                commands::sessions_locks,
+                commands::panes_input_lock,
+                commands::panes_input_unlock,
+                commands::panes_interrupt,
+                commands::panes_respawn,
+                commands::panes_copy_mode,
+                commands::panes_command_history,
+                commands::panes_log_start,
+                commands::panes_log_stop,
+                commands::panes_watch_expr,
+                commands::panes_unwatch,
+                commands::panes_watches,
+                commands::pairs_create,
+                commands::pairs_delete,
+                commands::pairs_list,
+                commands::panes_env,
+                commands::panes_meta_get,
+                commands::panes_meta_set,
+                commands::panes_set_title,
+                commands::clipboard_write,
+                commands::clipboard_read,
+                commands::panes_copy_output,
+                commands::panes_export,
+                commands::previews_get,
+                commands::previews_invalidate,
+                commands::panes_preview,
+                commands::buffers_list,
+                commands::buffers_read,
+                commands::buffers_set,
+                commands::panes_paste_buffer,
+                commands::projects_list,
+                commands::projects_resolve,
+                commands::projects_import_dir,
+                commands::claude_mark_viewed,
+                commands::claude_queue_prompt,
+                commands::claude_queue_list,
+                commands::claude_queue_cancel,
+                commands::session_order_get,
+                commands::session_order_save,
+                commands::settings_get,
+                commands::tmux_config_issues,
+                commands::files_read,
+                commands::capabilities_get,
+                commands::features_get,
+                commands::health_check,
+                commands::diagnostics,
+                commands::state_check,
+                commands::safe_mode_get,
+                commands::follow_start,
+                commands::follow_stop,
+                commands::focus_external_client,
+                commands::pty_connect,
+                commands::pty_shell,
+                commands::pty_send,
+                commands::pty_paste,
+                commands::pty_focus,
+                commands::visibility_report,
+                commands::pty_reclaim,
+                commands::pty_ack,
+                commands::pty_pong,
+                commands::pty_detach,
+                commands::pty_record_start,
+                commands::pty_record_stop,
+                commands::pty_play,
+                commands::pty_transport_benchmark,
+                commands::pty_close,
+                commands::pty_close_all,
+                commands::pty_list,
+                commands::pty_gc,
+                commands::asset_background,
+                commands::asset_font,
+                commands::events_schema,
+                commands::report_generate,
+                commands::focus_report,
+                commands::stats_focus,
+                commands::memory_usage,
+                commands::notify,
+                commands::sounds_preview,
+                commands::workspace_save,
+                commands::workspace_list,
+                commands::workspace_restore,
+            ])
+    };
+
+    builder
         .run(tauri::generate_context!())
         .expect("error while running MuxTunnel");
 }
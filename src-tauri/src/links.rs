@@ -0,0 +1,179 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use super::settings::LinkPattern;
+
+/// Remembered links per `session_key` of a target, so each one is only announced once
+const MAX_SEEN_PER_TARGET: usize = 500;
+
+static SEEN: once_cell::sync::Lazy<Mutex<HashMap<String, HashSet<String>>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(HashMap::new()));
+
+static ANSI: once_cell::sync::Lazy<regex::Regex> = once_cell::sync::Lazy::new(|| {
+    regex::Regex::new(r"\x1b\[[0-9;?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)").unwrap()
+});
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct Link {
+    pub kind: String,
+    pub text: String,
+    /// Where the link points when that differs from `text` (e.g. issue references)
+    pub url: Option<String>,
+}
+
+fn default_patterns() -> Vec<LinkPattern> {
+    let pattern = |kind: &str, regex: &str| LinkPattern {
+        kind: kind.to_string(),
+        regex: regex.to_string(),
+        url: None,
+    };
+    vec![
+        pattern("url", r#"https?://[^\s<>"'`)\]]+"#),
+        pattern("path", r"(?:~|\.{1,2})?/[\w.@+-]+(?:/[\w.@+-]+)+(?::\d+(?::\d+)?)?"),
+        pattern("issue", r"(?:\b[\w.-]+/[\w.-]+)?#\d+\b"),
+    ]
+}
+
+/// A pattern with its regex compiled
+type Compiled = (LinkPattern, regex::Regex);
+
+/// Compiled default and `links` patterns
+struct Patterns {
+    /// Default patterns plus `links.patterns`
+    global: Vec<Compiled>,
+    /// `links.projects`, with the project paths expanded
+    projects: Vec<(std::path::PathBuf, Vec<Compiled>)>,
+}
+
+/// `Patterns` for the settings version they were compiled from
+static PATTERNS: Mutex<Option<(u32, Arc<Patterns>)>> = Mutex::new(None);
+
+fn compile(patterns: Vec<LinkPattern>) -> Vec<Compiled> {
+    patterns
+        .into_iter()
+        .filter_map(|p| match regex::Regex::new(&p.regex) {
+            Ok(re) => Some((p, re)),
+            Err(e) => {
+                log::warn!("[links] Invalid {} pattern {}: {}", p.kind, p.regex, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// The compiled patterns, recompiled only when the settings changed
+fn patterns() -> Arc<Patterns> {
+    let version = super::settings::settings_version();
+    let mut cached = PATTERNS.lock().unwrap();
+    if let Some((compiled_version, patterns)) = cached.as_ref() {
+        if *compiled_version == version {
+            return patterns.clone();
+        }
+    }
+    let settings = super::settings::get_settings().settings.links;
+    let mut global = default_patterns();
+    global.extend(settings.patterns);
+    let patterns = Arc::new(Patterns {
+        global: compile(global),
+        projects: settings
+            .projects
+            .into_iter()
+            .map(|(project, patterns)| {
+                (super::settings::expand_tilde(&project), compile(patterns))
+            })
+            .collect(),
+    });
+    *cached = Some((version, patterns.clone()));
+    patterns
+}
+
+/// Default patterns plus `links.patterns`, plus the patterns of every
+/// `links.projects` entry containing `cwd`
+fn patterns_for<'a>(
+    patterns: &'a Patterns,
+    cwd: Option<&'a str>,
+) -> impl Iterator<Item = &'a Compiled> + 'a {
+    let projects = patterns
+        .projects
+        .iter()
+        .filter(move |(project, _)| {
+            cwd.is_some_and(|cwd| std::path::Path::new(cwd).starts_with(project))
+        })
+        .flat_map(|(_, patterns)| patterns);
+    patterns.global.iter().chain(projects)
+}
+
+/// `text` without CSI and OSC escape sequences
+pub fn strip_ansi(text: &str) -> std::borrow::Cow<'_, str> {
+    ANSI.replace_all(text, "")
+}
+
+/// Every distinct link in `text`, in order of first appearance
+pub fn scan(text: &str, cwd: Option<&str>) -> Vec<Link> {
+    let text = strip_ansi(text);
+    let mut seen = HashSet::new();
+    let mut links = Vec::new();
+    let patterns = patterns();
+    for (pattern, re) in patterns_for(&patterns, cwd) {
+        for caps in re.captures_iter(&text) {
+            let matched = caps[0].trim_end_matches(['.', ',', ';', ':']);
+            let url = pattern.url.as_ref().map(|template| {
+                let mut url = String::new();
+                caps.expand(template, &mut url);
+                url
+            });
+            let link = Link {
+                kind: pattern.kind.clone(),
+                text: matched.to_string(),
+                url,
+            };
+            if seen.insert(link.clone()) {
+                links.push(link);
+            }
+        }
+    }
+    links
+}
+
+/// Scan new output from `target` on `server` and emit `links-found` for
+/// links not seen before
+pub fn observe(server: Option<&str>, target: &str, text: &str, cwd: Option<&str>) {
+    announce(server, target, scan(text, cwd));
+}
+
+fn announce(server: Option<&str>, target: &str, links: Vec<Link>) {
+    let fresh: Vec<Link> = {
+        let mut seen = SEEN.lock().unwrap();
+        let key = super::pty_manager::session_key(server, target);
+        let seen = seen.entry(key).or_default();
+        if seen.len() > MAX_SEEN_PER_TARGET {
+            seen.clear();
+        }
+        links
+            .into_iter()
+            .filter(|link| seen.insert(link.text.clone()))
+            .collect()
+    };
+    if !fresh.is_empty() {
+        super::events::emit(super::events::BackendEvent::LinksFound {
+            server: server.map(str::to_string),
+            target: target.to_string(),
+            links: fresh,
+        });
+    }
+}
+
+/// Links in a pane's visible screen and scrollback
+pub async fn pane_links(target: &str) -> Result<Vec<Link>, String> {
+    let (capture, cwd) = tokio::join!(
+        super::tmux::capture_history(target),
+        super::tmux::get_pane_cwd(target)
+    );
+    let capture = capture?;
+    let links = scan(&capture.content, cwd.as_deref());
+    let server = super::tmux::current_server();
+    announce(server.as_deref(), target, links.clone());
+    Ok(links)
+}
@@ -0,0 +1,163 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+/// Subsystems holding buffers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Subsystem {
+    /// Output kept for orphaned PTYs until `reclaim`, and PTY scrollback
+    PtyBacklog,
+    /// Scrollback of PTYs evicted past `terminal.maxPtys`
+    EvictedScrollback,
+    Previews,
+    /// VT screens of open PTYs; they can't shrink while the PTY is open
+    Screens,
+    /// Write buffers of recordings in progress
+    Recordings,
+}
+
+const SUBSYSTEMS: [Subsystem; 5] = [
+    Subsystem::PtyBacklog,
+    Subsystem::EvictedScrollback,
+    Subsystem::Previews,
+    Subsystem::Screens,
+    Subsystem::Recordings,
+];
+
+impl Subsystem {
+    fn name(self) -> &'static str {
+        match self {
+            Subsystem::PtyBacklog => "ptyBacklog",
+            Subsystem::EvictedScrollback => "evictedScrollback",
+            Subsystem::Previews => "previews",
+            Subsystem::Screens => "screens",
+            Subsystem::Recordings => "recordings",
+        }
+    }
+
+    /// Whether `evict` frees this subsystem's buffers; the others count
+    /// towards the limit but are left alone
+    fn evictable(self) -> bool {
+        !matches!(self, Subsystem::Screens | Subsystem::Recordings)
+    }
+}
+
+/// One evictable buffer, as reported by its subsystem
+pub struct Buffer {
+    pub key: String,
+    pub bytes: usize,
+    pub last_used: Instant,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BufferUsage {
+    pub subsystem: String,
+    pub key: String,
+    pub bytes: usize,
+    /// Seconds since the buffer was last written or read
+    pub idle_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryUsage {
+    /// `memory.maxBufferBytes`; 0 means unlimited
+    pub limit_bytes: usize,
+    pub total_bytes: usize,
+    /// Subsystem → bytes held
+    pub subsystems: BTreeMap<String, usize>,
+    /// Largest first
+    pub buffers: Vec<BufferUsage>,
+}
+
+fn collect() -> Vec<(Subsystem, Buffer)> {
+    SUBSYSTEMS
+        .iter()
+        .flat_map(|&subsystem| {
+            let buffers = match subsystem {
+                Subsystem::PtyBacklog => super::pty_manager::buffers(),
+                Subsystem::EvictedScrollback => super::pty_manager::evicted_buffers(),
+                Subsystem::Previews => super::previews::buffers(),
+                Subsystem::Screens => super::screen::buffers(),
+                Subsystem::Recordings => super::recording::buffers(),
+            };
+            buffers.into_iter().map(move |b| (subsystem, b))
+        })
+        .collect()
+}
+
+fn evict(subsystem: Subsystem, key: &str) {
+    match subsystem {
+        Subsystem::PtyBacklog => super::pty_manager::evict_buffer(key),
+        Subsystem::EvictedScrollback => super::pty_manager::evict_evicted(key),
+        Subsystem::Previews => super::previews::evict(key),
+        Subsystem::Screens | Subsystem::Recordings => {}
+    }
+}
+
+/// Evict least recently used buffers, across all subsystems and PTYs, until
+/// the total is within `memory.maxBufferBytes`
+pub fn enforce() {
+    let limit = super::settings::get_settings()
+        .settings
+        .memory
+        .max_buffer_bytes;
+    if limit == 0 {
+        return;
+    }
+    let mut buffers = collect();
+    let mut total: usize = buffers.iter().map(|(_, b)| b.bytes).sum();
+    if total <= limit {
+        return;
+    }
+    buffers.sort_by_key(|(_, b)| b.last_used);
+    for (subsystem, buffer) in buffers {
+        if total <= limit {
+            break;
+        }
+        if !subsystem.evictable() {
+            continue;
+        }
+        log::info!(
+            "[memory] Evicting {} {} ({} KiB) to stay under {} KiB",
+            subsystem.name(),
+            buffer.key,
+            buffer.bytes / 1024,
+            limit / 1024
+        );
+        evict(subsystem, &buffer.key);
+        total -= buffer.bytes;
+    }
+}
+
+/// Bytes held per subsystem and buffer
+pub fn usage() -> MemoryUsage {
+    let limit_bytes = super::settings::get_settings()
+        .settings
+        .memory
+        .max_buffer_bytes;
+    let mut subsystems: BTreeMap<String, usize> = SUBSYSTEMS
+        .iter()
+        .map(|s| (s.name().to_string(), 0))
+        .collect();
+    let mut buffers: Vec<BufferUsage> = collect()
+        .into_iter()
+        .map(|(subsystem, b)| {
+            *subsystems.entry(subsystem.name().to_string()).or_default() += b.bytes;
+            BufferUsage {
+                subsystem: subsystem.name().to_string(),
+                key: b.key,
+                bytes: b.bytes,
+                idle_secs: b.last_used.elapsed().as_secs(),
+            }
+        })
+        .collect();
+    buffers.sort_by_key(|b| std::cmp::Reverse(b.bytes));
+    MemoryUsage {
+        limit_bytes,
+        total_bytes: subsystems.values().sum(),
+        subsystems,
+        buffers,
+    }
+}
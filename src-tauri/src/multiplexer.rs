@@ -0,0 +1,201 @@
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use serde::Serialize;
+use std::path::Path;
+
+use super::capabilities::Tool;
+use super::templates::TemplateWindow;
+use super::tmux::{TmuxPane, TmuxSession};
+
+/// `server` value under which WezTerm panes are listed and addressed
+pub const WEZTERM_SERVER: &str = "wezterm";
+
+/// Program a PTY runs to attach to a session
+#[derive(Debug, Clone)]
+pub struct AttachCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+}
+
+/// Outcome of input broadcast to one pane
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastResult {
+    pub target: String,
+    /// Why input couldn't be sent to this pane
+    pub error: Option<String>,
+}
+
+/// What MuxTunnel needs from a terminal multiplexer to list, create, kill,
+/// type into and attach to its sessions. Sessions are reported in tmux's
+/// shape; backends without windows or panes report one pane per session,
+/// addressed by the session name.
+pub trait Multiplexer: Send + Sync {
+    /// `multiplexer.backend` value selecting this backend
+    fn name(&self) -> &'static str;
+
+    /// Program the backend drives (`None` when it needs none)
+    fn tool(&self) -> Option<Tool>;
+
+    /// Error if the backend's program isn't installed
+    fn require(&self) -> Result<(), String> {
+        match self.tool() {
+            Some(tool) => super::capabilities::require(tool),
+            None => Ok(()),
+        }
+    }
+
+    fn list_sessions(&self) -> BoxFuture<'_, Vec<TmuxSession>>;
+
+    /// Create a detached session rooted in `cwd` running `command` (the
+    /// default shell when `None`); a no-op returning `false` if `name` exists
+    fn create_session<'a>(
+        &'a self,
+        name: &'a str,
+        cwd: &'a str,
+        command: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<bool, String>>;
+
+    fn kill_session<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<(), String>>;
+
+    /// Append `window` to `session`: its first pane, the rest split off it,
+    /// each typed its command. Cwds are resolved against `project`.
+    fn add_window<'a>(
+        &'a self,
+        session: &'a str,
+        window: &'a TemplateWindow,
+        project: &'a Path,
+    ) -> BoxFuture<'a, Result<(), String>>;
+
+    /// Type `text` into `target` followed by Enter
+    fn send_input<'a>(
+        &'a self,
+        target: &'a str,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<(), String>>;
+
+    /// Pane `target` addresses, sent to the frontend before attaching
+    fn pane_info<'a>(&'a self, target: &'a str) -> BoxFuture<'a, Option<TmuxPane>>;
+
+    /// Whether `target` still exists; used to sweep PTYs of vanished panes
+    fn has_target<'a>(&'a self, target: &'a str) -> BoxFuture<'a, bool> {
+        async move { self.pane_info(target).await.is_some() }.boxed()
+    }
+
+    fn attach_command(&self, target: &str, read_only: bool) -> Result<AttachCommand, String>;
+}
+
+pub struct Tmux;
+
+impl Multiplexer for Tmux {
+    fn name(&self) -> &'static str {
+        "tmux"
+    }
+
+    fn tool(&self) -> Option<Tool> {
+        Some(Tool::Tmux)
+    }
+
+    fn list_sessions(&self) -> BoxFuture<'_, Vec<TmuxSession>> {
+        super::tmux::list_sessions().boxed()
+    }
+
+    fn create_session<'a>(
+        &'a self,
+        name: &'a str,
+        cwd: &'a str,
+        command: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<bool, String>> {
+        super::tmux::new_session(name, cwd, command).boxed()
+    }
+
+    fn kill_session<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<(), String>> {
+        super::tmux::kill_session(name).boxed()
+    }
+
+    fn add_window<'a>(
+        &'a self,
+        session: &'a str,
+        window: &'a TemplateWindow,
+        project: &'a Path,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        super::templates::add_window(session, window, project).boxed()
+    }
+
+    fn send_input<'a>(
+        &'a self,
+        target: &'a str,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        super::tmux::send_keys_literal(target, text).boxed()
+    }
+
+    fn pane_info<'a>(&'a self, target: &'a str) -> BoxFuture<'a, Option<TmuxPane>> {
+        super::tmux::get_pane_info(target).boxed()
+    }
+
+    fn has_target<'a>(&'a self, target: &'a str) -> BoxFuture<'a, bool> {
+        super::tmux::has_target(target).boxed()
+    }
+
+    fn attach_command(&self, target: &str, read_only: bool) -> Result<AttachCommand, String> {
+        let mut args = super::tmux::socket_args();
+        args.push("attach-session".to_string());
+        if read_only {
+            args.push("-r".to_string());
+        }
+        args.extend(["-t".to_string(), target.to_string()]);
+        Ok(AttachCommand {
+            program: super::tmux::tmux_binary(),
+            args,
+            env: super::tmux::tmux_env(),
+        })
+    }
+}
+
+/// Backend for the server the current task is scoped to: fixtures with
+/// `Feature::DemoData`, WezTerm for `WEZTERM_SERVER`, otherwise the one
+/// selected by `multiplexer.backend` (unknown values fall back to tmux)
+pub fn current() -> &'static dyn Multiplexer {
+    if super::features::enabled(super::features::Feature::DemoData) {
+        return &super::demo::Demo;
+    }
+    if super::tmux::current_server().as_deref() == Some(WEZTERM_SERVER) {
+        return &super::wezterm::WezTerm;
+    }
+    match super::settings::get_settings()
+        .settings
+        .multiplexer
+        .backend
+        .as_str()
+    {
+        "zellij" => &super::zellij::Zellij,
+        _ => &Tmux,
+    }
+}
+
+/// Servers to list sessions from: the default one, the registered tmux
+/// servers when the backend is tmux, and `extra_servers`. Demo fixtures are
+/// the same on every server, so only the default one lists them.
+pub fn servers() -> Vec<Option<String>> {
+    let mut servers = vec![None];
+    match current().name() {
+        "demo" => return servers,
+        "tmux" => servers.extend(super::tmux::server_names().into_iter().map(Some)),
+        _ => {}
+    }
+    servers.extend(extra_servers().into_iter().map(Some));
+    servers
+}
+
+/// Servers listed alongside the tmux ones: `WEZTERM_SERVER` when
+/// `multiplexer.wezterm` is on
+pub fn extra_servers() -> Vec<String> {
+    let settings = super::settings::get_settings().settings.multiplexer;
+    settings
+        .wezterm
+        .then(|| WEZTERM_SERVER.to_string())
+        .into_iter()
+        .collect()
+}
@@ -0,0 +1,240 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+use super::settings::{QuietHours, Sound};
+
+static DISPATCH: once_cell::sync::Lazy<Mutex<DispatchState>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(DispatchState::default()));
+
+#[derive(Default)]
+struct DispatchState {
+    next_id: u64,
+    /// Dedup key → when it was last dispatched
+    recent: HashMap<String, Instant>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotifyOutcome {
+    pub id: u64,
+    /// Shown as a desktop notification
+    pub delivered: bool,
+    /// Why it wasn't: "duplicate", "disabled" or "quiet-hours"
+    pub suppressed: Option<String>,
+}
+
+/// Attention events that can play a sound (`notifications.sounds`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundEvent {
+    /// A Claude turn went from thinking to done
+    TurnFinished,
+    #[allow(dead_code)] // reserved for the trigger subsystem
+    TriggerMatched,
+}
+
+impl SoundEvent {
+    pub fn id(self) -> &'static str {
+        match self {
+            SoundEvent::TurnFinished => "turn-finished",
+            SoundEvent::TriggerMatched => "trigger-matched",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        [SoundEvent::TurnFinished, SoundEvent::TriggerMatched]
+            .into_iter()
+            .find(|e| e.id() == id)
+    }
+}
+
+/// "HH:MM" → minutes since midnight
+fn parse_hhmm(value: &str) -> Option<u32> {
+    let (h, m) = value.trim().split_once(':')?;
+    let (h, m): (u32, u32) = (h.parse().ok()?, m.parse().ok()?);
+    (h < 24 && m < 60).then_some(h * 60 + m)
+}
+
+async fn local_minutes() -> Option<u32> {
+    let output = Command::new("date").arg("+%H:%M").output().await.ok()?;
+    parse_hhmm(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Whether `now` falls in the window; windows may wrap past midnight
+fn in_quiet_hours(quiet: &QuietHours, now: u32) -> bool {
+    let (Some(start), Some(end)) = (parse_hhmm(&quiet.start), parse_hhmm(&quiet.end)) else {
+        return false;
+    };
+    if start <= end {
+        now >= start && now < end
+    } else {
+        now >= start || now < end
+    }
+}
+
+/// The sound for `event` in `project`: the longest `sounds.projects` path
+/// containing it, else `sounds.events`
+fn sound_for(event: SoundEvent, project: Option<&str>) -> Option<(Sound, f64)> {
+    let settings = super::settings::get_settings().settings.notifications.sounds;
+    let project_sound = project.and_then(|project| {
+        settings
+            .projects
+            .iter()
+            .filter(|(path, _)| {
+                std::path::Path::new(project).starts_with(super::settings::expand_tilde(path))
+            })
+            .max_by_key(|(path, _)| path.len())
+            .and_then(|(_, sounds)| sounds.get(event.id()).cloned())
+    });
+    let sound = project_sound.or_else(|| settings.events.get(event.id()).cloned())?;
+    let volume = sound.volume.unwrap_or(settings.volume).clamp(0.0, 1.0);
+    Some((sound, volume))
+}
+
+async fn play_file(file: &str, volume: f64) -> Result<(), String> {
+    let path = super::settings::expand_tilde(file);
+    if !path.exists() {
+        return Err(format!("Sound file not found: {}", path.display()));
+    }
+    let output = if cfg!(target_os = "macos") {
+        Command::new("afplay")
+            .args(["-v", &format!("{:.2}", volume)])
+            .arg(&path)
+            .output()
+            .await
+    } else {
+        // PulseAudio volume is 0–65536
+        Command::new("paplay")
+            .arg(format!("--volume={}", (volume * 65536.0) as u32))
+            .arg(&path)
+            .output()
+            .await
+    };
+    match output {
+        Ok(o) if o.status.success() => Ok(()),
+        Ok(o) => Err(String::from_utf8_lossy(&o.stderr).trim().to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Play `event`'s sound in the background unless sounds are off or it's quiet hours
+pub fn play_sound(event: SoundEvent, project: Option<String>) {
+    let settings = super::settings::get_settings().settings.notifications;
+    if !settings.sounds.enabled {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        if let (Some(quiet), Some(now)) = (&settings.quiet_hours, local_minutes().await) {
+            if in_quiet_hours(quiet, now) {
+                return;
+            }
+        }
+        let Some((sound, volume)) = sound_for(event, project.as_deref()) else {
+            return;
+        };
+        if let Err(e) = play_file(&sound.file, volume).await {
+            log::warn!("[notifications] Failed to play {}: {}", sound.file, e);
+        }
+    });
+}
+
+/// Play the sound `event` would play (for `project`), regardless of
+/// `sounds.enabled` and quiet hours
+pub async fn preview_sound(event: &str, project: Option<&str>) -> Result<(), String> {
+    let event = SoundEvent::from_id(event).ok_or_else(|| format!("Unknown sound: {}", event))?;
+    let (sound, volume) =
+        sound_for(event, project).ok_or_else(|| format!("No sound set for {}", event.id()))?;
+    play_file(&sound.file, volume).await
+}
+
+/// Escape a string for an AppleScript string literal
+fn applescript_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+async fn show_desktop(title: &str, body: &str, kind: &str) -> Result<(), String> {
+    let output = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {} with title {}",
+            applescript_string(body),
+            applescript_string(title)
+        );
+        Command::new("osascript").args(["-e", &script]).output().await
+    } else {
+        let urgency = if kind == "error" { "critical" } else { "normal" };
+        Command::new("notify-send")
+            .args(["-a", "MuxTunnel", "-u", urgency, "--", title, body])
+            .output()
+            .await
+    };
+    match output {
+        Ok(o) if o.status.success() => Ok(()),
+        Ok(o) => Err(String::from_utf8_lossy(&o.stderr).trim().to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Dispatch a notification: always emitted in-app as a `notification` event,
+/// shown on the desktop unless disabled, a recent duplicate, or in quiet hours.
+/// `action` is passed through for the frontend to run when the toast is clicked.
+pub async fn notify(title: &str, body: &str, kind: &str, action: Option<String>) -> NotifyOutcome {
+    let settings = super::settings::get_settings().settings.notifications;
+    let dedup = Duration::from_secs(settings.dedup_secs);
+    let key = format!("{}\u{0}{}\u{0}{}", kind, title, body);
+
+    let (id, duplicate) = {
+        let mut state = DISPATCH.lock().unwrap();
+        state.next_id += 1;
+        let now = Instant::now();
+        state.recent.retain(|_, at| now.duration_since(*at) < dedup);
+        let duplicate = state.recent.contains_key(&key);
+        if !duplicate {
+            state.recent.insert(key, now);
+        }
+        (state.next_id, duplicate)
+    };
+    if duplicate {
+        return NotifyOutcome {
+            id,
+            delivered: false,
+            suppressed: Some("duplicate".to_string()),
+        };
+    }
+
+    let suppressed = if !settings.enabled {
+        Some("disabled")
+    } else {
+        match (&settings.quiet_hours, local_minutes().await) {
+            (Some(quiet), Some(now)) if in_quiet_hours(quiet, now) => Some("quiet-hours"),
+            _ => None,
+        }
+    };
+
+    let delivered = match suppressed {
+        Some(_) => false,
+        None => match show_desktop(title, body, kind).await {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("[notifications] Desktop notification failed: {}", e);
+                false
+            }
+        },
+    };
+
+    super::events::emit(super::events::BackendEvent::Notification {
+        id,
+        title: title.to_string(),
+        body: body.to_string(),
+        kind: kind.to_string(),
+        action,
+        delivered,
+    });
+
+    NotifyOutcome {
+        id,
+        delivered,
+        suppressed: suppressed.map(String::from),
+    }
+}
@@ -0,0 +1,110 @@
+/// Start of an OSC sequence
+const MARKER: &[u8] = b"\x1b]";
+
+/// An unterminated OSC sequence longer than this is dropped
+const MAX_PARTIAL: usize = 4096;
+
+/// Title and working directory changes a program announces in its output
+#[derive(Debug, Clone, PartialEq)]
+pub enum Osc {
+    /// `OSC 0` or `OSC 2`
+    Title(String),
+    /// `OSC 7`, as a local path
+    Cwd(String),
+}
+
+/// Finds title and cwd sequences in a PTY's output, across reads
+#[derive(Default)]
+pub struct Scanner {
+    /// Start of a sequence split across reads
+    partial: Vec<u8>,
+    title: Option<String>,
+    cwd: Option<String>,
+}
+
+impl Scanner {
+    /// Title and cwd changes in new output; repeats of the current value are
+    /// skipped
+    pub fn feed(&mut self, data: &[u8]) -> Vec<Osc> {
+        let mut found = Vec::new();
+        let joined;
+        let mut rest = if self.partial.is_empty() {
+            data
+        } else {
+            let mut buf = std::mem::take(&mut self.partial);
+            buf.extend_from_slice(data);
+            joined = buf;
+            &joined[..]
+        };
+        loop {
+            let Some(start) = rest.windows(MARKER.len()).position(|w| w == MARKER) else {
+                if rest.last() == Some(&MARKER[0]) {
+                    self.partial.push(MARKER[0]);
+                }
+                return found;
+            };
+            let body = &rest[start + MARKER.len()..];
+            match super::shell_integration::terminator(body) {
+                Some((end, len)) => {
+                    if let Some(osc) = self.sequence(&String::from_utf8_lossy(&body[..end])) {
+                        found.push(osc);
+                    }
+                    rest = &body[end + len..];
+                }
+                None if body.len() < MAX_PARTIAL => {
+                    self.partial.extend_from_slice(&rest[start..]);
+                    return found;
+                }
+                None => rest = body,
+            }
+        }
+    }
+
+    fn sequence(&mut self, payload: &str) -> Option<Osc> {
+        let (code, value) = payload.split_once(';')?;
+        match code {
+            "0" | "2" => {
+                if self.title.as_deref() == Some(value) {
+                    return None;
+                }
+                self.title = Some(value.to_string());
+                Some(Osc::Title(value.to_string()))
+            }
+            "7" => {
+                let path = file_url_path(value)?;
+                if self.cwd.as_deref() == Some(path.as_str()) {
+                    return None;
+                }
+                self.cwd = Some(path.clone());
+                Some(Osc::Cwd(path))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The path of a `file://host/path` URL, percent-decoded
+fn file_url_path(url: &str) -> Option<String> {
+    let rest = url.strip_prefix("file://")?;
+    let path = &rest[rest.find('/')?..];
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    Some(String::from_utf8_lossy(&decoded).to_string())
+}
@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use super::pty_manager::session_key;
+use super::tmux::TmuxSession;
+
+/// Pairs as last saved; loaded on first use
+static PAIRS: once_cell::sync::Lazy<Mutex<Vec<Pair>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(load()));
+
+/// Two related panes (e.g. an agent and its dev server) the UI shows side by side
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pair {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    /// Pane ids, stable across moves and renames
+    pub primary: String,
+    pub secondary: String,
+    pub created_at: u64,
+}
+
+/// A pane's side of its pair, as reported in `sessions_list`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PanePair {
+    pub id: String,
+    /// "primary" or "secondary"
+    pub role: String,
+    /// Pane id of the other pane
+    pub partner: String,
+}
+
+fn pairs_file() -> PathBuf {
+    super::paths::data_dir().join("pairs.json")
+}
+
+fn load() -> Vec<Pair> {
+    match fs::read_to_string(pairs_file()) {
+        Ok(raw) => serde_json::from_str(&raw).unwrap_or_default(),
+        Err(_) => vec![],
+    }
+}
+
+fn save(pairs: &[Pair]) {
+    let path = pairs_file();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Err(e) = fs::write(
+        &path,
+        serde_json::to_string_pretty(pairs).unwrap_or_default(),
+    ) {
+        log::error!("[pairs] Failed to save: {}", e);
+    }
+}
+
+fn emit_changed(pairs: &[Pair]) {
+    super::events::emit(super::events::BackendEvent::PairsChanged {
+        pairs: pairs.to_vec(),
+    });
+}
+
+/// Pair two panes of the current server. Each pane is in at most one pair,
+/// so pairs either pane was in are replaced.
+pub async fn create(primary: &str, secondary: &str) -> Result<Pair, String> {
+    let primary = super::tmux::pane_id(primary).await?;
+    let secondary = super::tmux::pane_id(secondary).await?;
+    if primary == secondary {
+        return Err("Can't pair a pane with itself".to_string());
+    }
+    let server = super::tmux::current_server();
+    let now = super::activity::now_unix();
+    let pair = Pair {
+        id: format!("{}-{}", primary, secondary).replace('%', ""),
+        server: server.clone(),
+        primary,
+        secondary,
+        created_at: now,
+    };
+
+    let mut pairs = PAIRS.lock().unwrap();
+    pairs.retain(|p| {
+        p.server != server
+            || ![&p.primary, &p.secondary]
+                .iter()
+                .any(|pane| **pane == pair.primary || **pane == pair.secondary)
+    });
+    pairs.push(pair.clone());
+    save(&pairs);
+    emit_changed(&pairs);
+    Ok(pair)
+}
+
+pub fn delete(id: &str) -> Result<(), String> {
+    let mut pairs = PAIRS.lock().unwrap();
+    let before = pairs.len();
+    pairs.retain(|p| p.id != id);
+    if pairs.len() == before {
+        return Err(format!("Pair not found: {}", id));
+    }
+    save(&pairs);
+    emit_changed(&pairs);
+    Ok(())
+}
+
+pub fn list() -> Vec<Pair> {
+    PAIRS.lock().unwrap().clone()
+}
+
+/// Set `pair` on paired panes of a full listing, first dropping pairs with a
+/// pane that no longer exists (announced with `pairs-changed`). Servers
+/// without sessions in the listing are left alone, since their panes weren't
+/// seen either way.
+pub fn annotate(sessions: &mut [TmuxSession]) {
+    let listed: HashSet<Option<&str>> = sessions.iter().map(|s| s.server.as_deref()).collect();
+    let live: HashSet<String> = sessions
+        .iter()
+        .flat_map(|s| {
+            s.windows
+                .iter()
+                .flat_map(|w| &w.panes)
+                .map(|p| session_key(s.server.as_deref(), &p.pane_id))
+        })
+        .collect();
+
+    let mut pairs = PAIRS.lock().unwrap();
+    let before = pairs.len();
+    pairs.retain(|p| {
+        !listed.contains(&p.server.as_deref())
+            || (live.contains(&session_key(p.server.as_deref(), &p.primary))
+                && live.contains(&session_key(p.server.as_deref(), &p.secondary)))
+    });
+    if pairs.len() != before {
+        log::info!(
+            "[pairs] Dropped {} pairs with exited panes",
+            before - pairs.len()
+        );
+        save(&pairs);
+        emit_changed(&pairs);
+    }
+
+    for session in sessions {
+        for window in &mut session.windows {
+            for pane in &mut window.panes {
+                pane.pair = pairs
+                    .iter()
+                    .filter(|p| p.server == session.server)
+                    .find_map(|p| {
+                        if p.primary == pane.pane_id {
+                            Some(("primary", &p.secondary, &p.id))
+                        } else if p.secondary == pane.pane_id {
+                            Some(("secondary", &p.primary, &p.id))
+                        } else {
+                            None
+                        }
+                    })
+                    .map(|(role, partner, id)| PanePair {
+                        id: id.clone(),
+                        role: role.to_string(),
+                        partner: partner.clone(),
+                    });
+            }
+        }
+    }
+}
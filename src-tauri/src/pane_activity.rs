@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use super::pty_manager::session_key;
+use super::tmux::TmuxSession;
+
+/// Weight of the newest sample in the smoothed rate
+const RATE_SMOOTHING: f64 = 0.5;
+
+/// Per pane (keyed by server + pane id)
+static PANES: once_cell::sync::Lazy<Mutex<HashMap<String, PaneActivity>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Lines printed by panes whose output is read through `pipe-pane`, by the
+/// same key. Counted from the output itself, so unlike the history size it
+/// keeps growing once the pane's history is full.
+static PRINTED: once_cell::sync::Lazy<Mutex<HashMap<String, u64>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct PaneActivity {
+    /// Printed lines when piped, else `history_size + cursor_y`: grows as
+    /// the pane prints lines
+    lines: u64,
+    /// Whether `lines` came from `PRINTED`
+    piped: bool,
+    /// The history is at `history-limit`, so an unpiped pane's line count
+    /// no longer moves with its output and its rate is unknown
+    saturated: bool,
+    sampled_at: Instant,
+    /// Unix time output was last seen, from sampling, piped output, or
+    /// `#{window_activity}` for a pane alone in its window
+    last_output: u64,
+    /// Smoothed lines per second
+    rate: f64,
+    seen: bool,
+}
+
+/// Output read from the pipe of the pane at `key`
+pub fn observe(key: &str, data: &[u8]) {
+    let lines = data.iter().filter(|b| **b == b'\n').count() as u64;
+    *PRINTED.lock().unwrap().entry(key.to_string()).or_default() += lines;
+}
+
+/// The pipe of the pane at `key` closed
+pub fn closed(key: &str) {
+    PRINTED.lock().unwrap().remove(key);
+}
+
+/// Sample line counts of every pane on the current server
+async fn sample() {
+    let rows = match super::tmux::list_pane_fields(&[
+        "#{pane_id}",
+        "#{history_size}",
+        "#{history_limit}",
+        "#{cursor_y}",
+        "#{window_activity}",
+        "#{window_panes}",
+    ])
+    .await
+    {
+        Ok(rows) => rows,
+        Err(_) => return,
+    };
+    let server = super::tmux::current_server();
+    let now = Instant::now();
+    let now_unix = super::activity::now_unix();
+
+    let printed = PRINTED.lock().unwrap().clone();
+    let mut panes = PANES.lock().unwrap();
+    for pane in panes.values_mut() {
+        pane.seen = false;
+    }
+    for row in rows {
+        let Ok([pane_id, history, history_limit, cursor_y, window_activity, window_panes]) =
+            <[String; 6]>::try_from(row)
+        else {
+            continue;
+        };
+        let history: u64 = history.parse().unwrap_or(0);
+        let history_limit: u64 = history_limit.parse().unwrap_or(0);
+        // Window activity is per window; it only says something about a pane
+        // that has the window to itself
+        let window_activity: u64 = if window_panes == "1" {
+            window_activity.parse().unwrap_or(0)
+        } else {
+            0
+        };
+
+        let key = session_key(server.as_deref(), &pane_id);
+        let piped = printed.get(&key).copied();
+        let lines = piped.unwrap_or(history + cursor_y.parse::<u64>().unwrap_or(0));
+        let saturated = piped.is_none() && history_limit > 0 && history >= history_limit;
+        let Some(pane) = panes.get_mut(&key) else {
+            panes.insert(
+                key,
+                PaneActivity {
+                    lines,
+                    piped: piped.is_some(),
+                    saturated,
+                    sampled_at: now,
+                    last_output: window_activity,
+                    rate: 0.0,
+                    seen: true,
+                },
+            );
+            continue;
+        };
+
+        // Switching between counts starts over from the new one
+        if pane.piped == piped.is_some() {
+            let elapsed = now.duration_since(pane.sampled_at).as_secs_f64().max(1.0);
+            // A cleared screen shrinks the count; that still counts as output
+            let delta = lines.abs_diff(pane.lines);
+            pane.rate =
+                RATE_SMOOTHING * (delta as f64 / elapsed) + (1.0 - RATE_SMOOTHING) * pane.rate;
+            if delta > 0 {
+                pane.last_output = now_unix;
+            }
+        }
+        // Catches output that didn't change the line count (e.g. redraws)
+        pane.last_output = pane.last_output.max(window_activity);
+        pane.lines = lines;
+        pane.piped = piped.is_some();
+        pane.saturated = saturated;
+        pane.sampled_at = now;
+        pane.seen = true;
+    }
+    // Forget panes that are gone from this server
+    panes.retain(|key, pane| pane.seen || !belongs_to(key, server.as_deref()));
+}
+
+fn belongs_to(key: &str, server: Option<&str>) -> bool {
+    match server {
+        Some(server) => key.starts_with(&format!("{}/", server)),
+        None => key.starts_with('%'),
+    }
+}
+
+/// Fill in `last_output` and `output_rate` for panes that have been sampled
+/// (no rate for unpiped panes whose history is full)
+pub fn annotate(sessions: &mut [TmuxSession]) {
+    let panes = PANES.lock().unwrap();
+    for session in sessions {
+        for window in &mut session.windows {
+            for pane in &mut window.panes {
+                let key = session_key(session.server.as_deref(), &pane.pane_id);
+                if let Some(activity) = panes.get(&key) {
+                    pane.last_output = Some(activity.last_output);
+                    pane.output_rate = (!activity.saturated)
+                        .then(|| (activity.rate * 10.0).round() / 10.0);
+                }
+            }
+        }
+    }
+}
+
+/// Periodically sample every registered server, those with panes on screen
+/// more often (see `visibility`)
+pub fn start_sampler() {
+    tauri::async_runtime::spawn(async move {
+        let _task = super::diagnostics::track("activity-sampler");
+        let mut sampled: HashMap<Option<String>, Instant> = HashMap::new();
+        loop {
+            let servers =
+                std::iter::once(None).chain(super::tmux::server_names().into_iter().map(Some));
+            for server in servers {
+                let pace = super::visibility::pace(server.as_deref());
+                let interval = super::visibility::interval(pace);
+                if sampled.get(&server).is_some_and(|at| at.elapsed() < interval) {
+                    continue;
+                }
+                sampled.insert(server.clone(), Instant::now());
+                super::tmux::with_server(server, sample()).await;
+            }
+            super::visibility::sleep(super::visibility::tick()).await;
+        }
+    });
+}
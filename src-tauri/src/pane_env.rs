@@ -0,0 +1,124 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tokio::process::Command;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessEnv {
+    pub pid: u32,
+    pub command: String,
+    /// "proc" (exact) or "ps" (`ps eww`, values containing spaces may be split)
+    pub source: String,
+    pub vars: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaneEnv {
+    pub target: String,
+    pub session: String,
+    /// `show-environment -t`: what tmux passes to new panes; `None` = removed
+    pub session_env: BTreeMap<String, Option<String>>,
+    /// `show-environment -g`
+    pub global_env: BTreeMap<String, Option<String>>,
+    /// The pane's shell
+    pub shell: Option<ProcessEnv>,
+    /// The foreground process, when it isn't the shell
+    pub foreground: Option<ProcessEnv>,
+}
+
+fn is_var_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// `/proc/<pid>/environ`: NUL-separated `KEY=value` entries
+fn read_proc(pid: u32) -> Option<BTreeMap<String, String>> {
+    let raw = std::fs::read(format!("/proc/{}/environ", pid)).ok()?;
+    Some(
+        raw.split(|b| *b == 0)
+            .filter_map(|entry| {
+                let entry = String::from_utf8_lossy(entry);
+                let (key, value) = entry.split_once('=')?;
+                Some((key.to_string(), value.to_string()))
+            })
+            .collect(),
+    )
+}
+
+/// `ps eww` prints the environment after the command line with no delimiter,
+/// so words are attributed to the last `KEY=` seen. Arguments that look like
+/// assignments are indistinguishable from variables.
+async fn read_ps(pid: u32) -> Option<BTreeMap<String, String>> {
+    let output = Command::new("ps")
+        .args(["eww", "-o", "command=", "-p", &pid.to_string()])
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut vars = BTreeMap::new();
+    let mut current: Option<String> = None;
+    for word in stdout.split_whitespace() {
+        match word.split_once('=') {
+            Some((key, value)) if is_var_name(key) => {
+                vars.insert(key.to_string(), value.to_string());
+                current = Some(key.to_string());
+            }
+            _ => {
+                if let Some(value) = current.as_ref().and_then(|k| vars.get_mut(k)) {
+                    value.push(' ');
+                    value.push_str(word);
+                }
+            }
+        }
+    }
+    Some(vars)
+}
+
+async fn process_env(pid: u32, command: &str) -> Option<ProcessEnv> {
+    let (source, vars) = match read_proc(pid) {
+        Some(vars) => ("proc", vars),
+        None => ("ps", read_ps(pid).await?),
+    };
+    Some(ProcessEnv {
+        pid,
+        command: command.to_string(),
+        source: source.to_string(),
+        vars,
+    })
+}
+
+/// Environment of `target` on the current server: what tmux hands new panes,
+/// and what its shell and foreground process actually have
+pub async fn inspect(target: &str) -> Result<PaneEnv, String> {
+    let (session, shell_pid, fg_pid, fg_command) = super::tmux::get_pane_processes(target).await?;
+    let session_env = super::tmux::show_environment(Some(&session)).await?;
+    let global_env = super::tmux::show_environment(None)
+        .await
+        .unwrap_or_default();
+
+    let shell_command = super::tmux::get_process_table()
+        .await
+        .get(&shell_pid)
+        .map(|p| p.command.clone())
+        .unwrap_or_default();
+    let shell = process_env(shell_pid, &shell_command).await;
+    let foreground = if fg_pid != shell_pid {
+        process_env(fg_pid, &fg_command).await
+    } else {
+        None
+    };
+
+    Ok(PaneEnv {
+        target: target.to_string(),
+        session,
+        session_env,
+        global_env,
+        shell,
+        foreground,
+    })
+}
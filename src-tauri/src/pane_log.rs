@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::pty_manager::session_key;
+
+/// Open logs by `session_key` of the pane id
+static LOGS: once_cell::sync::Lazy<Mutex<HashMap<String, PaneLog>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct PaneLog {
+    saved: SavedLog,
+    path: PathBuf,
+    file: File,
+    /// Size of the current file
+    written: u64,
+    /// `logs.maxBytes` and `logs.keep` when the log started
+    max_bytes: u64,
+    keep: usize,
+}
+
+/// A running log, saved so `restore` can start it again after a restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedLog {
+    pub server: Option<String>,
+    pub pane_id: String,
+    /// PID of the pane's process; a pane id reused by a restarted server
+    /// comes with another
+    pub pane_pid: u32,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogStarted {
+    pub pane_id: String,
+    pub path: String,
+}
+
+/// `logs.dir`, else `logs` in the data dir
+fn log_dir() -> PathBuf {
+    super::settings::get_settings()
+        .settings
+        .logs
+        .dir
+        .filter(|d| !d.trim().is_empty())
+        .map(|d| super::settings::expand_tilde(d.trim()))
+        .unwrap_or_else(|| super::paths::data_dir().join("logs"))
+}
+
+fn saved_file() -> PathBuf {
+    super::paths::data_dir().join("pane-logs.json")
+}
+
+pub fn load_saved() -> Vec<SavedLog> {
+    std::fs::read_to_string(saved_file())
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+/// Save the running logs; called with `LOGS` locked
+fn persist(logs: &HashMap<String, PaneLog>) {
+    let saved: Vec<&SavedLog> = logs.values().map(|log| &log.saved).collect();
+    let path = saved_file();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let result = serde_json::to_string_pretty(&saved)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        log::warn!("[pane-log] Failed to save {}: {}", path.display(), e);
+    }
+}
+
+fn open(path: &Path) -> Result<File, String> {
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+}
+
+impl PaneLog {
+    fn write(&mut self, data: &[u8]) {
+        if let Err(e) = self.file.write_all(data) {
+            log::warn!("[pane-log] Failed to write {}: {}", self.path.display(), e);
+            return;
+        }
+        self.written += data.len() as u64;
+        if self.max_bytes > 0 && self.written >= self.max_bytes {
+            self.rotate(self.keep);
+        }
+    }
+
+    /// `log` → `log.1` → … → `log.<keep>`, dropping the oldest
+    fn rotate(&mut self, keep: usize) {
+        let rotated = |n: usize| PathBuf::from(format!("{}.{}", self.path.display(), n));
+        if keep == 0 {
+            let _ = std::fs::remove_file(&self.path);
+        } else {
+            let _ = std::fs::remove_file(rotated(keep));
+            for n in (1..keep).rev() {
+                let _ = std::fs::rename(rotated(n), rotated(n + 1));
+            }
+            let _ = std::fs::rename(&self.path, rotated(1));
+        }
+        match open(&self.path) {
+            Ok(file) => {
+                self.file = file;
+                self.written = 0;
+            }
+            Err(e) => log::warn!("[pane-log] {}", e),
+        }
+    }
+}
+
+/// Append a pane's output to its log, if it has one
+pub fn observe(key: &str, data: &[u8]) {
+    if let Some(log) = LOGS.lock().unwrap().get_mut(key) {
+        log.write(data);
+    }
+}
+
+/// The pane's pipe closed (the pane exited); stop its log
+pub fn closed(key: &str) {
+    let mut logs = LOGS.lock().unwrap();
+    if let Some(log) = logs.remove(key) {
+        log::info!("[pane-log] Closed {}", log.path.display());
+        persist(&logs);
+    }
+}
+
+/// Log a tmux pane's output to `path` (default `<session>-<pane>.log` in
+/// `logs.dir`), rotating at `logs.maxBytes`. Output is read through
+/// `pipe-pane`, so it's logged whether or not the pane is attached, and the
+/// log is started again when the app next starts (see `restore`).
+pub async fn start(target: &str, path: Option<&str>) -> Result<LogStarted, String> {
+    let pane_id = super::tmux::pane_id(target).await?;
+    let server = super::tmux::current_server();
+    let key = session_key(server.as_deref(), &pane_id);
+    let pane_pid = super::tmux::get_pane_info(&pane_id)
+        .await
+        .map(|pane| pane.pid)
+        .unwrap_or(0);
+    let path = match path.filter(|p| !p.trim().is_empty()) {
+        Some(path) => super::settings::expand_tilde(path.trim()),
+        None => {
+            let session = super::tmux::session_of(target)
+                .await
+                .replace(|c: char| !c.is_alphanumeric() && c != '-' && c != '_', "_");
+            log_dir().join(format!(
+                "{}-{}.log",
+                session,
+                pane_id.trim_start_matches('%')
+            ))
+        }
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let file = open(&path)?;
+    let written = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let settings = super::settings::get_settings().settings.logs;
+    let log = PaneLog {
+        saved: SavedLog {
+            server,
+            pane_id: pane_id.clone(),
+            pane_pid,
+            path: path.to_string_lossy().to_string(),
+        },
+        path: path.clone(),
+        file,
+        written,
+        max_bytes: settings.max_bytes,
+        keep: settings.keep,
+    };
+    LOGS.lock().unwrap().insert(key.clone(), log);
+    if let Err(e) = super::pane_pipe::ensure(&pane_id, &key).await {
+        LOGS.lock().unwrap().remove(&key);
+        return Err(e);
+    }
+    persist(&LOGS.lock().unwrap());
+    Ok(LogStarted {
+        pane_id,
+        path: path.to_string_lossy().to_string(),
+    })
+}
+
+/// Stop logging a pane; the pipe stays open for command tracking
+pub async fn stop(target: &str) -> Result<(), String> {
+    let pane_id = super::tmux::pane_id(target).await?;
+    let key = session_key(super::tmux::current_server().as_deref(), &pane_id);
+    let mut logs = LOGS.lock().unwrap();
+    match logs.remove(&key) {
+        Some(_) => {
+            persist(&logs);
+            Ok(())
+        }
+        None => Err(format!("{} isn't being logged", target)),
+    }
+}
+
+/// Start the logs saved by the last run again, for panes that still exist
+pub async fn restore() {
+    for saved in load_saved() {
+        let restored = super::tmux::with_server(saved.server.clone(), async {
+            let pane = super::tmux::get_pane_info(&saved.pane_id).await?;
+            if pane.pid != saved.pane_pid {
+                return None;
+            }
+            Some(start(&saved.pane_id, Some(&saved.path)).await)
+        })
+        .await;
+        match restored {
+            Some(Ok(_)) => log::info!("[pane-log] Resumed {}", saved.path),
+            Some(Err(e)) => log::warn!("[pane-log] Failed to resume {}: {}", saved.path, e),
+            None => log::info!("[pane-log] Dropped {}; its pane is gone", saved.path),
+        }
+    }
+    // Forget logs that weren't resumed
+    persist(&LOGS.lock().unwrap());
+}
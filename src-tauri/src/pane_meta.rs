@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use super::pty_manager::session_key;
+
+/// Pane user option the metadata is stored in, so it lives as long as the pane
+/// and is seen by any MuxTunnel talking to the same tmux server
+pub const OPTION: &str = "@muxtunnel_meta";
+
+/// MuxTunnel's own annotations of a pane
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PaneMeta {
+    pub tags: Vec<String>,
+    pub pinned: bool,
+    /// Watch expressions or trigger ids subscribed to this pane's output
+    pub watch: Vec<String>,
+}
+
+/// Local copy, keyed by `session_key(server, pane_id)`, for tmux without pane
+/// options (before 3.0). Not read when tmux has them: pane ids are reused
+/// after a server restart, so a stale entry would land on an unrelated pane.
+fn meta_file() -> PathBuf {
+    super::paths::data_dir().join("pane-meta.json")
+}
+
+pub fn load_local() -> BTreeMap<String, PaneMeta> {
+    fs::read_to_string(meta_file())
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_local(all: &BTreeMap<String, PaneMeta>) -> Result<(), String> {
+    let path = meta_file();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let json = serde_json::to_string_pretty(all)
+        .map_err(|e| format!("Failed to serialize pane metadata: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to save pane metadata: {}", e))
+}
+
+/// Metadata of a pane listed by `list_sessions`: the tmux option's value, or
+/// the local copy (`load_local`) on tmux without pane options
+pub fn resolve(
+    option_value: &str,
+    pane_id: &str,
+    local: &BTreeMap<String, PaneMeta>,
+) -> Option<PaneMeta> {
+    if super::tmux::supports(super::tmux::Feature::PaneOptions) {
+        if option_value.is_empty() {
+            return None;
+        }
+        return serde_json::from_str(option_value)
+            .map_err(|e| log::debug!("[pane-meta] Ignoring {} of {}: {}", OPTION, pane_id, e))
+            .ok();
+    }
+    let key = session_key(super::tmux::current_server().as_deref(), pane_id);
+    local.get(&key).cloned()
+}
+
+/// Pane id of a local key, if the key belongs to `server`
+fn pane_id_on<'a>(key: &'a str, server: Option<&str>) -> Option<&'a str> {
+    match server {
+        Some(server) => key.strip_prefix(server)?.strip_prefix('/'),
+        None => (!key.contains('/')).then_some(key),
+    }
+}
+
+/// Drop local entries of the current server's panes missing from `live`, the
+/// pane ids of a full listing
+pub fn prune(live: &HashSet<String>) {
+    let server = super::tmux::current_server();
+    let mut all = load_local();
+    let before = all.len();
+    all.retain(|key, _| match pane_id_on(key, server.as_deref()) {
+        Some(pane_id) => live.contains(pane_id),
+        None => true,
+    });
+    if all.len() != before {
+        if let Err(e) = save_local(&all) {
+            log::warn!("[pane-meta] {}", e);
+        }
+    }
+}
+
+/// Replace a pane's metadata, in tmux or (without pane options) locally.
+/// Empty metadata clears it.
+pub async fn set(target: &str, meta: &PaneMeta) -> Result<PaneMeta, String> {
+    let pane_id = super::tmux::pane_id(target).await?;
+    let clear = *meta == PaneMeta::default();
+
+    if super::tmux::supports(super::tmux::Feature::PaneOptions) {
+        let value = if clear {
+            None
+        } else {
+            Some(serde_json::to_string(meta).map_err(|e| e.to_string())?)
+        };
+        super::tmux::set_pane_option(&pane_id, OPTION, value.as_deref()).await?;
+        return Ok(meta.clone());
+    }
+
+    let key = session_key(super::tmux::current_server().as_deref(), &pane_id);
+    let mut all = load_local();
+    if clear {
+        all.remove(&key);
+    } else {
+        all.insert(key, meta.clone());
+    }
+    save_local(&all)?;
+    Ok(meta.clone())
+}
+
+/// Current metadata of a pane (empty when none was set)
+pub async fn get(target: &str) -> Result<PaneMeta, String> {
+    let pane_id = super::tmux::pane_id(target).await?;
+    if super::tmux::supports(super::tmux::Feature::PaneOptions) {
+        let value = super::tmux::pane_option(&pane_id, OPTION).await?;
+        return Ok(resolve(&value, &pane_id, &BTreeMap::new()).unwrap_or_default());
+    }
+    Ok(resolve("", &pane_id, &load_local()).unwrap_or_default())
+}
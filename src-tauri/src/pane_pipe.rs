@@ -0,0 +1,110 @@
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::tmux::Feature;
+
+/// Marks pipes we opened, so one left over from a previous run can be replaced
+const PIPE_OPTION: &str = "@muxtunnel_pipe";
+
+/// Panes (by `session_key` of the pane id) whose output reaches us through `pipe-pane`
+static PIPED: once_cell::sync::Lazy<Mutex<HashSet<String>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Whether `key`'s output is already read through `pipe-pane`
+pub fn is_piped(key: &str) -> bool {
+    PIPED.lock().unwrap().contains(key)
+}
+
+/// Quote an argument for `sh`: single quotes, with embedded ones escaped
+pub fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// Replace whatever is at `path` with a new FIFO
+pub async fn make_fifo(path: &Path) -> Result<(), String> {
+    let _ = std::fs::remove_file(path);
+    let status = tokio::process::Command::new("mkfifo")
+        .arg(path)
+        .status()
+        .await
+        .map_err(|e| format!("Failed to run mkfifo: {}", e))?;
+    if !status.success() {
+        return Err(format!("mkfifo {} failed", path.display()));
+    }
+    Ok(())
+}
+
+/// Pipe a tmux pane's output through a FIFO to the command tracker, pane log
+/// and watches, whether or not the pane is attached. tmux allows one pipe per
+/// pane, so a pipe the user opened is left alone; ours from a previous run is
+/// replaced.
+pub async fn ensure(pane_id: &str, key: &str) -> Result<(), String> {
+    if is_piped(key) {
+        return Ok(());
+    }
+    if super::tmux::pane_option(pane_id, "pane_pipe").await? == "1" {
+        let ours = super::tmux::supports(Feature::PaneOptions)
+            && !super::tmux::pane_option(pane_id, PIPE_OPTION)
+                .await?
+                .is_empty();
+        if !ours {
+            return Err(format!("{} already has a pipe-pane", pane_id));
+        }
+        super::tmux::pipe_pane(pane_id, None).await?;
+    }
+    let dir = super::paths::data_dir().join("pipes");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let fifo = dir.join(format!("{}.fifo", key.replace(['%', '/', ':'], "_")));
+    make_fifo(&fifo).await?;
+
+    PIPED.lock().unwrap().insert(key.to_string());
+    tokio::task::spawn_blocking({
+        let key = key.to_string();
+        let fifo = fifo.clone();
+        move || read_pipe(key, fifo)
+    });
+    let command = format!("cat > {}", shell_quote(&fifo.to_string_lossy()));
+    if let Err(e) = super::tmux::pipe_pane(pane_id, Some(&command)).await {
+        // Release the reader waiting for a writer
+        tokio::task::spawn_blocking(move || {
+            let _ = std::fs::OpenOptions::new().write(true).open(&fifo);
+        });
+        return Err(e);
+    }
+    if super::tmux::supports(Feature::PaneOptions) {
+        let _ = super::tmux::set_pane_option(pane_id, PIPE_OPTION, Some("1")).await;
+    }
+    Ok(())
+}
+
+/// Read a pane's FIFO until tmux closes the pipe (the pane exited)
+fn read_pipe(key: String, fifo: PathBuf) {
+    let _task = super::diagnostics::track("pipe-reader");
+    // Blocks until `cat` opens the other end
+    match std::fs::File::open(&fifo) {
+        Ok(mut file) => {
+            let _ = std::fs::remove_file(&fifo);
+            let mut buf = [0u8; 8192];
+            while let Ok(n) = file.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+                super::shell_integration::observe(&key, &buf[..n]);
+                super::pane_activity::observe(&key, &buf[..n]);
+                super::pane_log::observe(&key, &buf[..n]);
+                super::watches::observe(&key, &buf[..n]);
+            }
+        }
+        Err(e) => {
+            log::warn!("[pipe] Failed to open {}: {}", fifo.display(), e);
+            let _ = std::fs::remove_file(&fifo);
+        }
+    }
+    PIPED.lock().unwrap().remove(&key);
+    super::pane_activity::closed(&key);
+    super::pane_log::closed(&key);
+    super::watches::closed(&key);
+}
@@ -0,0 +1,173 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::settings::expand_tilde;
+
+/// Relocates everything MuxTunnel stores, including settings.json
+pub const DATA_DIR_ENV: &str = "MUXTUNNEL_DATA_DIR";
+/// Same variable Claude itself honors for its config root
+pub const CLAUDE_DIR_ENV: &str = "CLAUDE_CONFIG_DIR";
+
+/// Entries of the legacy directory that are user-authored config rather than state
+const CONFIG_ENTRIES: &[&str] = &["settings.json", "layouts.json", "templates"];
+
+/// Left in the legacy directory once its contents have been copied
+const MIGRATED_POINTER: &str = "MOVED.txt";
+
+fn env_dir(var: &str) -> Option<PathBuf> {
+    std::env::var(var)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(|v| expand_tilde(v.trim()))
+}
+
+fn home_dir(name: &str) -> PathBuf {
+    dirs::home_dir().unwrap_or_default().join(name)
+}
+
+/// `~/.muxtunnel`, where everything lived before XDG support
+fn legacy_dir() -> PathBuf {
+    home_dir(".muxtunnel")
+}
+
+/// XDG base directories are used on Linux and wherever XDG_CONFIG_HOME is set;
+/// elsewhere (macOS) state stays in `~/.muxtunnel`
+fn use_xdg() -> bool {
+    cfg!(target_os = "linux") || env_dir("XDG_CONFIG_HOME").is_some()
+}
+
+fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    env_dir(var)
+        .unwrap_or_else(|| home_dir(fallback))
+        .join("muxtunnel")
+}
+
+/// Where settings.json, layouts and templates live: `$MUXTUNNEL_DATA_DIR`,
+/// else `$XDG_CONFIG_HOME/muxtunnel`, else `~/.muxtunnel`.
+/// `paths.dataDir` can't apply here since settings.json is read from this directory.
+pub fn config_dir() -> PathBuf {
+    if let Some(dir) = env_dir(DATA_DIR_ENV) {
+        return dir;
+    }
+    if use_xdg() {
+        xdg_dir("XDG_CONFIG_HOME", ".config")
+    } else {
+        legacy_dir()
+    }
+}
+
+fn default_state_dir() -> PathBuf {
+    if use_xdg() {
+        xdg_dir("XDG_STATE_HOME", ".local/state")
+    } else {
+        legacy_dir()
+    }
+}
+
+/// MuxTunnel's state (history, session order, snapshots, ...): `$MUXTUNNEL_DATA_DIR`,
+/// else `paths.dataDir`, else `$XDG_STATE_HOME/muxtunnel`, else `~/.muxtunnel`.
+/// Tests get a temporary directory of their own.
+pub fn data_dir() -> PathBuf {
+    if cfg!(test) {
+        return std::env::temp_dir().join(format!("muxtunnel-test-{}", std::process::id()));
+    }
+    if let Some(dir) = env_dir(DATA_DIR_ENV) {
+        return dir;
+    }
+    super::settings::get_settings()
+        .settings
+        .paths
+        .data_dir
+        .filter(|d| !d.trim().is_empty())
+        .map(|d| expand_tilde(d.trim()))
+        .unwrap_or_else(default_state_dir)
+}
+
+/// Claude's config root: `$CLAUDE_CONFIG_DIR`, else `paths.claudeDir`, else `~/.claude`
+pub fn claude_dir() -> PathBuf {
+    if let Some(dir) = env_dir(CLAUDE_DIR_ENV) {
+        return dir;
+    }
+    super::settings::get_settings()
+        .settings
+        .paths
+        .claude_dir
+        .filter(|d| !d.trim().is_empty())
+        .map(|d| expand_tilde(d.trim()))
+        .unwrap_or_else(|| home_dir(".claude"))
+}
+
+/// Per-project Claude session transcripts
+pub fn claude_projects_dir() -> PathBuf {
+    claude_dir().join("projects")
+}
+
+/// Copy `from` to `to` recursively, skipping anything that already exists at `to`
+fn copy_missing(from: &Path, to: &Path) -> std::io::Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_missing(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else if !to.exists() {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+/// One-time copy of `~/.muxtunnel` into the XDG config and state directories.
+/// The legacy directory is left in place with a pointer file, which also marks
+/// the migration as done. Must run before settings are loaded.
+pub fn migrate_legacy_dir() {
+    if env_dir(DATA_DIR_ENV).is_some() || !use_xdg() {
+        return;
+    }
+    let legacy = legacy_dir();
+    if !legacy.is_dir() || legacy.join(MIGRATED_POINTER).exists() {
+        return;
+    }
+    let (config, state) = (config_dir(), default_state_dir());
+
+    let entries = match fs::read_dir(&legacy) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::warn!("[paths] Can't read {}: {}", legacy.display(), e);
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let dest = if CONFIG_ENTRIES.contains(&name.to_string_lossy().as_ref()) {
+            &config
+        } else {
+            &state
+        };
+        if let Err(e) = copy_missing(&entry.path(), &dest.join(&name)) {
+            // Leave the pointer unwritten so the next launch retries
+            log::warn!("[paths] Failed to migrate {:?}: {}", name, e);
+            return;
+        }
+    }
+
+    let pointer = format!(
+        "MuxTunnel now keeps its files in:\n\n  config: {}\n  state:  {}\n\n\
+         The files here were copied there and are no longer read.\n\
+         Set {} to keep using a single directory.\n",
+        config.display(),
+        state.display(),
+        DATA_DIR_ENV
+    );
+    if let Err(e) = fs::write(legacy.join(MIGRATED_POINTER), pointer) {
+        log::warn!("[paths] Failed to write migration pointer: {}", e);
+    }
+    log::info!(
+        "[paths] Migrated {} to {} and {}",
+        legacy.display(),
+        config.display(),
+        state.display()
+    );
+}
@@ -0,0 +1,315 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::pty_manager::session_key;
+use super::session_order::SessionOrder;
+
+/// Cached previews older than this are re-captured when warming
+const PREVIEW_TTL: Duration = Duration::from_secs(10);
+
+static CACHE: once_cell::sync::Lazy<Mutex<HashMap<String, CacheEntry>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The session being viewed and the zoom its neighbors are shown at
+static VIEWING: once_cell::sync::Lazy<Mutex<Viewing>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(Viewing::default()));
+
+#[derive(Default)]
+struct Viewing {
+    server: Option<String>,
+    session: Option<String>,
+    zoom: Option<u32>,
+}
+
+struct CacheEntry {
+    preview: SessionPreview,
+    captured: Instant,
+    last_used: Instant,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PanePreview {
+    pub target: String,
+    pub pane_id: String,
+    pub cols: u32,
+    pub rows: u32,
+    pub left: u32,
+    pub top: u32,
+    /// Captured with escapes; includes scrollback above the screen when zoomed out
+    pub content: String,
+}
+
+/// The panes of a session's active window
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionPreview {
+    pub session: String,
+    /// Percent; at 50 a card shows twice the screen's rows
+    pub zoom: u32,
+    pub panes: Vec<PanePreview>,
+    pub captured_at: u64,
+}
+
+fn cache_key(server: Option<&str>, session: &str, zoom: u32) -> String {
+    format!("{}@{}", session_key(server, session), zoom)
+}
+
+/// Remember which session is on screen so its neighbors get warmed
+pub fn set_viewing(server: Option<String>, session: &str) {
+    let mut viewing = VIEWING.lock().unwrap();
+    viewing.server = server;
+    viewing.session = Some(session.to_string());
+}
+
+/// Capture the active window of `session` on the current server
+async fn capture(session: &str, zoom: u32) -> Result<SessionPreview, String> {
+    let rows = super::tmux::list_pane_fields(&[
+        "#{session_name}",
+        "#{window_active}",
+        "#{session_name}:#{window_index}.#{pane_index}",
+        "#{pane_id}",
+        "#{pane_width}",
+        "#{pane_height}",
+        "#{pane_left}",
+        "#{pane_top}",
+    ])
+    .await?;
+    let mut panes = Vec::new();
+    for row in rows {
+        let Ok([name, active, target, pane_id, cols, pane_rows, left, top]) =
+            <[String; 8]>::try_from(row)
+        else {
+            continue;
+        };
+        if name != session || active != "1" {
+            continue;
+        }
+        let pane_rows: u32 = pane_rows.parse().unwrap_or(24);
+        // Zoomed out, the same card fits more lines: take them from scrollback
+        let lines = pane_rows * 100 / zoom.max(1);
+        let start = -(lines.saturating_sub(pane_rows) as i32);
+        let content = super::tmux::capture_pane_range(&pane_id, start, None, true).await?;
+        panes.push(PanePreview {
+            target,
+            pane_id,
+            cols: cols.parse().unwrap_or(80),
+            rows: pane_rows,
+            left: left.parse().unwrap_or(0),
+            top: top.parse().unwrap_or(0),
+            content,
+        });
+    }
+    if panes.is_empty() {
+        return Err(format!("Session not found: {}", session));
+    }
+    Ok(SessionPreview {
+        session: session.to_string(),
+        zoom,
+        panes,
+        captured_at: super::activity::now_unix(),
+    })
+}
+
+/// A pane's current screen as plain text
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaneSnapshot {
+    pub target: String,
+    pub cols: u32,
+    pub rows: u32,
+    /// The screen's last rows, at most `max_lines`, trailing blanks trimmed
+    pub lines: Vec<String>,
+    /// "pty" when read from an attached PTY's screen, else "capture"
+    pub source: String,
+}
+
+/// The last `max_lines` (default: all) rows of `target`'s screen on the
+/// current server. A pane with a PTY attached is read from its screen;
+/// others are captured, so previews never attach a PTY.
+pub async fn pane(target: &str, max_lines: Option<u32>) -> Result<PaneSnapshot, String> {
+    let pane = super::multiplexer::current()
+        .pane_info(target)
+        .await
+        .ok_or_else(|| format!("Pane not found: {}", target))?;
+    let max_lines = max_lines.unwrap_or(pane.rows).min(pane.rows);
+    let pty = super::screen::for_pane(&session_key(
+        super::tmux::current_server().as_deref(),
+        &pane.pane_id,
+    ));
+    let from_pty = pty.and_then(|key| {
+        super::screen::lines(&key, super::screen::Region::pane_tail(&pane, max_lines))
+    });
+    let (lines, source) = match from_pty {
+        Some(lines) => (lines, "pty"),
+        None => {
+            let content = super::tmux::capture_pane_range(&pane.pane_id, 0, None, false).await?;
+            let lines: Vec<String> = content.lines().map(|l| l.trim_end().to_string()).collect();
+            let skip = lines.len().saturating_sub(max_lines as usize);
+            (lines.into_iter().skip(skip).collect(), "capture")
+        }
+    };
+    Ok(PaneSnapshot {
+        target: pane.target,
+        cols: pane.cols,
+        rows: pane.rows,
+        lines,
+        source: source.to_string(),
+    })
+}
+
+fn store(server: Option<&str>, preview: SessionPreview) {
+    let cache_size = super::settings::get_settings().settings.previews.cache_size;
+    let now = Instant::now();
+    let mut cache = CACHE.lock().unwrap();
+    cache.insert(
+        cache_key(server, &preview.session, preview.zoom),
+        CacheEntry {
+            preview,
+            captured: now,
+            last_used: now,
+        },
+    );
+    // Evict least recently used
+    while cache.len() > cache_size.max(1) {
+        let Some(oldest) = cache
+            .iter()
+            .min_by_key(|(_, e)| e.last_used)
+            .map(|(k, _)| k.clone())
+        else {
+            break;
+        };
+        cache.remove(&oldest);
+    }
+    drop(cache);
+    super::memory::enforce();
+}
+
+/// Cached previews, sized by their captured content
+pub(crate) fn buffers() -> Vec<super::memory::Buffer> {
+    CACHE
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(key, entry)| super::memory::Buffer {
+            key: key.clone(),
+            bytes: entry.preview.panes.iter().map(|p| p.content.len()).sum(),
+            last_used: entry.last_used,
+        })
+        .collect()
+}
+
+pub(crate) fn evict(key: &str) {
+    CACHE.lock().unwrap().remove(key);
+}
+
+/// Preview of `session` at `zoom` on the current server, from cache when warmed.
+/// Also makes `zoom` the level neighbors are warmed at.
+pub async fn get(session: &str, zoom: Option<u32>) -> Result<SessionPreview, String> {
+    let settings = super::settings::get_settings().settings.previews;
+    let zoom = zoom.unwrap_or(settings.zoom).clamp(10, 400);
+    VIEWING.lock().unwrap().zoom = Some(zoom);
+
+    let server = super::tmux::current_server();
+    let key = cache_key(server.as_deref(), session, zoom);
+    if let Some(entry) = CACHE.lock().unwrap().get_mut(&key) {
+        if entry.captured.elapsed() < PREVIEW_TTL {
+            entry.last_used = Instant::now();
+            return Ok(entry.preview.clone());
+        }
+    }
+    let preview = capture(session, zoom).await?;
+    store(server.as_deref(), preview.clone());
+    Ok(preview)
+}
+
+/// Drop cached previews of `target`'s session (a session, window or pane target)
+pub async fn invalidate(target: &str) {
+    let session = super::tmux::session_of(target).await;
+    let prefix = format!(
+        "{}@",
+        session_key(super::tmux::current_server().as_deref(), &session)
+    );
+    CACHE
+        .lock()
+        .unwrap()
+        .retain(|key, _| !key.starts_with(&prefix));
+}
+
+/// Sessions within `warm_count` of `current` in UI order (saved order first,
+/// then the rest as tmux lists them)
+fn neighbors(order: &[String], live: &[String], current: &str, warm_count: usize) -> Vec<String> {
+    let mut ordered: Vec<&String> = order.iter().filter(|s| live.contains(s)).collect();
+    ordered.extend(live.iter().filter(|s| !order.contains(s)));
+    let Some(index) = ordered.iter().position(|s| *s == current) else {
+        return vec![];
+    };
+    let start = index.saturating_sub(warm_count);
+    let end = (index + warm_count + 1).min(ordered.len());
+    ordered[start..end]
+        .iter()
+        .filter(|s| **s != current)
+        .map(|s| s.to_string())
+        .collect()
+}
+
+async fn warm(order: &SessionOrder) {
+    let (server, current, zoom) = {
+        let viewing = VIEWING.lock().unwrap();
+        (
+            viewing.server.clone(),
+            viewing.session.clone(),
+            viewing.zoom,
+        )
+    };
+    let Some(current) = current else {
+        return;
+    };
+    let settings = super::settings::get_settings().settings.previews;
+    if settings.warm_count == 0 {
+        return;
+    }
+    let zoom = zoom.unwrap_or(settings.zoom);
+
+    super::tmux::with_server(server.clone(), async {
+        let Ok(rows) = super::tmux::list_pane_fields(&["#{session_name}"]).await else {
+            return;
+        };
+        let mut live: Vec<String> = rows
+            .into_iter()
+            .filter_map(|r| r.into_iter().next())
+            .collect();
+        live.dedup();
+
+        for session in neighbors(&order.get(), &live, &current, settings.warm_count) {
+            let key = cache_key(server.as_deref(), &session, zoom);
+            let fresh = CACHE
+                .lock()
+                .unwrap()
+                .get(&key)
+                .map(|e| e.captured.elapsed() < PREVIEW_TTL)
+                .unwrap_or(false);
+            if fresh {
+                continue;
+            }
+            match super::probes::limited(capture(&session, zoom)).await {
+                Ok(preview) => store(server.as_deref(), preview),
+                Err(e) => log::debug!("[previews] Failed to warm {}: {}", session, e),
+            }
+        }
+    })
+    .await;
+}
+
+/// Keep previews of the sessions next to the viewed one captured
+pub fn start_warmer(order: Arc<SessionOrder>) {
+    tauri::async_runtime::spawn(async move {
+        let _task = super::diagnostics::track("preview-warmer");
+        loop {
+            super::visibility::sleep(super::visibility::background()).await;
+            warm(&order).await;
+        }
+    });
+}
@@ -0,0 +1,34 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
+
+/// The budget and the limit it was created with; replaced when
+/// `advanced.maxConcurrentProbes` changes, while permits of the old one drain
+static BUDGET: once_cell::sync::Lazy<Mutex<(usize, Arc<Semaphore>)>> =
+    once_cell::sync::Lazy::new(|| Mutex::new((0, Arc::new(Semaphore::new(0)))));
+
+fn semaphore() -> Option<Arc<Semaphore>> {
+    let limit = super::settings::get_settings()
+        .settings
+        .advanced
+        .max_concurrent_probes;
+    if limit == 0 {
+        return None;
+    }
+    let mut budget = BUDGET.lock().unwrap();
+    if budget.0 != limit {
+        *budget = (limit, Arc::new(Semaphore::new(limit)));
+    }
+    Some(budget.1.clone())
+}
+
+/// Run a probe (a per-pane subprocess such as a capture or cwd lookup) within
+/// the budget shared by every enrichment subsystem, so fanning out over
+/// hundreds of panes doesn't spawn hundreds of processes at once
+pub async fn limited<F: Future>(probe: F) -> F::Output {
+    let _permit = match semaphore() {
+        Some(semaphore) => semaphore.acquire_owned().await.ok(),
+        None => None,
+    };
+    probe.await
+}
@@ -0,0 +1,192 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::claude_sessions::ClaudeTracker;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// After a delivery, wait this long before judging the pane idle again, so
+/// the new turn has time to show up as "thinking"
+const SETTLE: Duration = Duration::from_secs(5);
+
+static QUEUE: once_cell::sync::Lazy<Mutex<QueueState>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(QueueState::default()));
+
+#[derive(Default)]
+struct QueueState {
+    /// In delivery order
+    prompts: Vec<QueuedPrompt>,
+    next_id: u64,
+    polling: bool,
+    /// `session_key(server, target)` → last delivery
+    delivered_at: HashMap<String, Instant>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedPrompt {
+    pub id: u64,
+    pub target: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    pub text: String,
+    pub queued_at: u64,
+}
+
+/// Queue `text` for the agent in `target` on the current server; it's typed
+/// in once the agent finishes its turn. Prompts to one pane go in order.
+pub fn enqueue(target: &str, text: &str, claude: Arc<ClaudeTracker>) -> QueuedPrompt {
+    let mut state = QUEUE.lock().unwrap();
+    state.next_id += 1;
+    let prompt = QueuedPrompt {
+        id: state.next_id,
+        target: target.to_string(),
+        server: super::tmux::current_server(),
+        text: text.to_string(),
+        queued_at: super::activity::now_unix(),
+    };
+    state.prompts.push(prompt.clone());
+    if !state.polling {
+        state.polling = true;
+        start_poller(claude);
+    }
+    prompt
+}
+
+/// Queued prompts, all or those for `target` on the current server
+pub fn list(target: Option<&str>) -> Vec<QueuedPrompt> {
+    let server = super::tmux::current_server();
+    QUEUE
+        .lock()
+        .unwrap()
+        .prompts
+        .iter()
+        .filter(|p| target.map_or(true, |t| p.target == t && p.server == server))
+        .cloned()
+        .collect()
+}
+
+pub fn cancel(id: u64) -> Result<(), String> {
+    let mut state = QUEUE.lock().unwrap();
+    let before = state.prompts.len();
+    state.prompts.retain(|p| p.id != id);
+    if state.prompts.len() == before {
+        return Err(format!("No queued prompt {}", id));
+    }
+    Ok(())
+}
+
+/// The agent in `target` is mid-turn: the pane shows the thinking indicator
+/// or its Claude session was last seen thinking
+async fn busy(target: &str, claude: &ClaudeTracker) -> bool {
+    if super::tmux::is_pane_processing(target).await {
+        return true;
+    }
+    let Some(cwd) = super::tmux::get_pane_cwd(target).await else {
+        return false;
+    };
+    claude
+        .active_session(&cwd)
+        .map(|s| s.status == "thinking")
+        .unwrap_or(false)
+}
+
+/// Type `prompt` into its pane unless it's busy, locked or settling.
+/// `true` once the prompt is finished with (delivered or its pane is gone).
+async fn try_deliver(prompt: &QueuedPrompt, claude: &ClaudeTracker) -> bool {
+    let key = super::pty_manager::session_key(prompt.server.as_deref(), &prompt.target);
+    let settling = QUEUE
+        .lock()
+        .unwrap()
+        .delivered_at
+        .get(&key)
+        .is_some_and(|at| at.elapsed() < SETTLE);
+    if settling {
+        return false;
+    }
+    if !super::tmux::has_target(&prompt.target).await {
+        log::warn!(
+            "[prompt-queue] Dropping prompt {}: {} no longer exists",
+            prompt.id,
+            prompt.target
+        );
+        return true;
+    }
+    if busy(&prompt.target, claude).await {
+        return false;
+    }
+    let cancelled = !QUEUE
+        .lock()
+        .unwrap()
+        .prompts
+        .iter()
+        .any(|p| p.id == prompt.id);
+    if cancelled {
+        return true;
+    }
+    // Don't type over a paste or playback in progress
+    let Ok(_guard) = super::input_lock::hold(&prompt.target, None).await else {
+        return false;
+    };
+    if let Err(e) = super::tmux::send_keys_literal(&prompt.target, &prompt.text).await {
+        log::warn!(
+            "[prompt-queue] Failed to deliver prompt {}: {}",
+            prompt.id,
+            e
+        );
+        return false;
+    }
+    QUEUE
+        .lock()
+        .unwrap()
+        .delivered_at
+        .insert(key, Instant::now());
+    super::activity::record(super::activity::Activity::CommandSent {
+        ts: super::activity::now_unix(),
+        target: prompt.target.clone(),
+        text: super::activity::input_text(&prompt.text),
+    });
+    super::events::emit(super::events::BackendEvent::PromptDelivered {
+        id: prompt.id,
+        target: prompt.target.clone(),
+    });
+    true
+}
+
+/// Deliver queued prompts until the queue is empty
+fn start_poller(claude: Arc<ClaudeTracker>) {
+    tauri::async_runtime::spawn(async move {
+        let _task = super::diagnostics::track("prompt-queue");
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            // Only the head of each pane's queue is eligible
+            let heads: Vec<QueuedPrompt> = {
+                let mut state = QUEUE.lock().unwrap();
+                if state.prompts.is_empty() {
+                    state.polling = false;
+                    state.delivered_at.retain(|_, at| at.elapsed() < SETTLE);
+                    break;
+                }
+                let mut seen = std::collections::HashSet::new();
+                state
+                    .prompts
+                    .iter()
+                    .filter(|p| seen.insert((p.server.clone(), p.target.clone())))
+                    .cloned()
+                    .collect()
+            };
+
+            for prompt in heads {
+                let done =
+                    super::tmux::with_server(prompt.server.clone(), try_deliver(&prompt, &claude))
+                        .await;
+                if done {
+                    QUEUE.lock().unwrap().prompts.retain(|p| p.id != prompt.id);
+                }
+            }
+        }
+    });
+}
@@ -1,33 +1,407 @@
 use portable_pty::{CommandBuilder, NativePtySystem, PtySize, PtySystem};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::io::{Read, Write};
 use std::sync::Arc;
-use tauri::ipc::Channel;
+use tauri::ipc::{Channel, InvokeResponseBody};
 use tokio::sync::Mutex;
 
-/// Tracks all active PTY sessions, keyed by pane target string.
+use super::wire::{Compression, DataTransport};
+
+/// Minimum interval between `pty-stats` events for one PTY
+const STATS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Output buffered between link scans; anything beyond is not scanned
+const MAX_UNSCANNED: usize = 64 * 1024;
+
+/// How long a PTY whose channel failed waits for `reclaim` before the
+/// heartbeat closes it
+const RECLAIM_GRACE: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// Output kept for the next channel while orphaned; older output is dropped
+const MAX_BACKLOG: usize = 256 * 1024;
+
+/// Unacknowledged output at which reading pauses, once the frontend acks
+const HIGH_WATERMARK: usize = 512 * 1024;
+
+/// Unacknowledged output at which reading resumes
+const LOW_WATERMARK: usize = 128 * 1024;
+
+/// How long a paused reader waits for an ack before giving up on flow control
+const ACK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Output gathered into one `data` message during a burst
+const COALESCE_BYTES: usize = 16 * 1024;
+
+/// Quiet time that ends a burst and sends what was gathered
+const COALESCE_DELAY: std::time::Duration = std::time::Duration::from_millis(4);
+
+/// Reads up to this size after a pause are sent at once (typing echo)
+const INTERACTIVE_BYTES: usize = 256;
+
+/// Reads queued between the PTY and the reader task
+const PUMP_QUEUE: usize = 8;
+
+/// Writes queued for a PTY's writer thread; input past this is refused
+const WRITE_QUEUE: usize = 256;
+
+/// Output kept for finding the message a `tmux attach` client exits with
+const EXIT_TAIL: usize = 256;
+
+/// How often PTYs streaming to a view ping it
+const HEARTBEAT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Silence after which a view that has answered pings is taken as gone
+const HEARTBEAT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How long `close` waits for a hung-up child to exit before killing it
+const CLOSE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// The frontend channel a PTY streams to, swappable by `reclaim`
+struct Output {
+    channel: PtyChannel,
+    /// Set when a send failed (webview reloaded or crashed)
+    orphaned_since: Option<std::time::Instant>,
+    /// Output read while orphaned, replayed to the reclaiming channel
+    backlog: Vec<u8>,
+    /// Last output, for LRU eviction of backlogs (see `memory`)
+    last_used: std::time::Instant,
+    /// Recent output, replayed when the target is connected again
+    scrollback: VecDeque<u8>,
+    /// `terminal.scrollbackBytes` when the PTY was opened
+    scrollback_limit: usize,
+    /// Output was dropped from the front of `scrollback`
+    scrollback_truncated: bool,
+    /// Set by `detach`: output is kept but not sent until the next connect
+    detached_since: Option<std::time::Instant>,
+    /// Last title and cwd the program announced (OSC 0/2 and 7), for resumes
+    title: Option<String>,
+    cwd: Option<String>,
+    /// Bytes sent to the channel that `pty_ack` hasn't confirmed yet
+    unacked: usize,
+    /// The channel's frontend acks output, so the reader may pause on it
+    acking: bool,
+    /// Set by `close`: the child's exit isn't reported to the channel
+    closed: bool,
+    /// Last time a channel connected, for evicting past `terminal.maxPtys`
+    attached_at: std::time::Instant,
+    /// Last `pty_pong` from the channel's frontend; unset until it answers a
+    /// ping, so views that don't are never timed out
+    last_pong: Option<std::time::Instant>,
+    /// Size the tmux window last wanted the view to be (see `sync_sizes`)
+    window_size: Option<(u16, u16)>,
+}
+
+/// Focus in and out, as a terminal reports them to the program inside
+const FOCUS_IN: &[u8] = b"\x1b[I";
+const FOCUS_OUT: &[u8] = b"\x1b[O";
+
+/// `session_key` of the PTY whose terminal view has focus
+static FOCUSED: once_cell::sync::Lazy<std::sync::Mutex<Option<String>>> =
+    once_cell::sync::Lazy::new(|| std::sync::Mutex::new(None));
+
+/// Every PTY's output by `session_key`, so `memory` can measure and evict
+/// backlogs without the session map's async lock
+static OUTPUTS: once_cell::sync::Lazy<
+    std::sync::Mutex<HashMap<String, std::sync::Weak<std::sync::Mutex<Output>>>>,
+> = once_cell::sync::Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
+/// Scrollback of PTYs evicted past `terminal.maxPtys` by `session_key`,
+/// oldest first, with when they were evicted
+type EvictedScrollback = VecDeque<(String, Vec<u8>, std::time::Instant)>;
+
+/// Replayed by the next connect to the same key
+static EVICTED: once_cell::sync::Lazy<std::sync::Mutex<EvictedScrollback>> =
+    once_cell::sync::Lazy::new(|| std::sync::Mutex::new(VecDeque::new()));
+
+/// Non-empty backlogs of orphaned PTYs, and scrollback of every PTY
+pub(crate) fn buffers() -> Vec<super::memory::Buffer> {
+    let mut outputs = OUTPUTS.lock().unwrap();
+    outputs.retain(|_, output| output.strong_count() > 0);
+    outputs
+        .iter()
+        .filter_map(|(key, output)| {
+            let output = output.upgrade()?;
+            let output = output.lock().unwrap();
+            let bytes = output.backlog.capacity() + output.scrollback.capacity();
+            (bytes > 0).then(|| super::memory::Buffer {
+                key: key.clone(),
+                bytes,
+                last_used: output.last_used,
+            })
+        })
+        .collect()
+}
+
+/// Scrollback kept for evicted PTYs
+pub(crate) fn evicted_buffers() -> Vec<super::memory::Buffer> {
+    EVICTED
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(key, kept, at)| super::memory::Buffer {
+            key: key.clone(),
+            bytes: kept.capacity(),
+            last_used: *at,
+        })
+        .collect()
+}
+
+/// Drop the scrollback kept for the evicted PTY at `key`
+pub(crate) fn evict_evicted(key: &str) {
+    take_evicted(key);
+}
+
+/// Drop a PTY's backlog and scrollback; a later `reclaim` or reconnect
+/// replays only what arrives after
+pub(crate) fn evict_buffer(key: &str) {
+    let output = OUTPUTS.lock().unwrap().get(key).and_then(|o| o.upgrade());
+    if let Some(output) = output {
+        let mut output = output.lock().unwrap();
+        output.backlog = Vec::new();
+        output.scrollback = VecDeque::new();
+        output.scrollback_truncated = true;
+    }
+}
+
+impl Output {
+    /// Send to the channel, or buffer if it has failed. Returns true when
+    /// this send is the one that found the channel gone.
+    fn send_data(&mut self, data: &[u8]) -> bool {
+        self.last_used = std::time::Instant::now();
+        self.keep_scrollback(data);
+        if self.detached_since.is_some() {
+            // Without scrollback, the backlog is what the next connect replays
+            if self.scrollback_limit == 0 {
+                self.keep_backlog(data);
+            }
+            return false;
+        }
+        if self.orphaned_since.is_none()
+            && self
+                .channel
+                .send(PtyMessage::Data {
+                    data: data.to_vec(),
+                })
+                .is_ok()
+        {
+            self.unacked += data.len();
+            return false;
+        }
+        let newly_orphaned = self.orphaned_since.is_none();
+        if newly_orphaned {
+            self.orphaned_since = Some(std::time::Instant::now());
+        }
+        self.keep_backlog(data);
+        newly_orphaned
+    }
+
+    /// Point the PTY at a new channel, which starts with nothing to ack
+    fn set_channel(&mut self, channel: PtyChannel) {
+        self.channel = channel;
+        self.orphaned_since = None;
+        self.detached_since = None;
+        self.unacked = 0;
+        self.acking = false;
+        self.attached_at = std::time::Instant::now();
+        self.last_pong = None;
+        self.window_size = None;
+    }
+
+    /// Send a title or cwd change to an attached channel; orphaned and
+    /// detached PTYs get the latest when resumed
+    fn send_osc(&mut self, osc: super::osc::Osc) {
+        let message = match osc {
+            super::osc::Osc::Title(title) => {
+                self.title = Some(title.clone());
+                PtyMessage::Title { title }
+            }
+            super::osc::Osc::Cwd(cwd) => {
+                self.cwd = Some(cwd.clone());
+                PtyMessage::Cwd { cwd }
+            }
+        };
+        if self.orphaned_since.is_none() && self.detached_since.is_none() {
+            let _ = self.channel.send(message);
+        }
+    }
+
+    /// The last title and cwd, as messages for a new channel
+    fn osc_state(&self) -> Vec<PtyMessage> {
+        let title = self.title.clone().map(|title| PtyMessage::Title { title });
+        let cwd = self.cwd.clone().map(|cwd| PtyMessage::Cwd { cwd });
+        title.into_iter().chain(cwd).collect()
+    }
+
+    fn keep_backlog(&mut self, data: &[u8]) {
+        self.backlog.extend_from_slice(data);
+        if self.backlog.len() > MAX_BACKLOG {
+            let excess = self.backlog.len() - MAX_BACKLOG;
+            self.backlog.drain(..excess);
+        }
+    }
+
+    fn keep_scrollback(&mut self, data: &[u8]) {
+        if self.scrollback_limit == 0 {
+            return;
+        }
+        self.scrollback.extend(data);
+        if self.scrollback.len() > self.scrollback_limit {
+            let excess = self.scrollback.len() - self.scrollback_limit;
+            self.scrollback.drain(..excess);
+            self.scrollback_truncated = true;
+        }
+    }
+
+    /// Scrollback to replay. Once the front has been dropped it may start
+    /// inside an escape sequence or character, so it starts at the first
+    /// escape instead.
+    fn replay(&self) -> Vec<u8> {
+        let skip = if self.scrollback_truncated {
+            self.scrollback
+                .iter()
+                .position(|b| *b == 0x1b)
+                .unwrap_or(self.scrollback.len())
+        } else {
+            0
+        };
+        self.scrollback.iter().skip(skip).copied().collect()
+    }
+}
+
+/// Read the PTY on a thread of its own, so the reader task can wait for
+/// more output with a timeout. The queue is bounded: while the reader task
+/// is paused for acks, reading stops too.
+fn spawn_pump(
+    mut reader: Box<dyn Read + Send>,
+) -> std::sync::mpsc::Receiver<std::io::Result<Vec<u8>>> {
+    let (tx, rx) = std::sync::mpsc::sync_channel(PUMP_QUEUE);
+    std::thread::spawn(move || {
+        let _task = super::diagnostics::track("pty-pump");
+        let mut buf = [0u8; 8192];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    if tx.send(Ok(buf[..n].to_vec())).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(e));
+                    break;
+                }
+            }
+        }
+    });
+    rx
+}
+
+/// Write input to the PTY on a thread of its own, so a PTY that stops
+/// reading blocks that thread rather than a command handler
+fn spawn_writer(
+    mut writer: Box<dyn Write + Send>,
+    target: String,
+) -> std::sync::mpsc::SyncSender<Vec<u8>> {
+    let (tx, rx) = std::sync::mpsc::sync_channel::<Vec<u8>>(WRITE_QUEUE);
+    std::thread::spawn(move || {
+        let _task = super::diagnostics::track("pty-writer");
+        for data in rx {
+            if let Err(e) = writer.write_all(&data).and_then(|_| writer.flush()) {
+                log::warn!("[pty] Write to {} failed: {}", target, e);
+                break;
+            }
+        }
+    });
+    tx
+}
+
+/// Pause while the frontend is behind: past `HIGH_WATERMARK` unacked bytes,
+/// wait for acks down to `LOW_WATERMARK`. Not reading makes the attached
+/// client (and the program behind it) slow down rather than flood the
+/// channel. A frontend that stops acking is assumed to have stopped flow
+/// control until it acks again.
+fn wait_for_acks(output: &std::sync::Mutex<Output>, flow: &std::sync::Condvar, target: &str) {
+    let mut guard = output.lock().unwrap();
+    if !guard.acking || guard.unacked <= HIGH_WATERMARK {
+        return;
+    }
+    let deadline = std::time::Instant::now() + ACK_TIMEOUT;
+    while guard.acking && guard.unacked > LOW_WATERMARK {
+        let now = std::time::Instant::now();
+        if now >= deadline {
+            log::warn!("[pty] No acks from {} in {:?}; unpausing", target, ACK_TIMEOUT);
+            guard.acking = false;
+            break;
+        }
+        guard = flow.wait_timeout(guard, deadline - now).unwrap().0;
+    }
+}
+
+/// Scan complete lines of buffered output for links, keeping the trailing
+/// partial line so a link split across reads is still found
+fn scan_links(server: Option<&str>, target: &str, unscanned: &mut Vec<u8>, cwd: Option<&str>) {
+    let Some(end) = unscanned.iter().rposition(|b| *b == b'\n') else {
+        return;
+    };
+    let text = String::from_utf8_lossy(&unscanned[..end]);
+    super::links::observe(server, target, &text, cwd);
+    unscanned.drain(..=end);
+}
+
+/// Tracks all active PTY sessions, keyed by `session_key`.
 pub type PtySessionMap = HashMap<String, PtyHandle>;
 
+/// Map key for a target: the bare target on the default server,
+/// `server/target` on a registered one.
+pub fn session_key(server: Option<&str>, target: &str) -> String {
+    match server {
+        Some(server) => format!("{}/{}", server, target),
+        None => target.to_string(),
+    }
+}
+
 /// Handle to an active PTY session.
 pub struct PtyHandle {
-    /// Writer for sending input to PTY
-    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+    /// Queue of input for the PTY's writer thread
+    writer: std::sync::mpsc::SyncSender<Vec<u8>>,
     /// Master PTY for resize operations
     master: Arc<Mutex<Box<dyn portable_pty::MasterPty + Send>>>,
     /// Abort handle for the reader task
     abort: tokio::task::AbortHandle,
+    /// Hangs up the child (SIGHUP), which a `tmux attach` client takes as a detach
+    killer: std::sync::Mutex<Box<dyn portable_pty::ChildKiller + Send + Sync>>,
+    /// PID of the `tmux attach` client process
+    client_pid: Option<u32>,
+    /// Registered tmux server (`None` for the default one) and attach target
+    server: Option<String>,
+    target: String,
+    /// Attached with `tmux attach -r`; writes are refused
+    read_only: bool,
+    /// Wakes the reader paused on unacknowledged output
+    flow: Arc<std::sync::Condvar>,
+    /// Runs a command directly (`connect_direct`) rather than attaching to a target
+    direct: bool,
+    output: Arc<std::sync::Mutex<Output>>,
+    opened_at: std::time::Instant,
 }
 
 impl PtyHandle {
     pub async fn write(&self, data: &[u8]) -> Result<(), String> {
-        let mut writer = self.writer.lock().await;
-        writer
-            .write_all(data)
-            .map_err(|e| format!("PTY write failed: {}", e))?;
-        writer
-            .flush()
-            .map_err(|e| format!("PTY flush failed: {}", e))?;
-        Ok(())
+        if self.read_only {
+            return Err(format!("PTY for {} is read-only", self.target));
+        }
+        // A full queue means the PTY has stopped reading; drop the input
+        // rather than wait behind it
+        self.writer
+            .try_send(data.to_vec())
+            .map_err(|e| match e {
+                std::sync::mpsc::TrySendError::Full(_) => {
+                    format!("PTY input for {} is backed up; input dropped", self.target)
+                }
+                std::sync::mpsc::TrySendError::Disconnected(_) => {
+                    format!("PTY write failed: writer for {} has stopped", self.target)
+                }
+            })
     }
 
     pub async fn resize(&self, cols: u16, rows: u16) -> Result<(), String> {
@@ -39,12 +413,467 @@ impl PtyHandle {
                 pixel_width: 0,
                 pixel_height: 0,
             })
-            .map_err(|e| format!("PTY resize failed: {}", e))
+            .map_err(|e| format!("PTY resize failed: {}", e))?;
+        let key = session_key(self.server.as_deref(), &self.target);
+        super::recording::resized(&key, cols, rows);
+        super::screen::resized(&key, cols, rows);
+        Ok(())
+    }
+
+    pub fn client_pid(&self) -> Option<u32> {
+        self.client_pid
     }
 
+    /// Hang up the child and let the reader drain its last output; a child
+    /// still running after `CLOSE_TIMEOUT` is killed and the reader aborted
     pub fn close(&self) {
-        self.abort.abort();
+        {
+            let mut output = self.output.lock().unwrap();
+            output.closed = true;
+            output.acking = false;
+        }
+        self.flow.notify_all();
+        if let Err(e) = self.killer.lock().unwrap().kill() {
+            log::debug!("[pty] Failed to hang up {}: {}", self.target, e);
+        }
+        let abort = self.abort.clone();
+        let target = self.target.clone();
+        let client_pid = self.client_pid;
+        tauri::async_runtime::spawn(async move {
+            let deadline = tokio::time::Instant::now() + CLOSE_TIMEOUT;
+            while !abort.is_finished() && tokio::time::Instant::now() < deadline {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            }
+            if abort.is_finished() {
+                return;
+            }
+            log::warn!("[pty] {} didn't exit after hangup; killing", target);
+            if let Some(pid) = client_pid {
+                // SAFETY: kill(2) only signals; a pid that already exited fails with ESRCH
+                unsafe {
+                    libc::kill(pid as libc::pid_t, libc::SIGKILL);
+                }
+            }
+            abort.abort();
+        });
+    }
+
+    /// The frontend rendered `bytes` more of the output
+    pub fn ack(&self, bytes: usize) {
+        let mut output = self.output.lock().unwrap();
+        output.acking = true;
+        output.unacked = output.unacked.saturating_sub(bytes);
+        if output.unacked <= LOW_WATERMARK {
+            self.flow.notify_all();
+        }
+    }
+
+    /// The frontend answered a ping
+    pub fn pong(&self) {
+        self.output.lock().unwrap().last_pong = Some(std::time::Instant::now());
+    }
+
+    fn is_detached(&self) -> bool {
+        self.output.lock().unwrap().detached_since.is_some()
+    }
+}
+
+async fn send_focus(sessions: &Arc<Mutex<PtySessionMap>>, key: &str, focused: bool) {
+    if !super::settings::get_settings().settings.terminal.focus_events {
+        return;
+    }
+    let map = sessions.lock().await;
+    if let Some(handle) = map.get(key).filter(|h| !h.read_only) {
+        let _ = handle
+            .write(if focused { FOCUS_IN } else { FOCUS_OUT })
+            .await;
+    }
+}
+
+/// A terminal view gained focus (`Some(key)`) or lost it (`None`): focus-out
+/// to the PTY that had it, focus-in to the new one
+pub async fn set_focus(sessions: &Arc<Mutex<PtySessionMap>>, key: Option<String>) {
+    let previous = std::mem::replace(&mut *FOCUSED.lock().unwrap(), key.clone());
+    if previous == key {
+        return;
+    }
+    if let Some(previous) = previous {
+        send_focus(sessions, &previous, false).await;
+    }
+    if let Some(key) = key {
+        send_focus(sessions, &key, true).await;
+    }
+}
+
+/// The app window gained or lost focus; tell the focused terminal view's PTY
+pub async fn window_focus(sessions: &Arc<Mutex<PtySessionMap>>, focused: bool) {
+    let key = FOCUSED.lock().unwrap().clone();
+    if let Some(key) = key {
+        send_focus(sessions, &key, focused).await;
+    }
+}
+
+/// Ping every view a PTY streams to. A channel whose send fails, or whose
+/// frontend answered pings before but has been silent for
+/// `HEARTBEAT_TIMEOUT` (a reloaded webview can leave a channel that accepts
+/// sends but goes nowhere), is orphaned: the PTY buffers its output and is
+/// closed unless reclaimed within the grace period. Expiring orphans here
+/// rather than where they're found covers PTYs that go quiet once orphaned.
+pub fn start_heartbeat(sessions: Arc<Mutex<PtySessionMap>>) {
+    tauri::async_runtime::spawn(async move {
+        let _task = super::diagnostics::track("pty-heartbeat");
+        loop {
+            tokio::time::sleep(HEARTBEAT_INTERVAL).await;
+            let gone: Vec<String> = {
+                let mut map = sessions.lock().await;
+                let expired: Vec<String> = map
+                    .iter()
+                    .filter(|(_, h)| {
+                        h.output
+                            .lock()
+                            .unwrap()
+                            .orphaned_since
+                            .is_some_and(|at| at.elapsed() > RECLAIM_GRACE)
+                    })
+                    .map(|(key, _)| key.clone())
+                    .collect();
+                for key in expired {
+                    if let Some(handle) = map.remove(&key) {
+                        log::info!("[pty] {} was not reclaimed; closing", handle.target);
+                        handle.close();
+                    }
+                }
+                map.values()
+                    .filter_map(|h| {
+                        let mut output = h.output.lock().unwrap();
+                        if output.closed
+                            || output.orphaned_since.is_some()
+                            || output.detached_since.is_some()
+                        {
+                            return None;
+                        }
+                        let silent = output
+                            .last_pong
+                            .is_some_and(|at| at.elapsed() > HEARTBEAT_TIMEOUT);
+                        if !silent && output.channel.send(PtyMessage::Ping).is_ok() {
+                            return None;
+                        }
+                        output.orphaned_since = Some(std::time::Instant::now());
+                        output.acking = false;
+                        h.flow.notify_all();
+                        Some(h.target.clone())
+                    })
+                    .collect()
+            };
+            for target in gone {
+                log::info!("[pty] View of {} stopped answering; awaiting reclaim", target);
+            }
+        }
+    });
+}
+
+/// Tell views whose tmux window no longer matches their PTY's size (another
+/// client attached under `window-size smallest`, a manual `resize-window`)
+/// the size the window wants, and when it matches again. A view resizing
+/// its PTY (`pty_send`) resizes the client, which tmux follows where its
+/// `window-size` option lets it.
+async fn sync_sizes(sessions: &Arc<Mutex<PtySessionMap>>) {
+    let mut by_server: HashMap<Option<String>, Vec<(String, u32)>> = HashMap::new();
+    for (key, h) in sessions.lock().await.iter() {
+        let streaming = {
+            let output = h.output.lock().unwrap();
+            output.orphaned_since.is_none() && output.detached_since.is_none()
+        };
+        if let Some(pid) = h.client_pid.filter(|_| streaming && !h.direct) {
+            by_server
+                .entry(h.server.clone())
+                .or_default()
+                .push((key.clone(), pid));
+        }
+    }
+    for (server, ptys) in by_server {
+        let Ok(sizes) = super::tmux::with_server(server, super::tmux::client_window_sizes()).await
+        else {
+            continue;
+        };
+        let map = sessions.lock().await;
+        for (key, pid) in ptys {
+            let (Some(handle), Some(&wanted)) = (map.get(&key), sizes.get(&pid)) else {
+                continue;
+            };
+            let Ok(size) = handle.master.lock().await.get_size() else {
+                continue;
+            };
+            let mut output = handle.output.lock().unwrap();
+            let previous = output.window_size.replace(wanted);
+            let matches = wanted == (size.cols, size.rows);
+            // Report a mismatch, and the end of one
+            if previous != Some(wanted) && (!matches || previous.is_some()) {
+                let (cols, rows) = wanted;
+                let _ = output.channel.send(PtyMessage::Resize { cols, rows });
+            }
+        }
+    }
+}
+
+/// Keep views of tmux PTYs sized like the windows they show
+pub fn start_size_sync(sessions: Arc<Mutex<PtySessionMap>>) {
+    tauri::async_runtime::spawn(async move {
+        let _task = super::diagnostics::track("pty-size-sync");
+        loop {
+            super::visibility::sleep(super::visibility::tick()).await;
+            sync_sizes(&sessions).await;
+        }
+    });
+}
+
+/// A PTY the backend holds, as `pty_list` reports it
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PtyInfo {
+    pub server: Option<String>,
+    pub target: String,
+    pub cols: u16,
+    pub rows: u16,
+    /// Seconds since the PTY was opened
+    pub age_secs: u64,
+    /// Channels streaming its output: 1 while attached, 0 once detached or
+    /// orphaned
+    pub subscribers: usize,
+    pub read_only: bool,
+    pub direct: bool,
+    pub client_pid: Option<u32>,
+}
+
+/// Every PTY in `sessions`, oldest first
+pub async fn list(sessions: &Arc<Mutex<PtySessionMap>>) -> Vec<PtyInfo> {
+    let map = sessions.lock().await;
+    let mut ptys = Vec::with_capacity(map.len());
+    for handle in map.values() {
+        let size = handle.master.lock().await.get_size().ok();
+        let attached = {
+            let output = handle.output.lock().unwrap();
+            output.orphaned_since.is_none() && output.detached_since.is_none()
+        };
+        ptys.push(PtyInfo {
+            server: handle.server.clone(),
+            target: handle.target.clone(),
+            cols: size.map(|s| s.cols).unwrap_or(0),
+            rows: size.map(|s| s.rows).unwrap_or(0),
+            age_secs: handle.opened_at.elapsed().as_secs(),
+            subscribers: usize::from(attached),
+            read_only: handle.read_only,
+            direct: handle.direct,
+            client_pid: handle.client_pid,
+        });
     }
+    ptys.sort_by_key(|p| std::cmp::Reverse(p.age_secs));
+    ptys
+}
+
+/// Close every PTY in `sessions`; returns how many were closed
+pub async fn close_all(sessions: &Arc<Mutex<PtySessionMap>>) -> usize {
+    let handles: Vec<PtyHandle> = sessions.lock().await.drain().map(|(_, h)| h).collect();
+    for handle in &handles {
+        handle.close();
+    }
+    log::info!("[pty] Closed all {} PTYs", handles.len());
+    handles.len()
+}
+
+/// Past `terminal.maxPtys` attached PTYs, close the least recently connected
+/// ones, those not streaming to a view first. Their scrollback is kept for
+/// the next connect; a view still streaming gets an `exit` with reason
+/// "evicted". `keep` (the PTY just opened), the focused PTY and direct PTYs
+/// are never evicted.
+async fn enforce_cap(sessions: &Arc<Mutex<PtySessionMap>>, keep: &str) {
+    let max = super::settings::get_settings().settings.terminal.max_ptys;
+    if max == 0 {
+        return;
+    }
+    let focused = FOCUSED.lock().unwrap().clone();
+    let mut map = sessions.lock().await;
+    let attached = map.values().filter(|h| !h.direct).count();
+    if attached <= max {
+        return;
+    }
+    let mut candidates: Vec<(bool, std::time::Instant, String)> = map
+        .iter()
+        .filter(|(key, h)| !h.direct && *key != keep && focused.as_ref() != Some(*key))
+        .map(|(key, h)| {
+            let output = h.output.lock().unwrap();
+            let streaming = output.orphaned_since.is_none() && output.detached_since.is_none();
+            (streaming, output.attached_at, key.clone())
+        })
+        .collect();
+    candidates.sort();
+    for (streaming, _, key) in candidates.into_iter().take(attached - max) {
+        let Some(handle) = map.remove(&key) else {
+            continue;
+        };
+        let kept = {
+            let mut output = handle.output.lock().unwrap();
+            if streaming {
+                let _ = output.channel.send(PtyMessage::Exit {
+                    code: None,
+                    signal: None,
+                    reason: Some("evicted".to_string()),
+                });
+            }
+            if output.scrollback_limit > 0 {
+                output.replay()
+            } else {
+                std::mem::take(&mut output.backlog)
+            }
+        };
+        {
+            let mut evicted = EVICTED.lock().unwrap();
+            evicted.retain(|(k, _, _)| *k != key);
+            evicted.push_back((key.clone(), kept, std::time::Instant::now()));
+            while evicted.len() > max {
+                evicted.pop_front();
+            }
+        }
+        super::memory::enforce();
+        handle.close();
+        log::info!("[pty] Evicted {} (over {} PTYs)", handle.target, max);
+        super::events::emit(super::events::BackendEvent::PtyDropped {
+            server: handle.server.clone(),
+            target: handle.target.clone(),
+            reason: "evicted".to_string(),
+        });
+    }
+}
+
+/// Scrollback kept when the PTY at `key` was evicted, if any
+fn take_evicted(key: &str) -> Option<Vec<u8>> {
+    let mut evicted = EVICTED.lock().unwrap();
+    let at = evicted.iter().position(|(k, _, _)| k == key)?;
+    evicted.remove(at).map(|(_, kept, _)| kept)
+}
+
+/// How often `start_sweeper` runs `gc`
+const SWEEP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// A PTY entry removed by `gc`
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DroppedPty {
+    pub server: Option<String>,
+    pub target: String,
+    /// "reader-exited", "client-exited", "target-missing" or "detach-expired"
+    pub reason: String,
+}
+
+/// Close and remove entries whose reader task ended, whose `tmux attach`
+/// client is gone, whose target no longer exists, or that stayed detached
+/// longer than `terminal.detachedTimeoutSecs`
+pub async fn gc(sessions: &Arc<Mutex<PtySessionMap>>) -> Vec<DroppedPty> {
+    struct Candidate {
+        key: String,
+        server: Option<String>,
+        target: String,
+        client_pid: Option<u32>,
+        reader_finished: bool,
+        direct: bool,
+        detached_for: Option<std::time::Duration>,
+    }
+
+    let candidates: Vec<Candidate> = sessions
+        .lock()
+        .await
+        .iter()
+        .map(|(key, h)| Candidate {
+            key: key.clone(),
+            server: h.server.clone(),
+            target: h.target.clone(),
+            client_pid: h.client_pid,
+            reader_finished: h.abort.is_finished(),
+            direct: h.direct,
+            detached_for: h
+                .output
+                .lock()
+                .unwrap()
+                .detached_since
+                .map(|at| at.elapsed()),
+        })
+        .collect();
+    if candidates.is_empty() {
+        return vec![];
+    }
+
+    let detached_timeout = std::time::Duration::from_secs(
+        super::settings::get_settings()
+            .settings
+            .terminal
+            .detached_timeout_secs,
+    );
+    let table = super::tmux::get_process_table().await;
+    let mut stale = Vec::new();
+    for c in candidates {
+        // An empty table means ps is unavailable, not that every client died
+        let client_alive =
+            table.is_empty() || c.client_pid.map(|p| table.contains_key(&p)).unwrap_or(true);
+        let reason = if c.reader_finished {
+            "reader-exited"
+        } else if !client_alive {
+            "client-exited"
+        } else if c.detached_for.is_some_and(|d| d > detached_timeout) {
+            "detach-expired"
+        } else if !c.direct
+            && !super::tmux::with_server(c.server.clone(), async {
+                // Inside the scope, so the server picks the backend
+                super::multiplexer::current().has_target(&c.target).await
+            })
+            .await
+        {
+            "target-missing"
+        } else {
+            continue;
+        };
+        let entry = DroppedPty {
+            server: c.server,
+            target: c.target,
+            reason: reason.to_string(),
+        };
+        stale.push((c.key, c.client_pid, entry));
+    }
+
+    let mut dropped = Vec::new();
+    {
+        let mut map = sessions.lock().await;
+        for (key, client_pid, entry) in stale {
+            // Skip entries replaced by a reconnect since the snapshot
+            if map.get(&key).map(|h| h.client_pid != client_pid).unwrap_or(true) {
+                continue;
+            }
+            if let Some(handle) = map.remove(&key) {
+                handle.close();
+            }
+            dropped.push(entry);
+        }
+    }
+
+    for entry in &dropped {
+        log::info!("[pty] Dropped {} ({})", entry.target, entry.reason);
+        super::events::emit(super::events::BackendEvent::PtyDropped {
+            server: entry.server.clone(),
+            target: entry.target.clone(),
+            reason: entry.reason.clone(),
+        });
+    }
+    dropped
+}
+
+/// Periodically run `gc` on the session map
+pub fn start_sweeper(sessions: Arc<Mutex<PtySessionMap>>) {
+    tauri::async_runtime::spawn(async move {
+        let _task = super::diagnostics::track("pty-sweeper");
+        loop {
+            tokio::time::sleep(SWEEP_INTERVAL).await;
+            gc(&sessions).await;
+        }
+    });
 }
 
 /// Message types sent over the Tauri Channel to frontend
@@ -53,36 +882,498 @@ impl PtyHandle {
 pub enum PtyMessage {
     /// Initial pane info
     #[serde(rename = "pane-info")]
-    PaneInfo { pane: super::tmux::TmuxPane },
+    PaneInfo { pane: Box<super::tmux::TmuxPane> },
     /// Binary PTY data encoded as array of bytes
     #[serde(rename = "data")]
     Data { data: Vec<u8> },
-    /// PTY process exited
+    /// PTY data as base64, for channels using that transport
+    #[serde(rename = "data-base64")]
+    DataBase64 { data: String },
+    /// PTY data LZ4-compressed and base64-encoded, for channels that
+    /// negotiated compression; sent this way in every transport
+    #[serde(rename = "data-lz4")]
+    DataLz4 { data: String },
+    /// PTY process exited with `code`, or was killed by `signal`. `reason`
+    /// says why a `tmux attach` client ended (see `tmux::attach_exit_reason`),
+    /// e.g. "detached" or "server-exited", or is "evicted" for a PTY closed
+    /// past `terminal.maxPtys`.
     #[serde(rename = "exit")]
-    Exit { code: Option<i32> },
+    Exit {
+        code: Option<i32>,
+        signal: Option<String>,
+        reason: Option<String>,
+    },
     /// Error
     #[serde(rename = "error")]
     Error { message: String },
+    /// The program set the window title (OSC 0 or 2)
+    #[serde(rename = "title")]
+    Title { title: String },
+    /// The shell reported its working directory (OSC 7)
+    #[serde(rename = "cwd")]
+    Cwd { cwd: String },
+    /// Liveness check; the frontend answers with `pty_pong`
+    #[serde(rename = "ping")]
+    Ping,
+    /// The tmux window wants the view's terminal at this size (another
+    /// client attached, a layout change); resize to it rather than clip
+    #[serde(rename = "resize")]
+    Resize { cols: u16, rows: u16 },
+}
+
+/// How a terminal view connects, the `options` of `pty_connect` and
+/// `pty_shell`
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectOptions {
+    /// Attach with `tmux attach -r`; shell PTYs ignore it
+    #[serde(default)]
+    pub read_only: bool,
+    /// Compression the view accepts for large output ("lz4"), see
+    /// `PtyChannel::negotiate`
+    pub compression: Option<String>,
+}
+
+/// `pty_shell`'s options: what to run, and how the view connects
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellOptions {
+    /// Argv to run; the login shell when empty
+    #[serde(default)]
+    pub command: Vec<String>,
+    pub cwd: Option<String>,
+    #[serde(flatten)]
+    pub connect: ConnectOptions,
+}
+
+/// A terminal view's channel. `data` goes out in the transport chosen when
+/// the view connected, compressed if it negotiated that; other messages are
+/// always JSON.
+#[derive(Clone)]
+pub struct PtyChannel {
+    channel: Channel,
+    transport: DataTransport,
+    compression: Option<Compression>,
+}
+
+impl PtyChannel {
+    pub fn new(channel: Channel) -> Self {
+        PtyChannel {
+            channel,
+            transport: DataTransport::current(),
+            compression: None,
+        }
+    }
+
+    /// Accept the compression a view asked for, if supported
+    pub fn negotiate(mut self, compression: Option<&str>) -> Result<Self, String> {
+        self.compression = Compression::negotiate(compression)?;
+        Ok(self)
+    }
+
+    pub fn send(&self, message: PtyMessage) -> tauri::Result<()> {
+        self.channel
+            .send(encode(message, self.transport, self.compression)?)
+    }
+}
+
+/// A message as a channel using `transport` and `compression` carries it
+pub(crate) fn encode(
+    message: PtyMessage,
+    transport: DataTransport,
+    compression: Option<Compression>,
+) -> serde_json::Result<InvokeResponseBody> {
+    if let (PtyMessage::Data { data }, Some(compression)) = (&message, compression) {
+        if let Some(compressed) = compression.compress(data) {
+            let message = PtyMessage::DataLz4 {
+                data: super::wire::base64(&compressed),
+            };
+            return serde_json::to_string(&message).map(InvokeResponseBody::Json);
+        }
+    }
+    let message = match (message, transport) {
+        (PtyMessage::Data { data }, DataTransport::Raw) => {
+            return Ok(InvokeResponseBody::Raw(data));
+        }
+        (PtyMessage::Data { data }, DataTransport::Base64) => PtyMessage::DataBase64 {
+            data: super::wire::base64(&data),
+        },
+        (message, _) => message,
+    };
+    serde_json::to_string(&message).map(InvokeResponseBody::Json)
+}
+
+/// The `exit` message for a child that ended with `status`
+fn exit_message(
+    status: std::io::Result<portable_pty::ExitStatus>,
+    reason: Option<&str>,
+) -> PtyMessage {
+    let reason = reason.map(str::to_string);
+    let status = match status {
+        Ok(status) => status,
+        Err(e) => {
+            log::warn!("[pty] Failed to wait for child: {}", e);
+            return PtyMessage::Exit {
+                code: None,
+                signal: None,
+                reason,
+            };
+        }
+    };
+    // portable-pty only exposes the signal through its `Display`
+    let signal = status
+        .to_string()
+        .strip_prefix("Terminated by ")
+        .map(str::to_string);
+    PtyMessage::Exit {
+        code: signal.is_none().then_some(status.exit_code() as i32),
+        signal,
+        reason,
+    }
+}
+
+/// Point the PTY for `key` at a new channel (after a webview reload), replaying
+/// output that arrived while it had none
+pub async fn reclaim(
+    key: &str,
+    channel: PtyChannel,
+    sessions: &Arc<Mutex<PtySessionMap>>,
+) -> Result<(), String> {
+    // Not holding the map across the tmux call below
+    let (target, output, flow) = {
+        let map = sessions.lock().await;
+        let handle = map
+            .get(key)
+            .ok_or_else(|| format!("No PTY session for target: {}", key))?;
+        (
+            handle.target.clone(),
+            handle.output.clone(),
+            handle.flow.clone(),
+        )
+    };
+    if let Some(pane) = super::multiplexer::current().pane_info(&target).await {
+        channel
+            .send(PtyMessage::PaneInfo {
+                pane: Box::new(pane),
+            })
+            .map_err(|e| format!("Failed to send pane info: {}", e))?;
+    }
+    let mut output = output.lock().unwrap();
+    if output.closed {
+        return Err(format!("PTY for {} closed before it was reclaimed", target));
+    }
+    let backlog = std::mem::take(&mut output.backlog);
+    if !backlog.is_empty() {
+        channel
+            .send(PtyMessage::Data { data: backlog })
+            .map_err(|e| format!("Failed to replay output: {}", e))?;
+    }
+    for message in output.osc_state() {
+        let _ = channel.send(message);
+    }
+    output.set_channel(channel);
+    flow.notify_all();
+    Ok(())
+}
+
+/// Paste `text` into `target` as one block, so a multi-line snippet isn't
+/// run line by line. Through the PTY at `key` it's wrapped in bracketed-paste
+/// sequences when the program turned that mode on (per the PTY's screen);
+/// otherwise it goes through a tmux buffer and `paste-buffer -p`, which
+/// brackets it if the pane's program asks. Direct PTYs get the text as is.
+pub async fn paste(
+    key: &str,
+    target: &str,
+    text: &str,
+    sessions: &Arc<Mutex<PtySessionMap>>,
+) -> Result<(), String> {
+    static PASTES: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    {
+        let map = sessions.lock().await;
+        if let Some(handle) = map.get(key) {
+            // Pasting through tmux would get past `write`'s check
+            if handle.read_only {
+                return Err(format!("PTY for {} is read-only", handle.target));
+            }
+            let bracketed = super::screen::bracketed_paste(key).unwrap_or(false);
+            if bracketed || handle.direct {
+                // An end marker inside the text would end the paste early
+                let text = text.replace("\x1b[201~", "");
+                let data = if bracketed {
+                    format!("\x1b[200~{}\x1b[201~", text)
+                } else {
+                    text
+                };
+                return handle.write(data.as_bytes()).await;
+            }
+        }
+    }
+    let buffer = format!(
+        "muxtunnel-paste-{}",
+        PASTES.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    );
+    super::tmux::set_buffer(Some(&buffer), text).await?;
+    let pasted = super::tmux::paste_buffer(target, Some(&buffer), true).await;
+    let _ = super::tmux::delete_buffer(&buffer).await;
+    pasted
+}
+
+/// Tee the PTY at `key` into an asciinema cast file at `path`
+pub async fn record_start(
+    key: &str,
+    path: &str,
+    sessions: &Arc<Mutex<PtySessionMap>>,
+) -> Result<super::recording::RecordingInfo, String> {
+    let map = sessions.lock().await;
+    let handle = map
+        .get(key)
+        .ok_or_else(|| format!("No PTY session for target: {}", key))?;
+    let size = handle
+        .master
+        .lock()
+        .await
+        .get_size()
+        .map_err(|e| format!("Failed to get PTY size: {}", e))?;
+    super::recording::start(key, &handle.target, path, size.cols, size.rows)
+}
+
+/// Stop streaming the PTY for `key` but keep it running, with its output
+/// buffered, so the next `connect` to the target resumes it instantly.
+/// Detached PTYs are closed by `gc` after `terminal.detachedTimeoutSecs`.
+pub async fn detach(key: &str, sessions: &Arc<Mutex<PtySessionMap>>) -> Result<(), String> {
+    let map = sessions.lock().await;
+    let handle = map
+        .get(key)
+        .ok_or_else(|| format!("No PTY session for target: {}", key))?;
+    let mut output = handle.output.lock().unwrap();
+    if output.detached_since.is_none() {
+        output.detached_since = Some(std::time::Instant::now());
+    }
+    output.orphaned_since = None;
+    Ok(())
+}
+
+/// Move a live PTY to a new channel: the screen is rebuilt from its
+/// scrollback and the view's size applied, without a new attach
+async fn resume(
+    handle: &PtyHandle,
+    pane: Option<super::tmux::TmuxPane>,
+    cols: u16,
+    rows: u16,
+    channel: PtyChannel,
+) -> Result<(), String> {
+    if let Some(pane) = pane {
+        channel
+            .send(PtyMessage::PaneInfo {
+                pane: Box::new(pane),
+            })
+            .map_err(|e| format!("Failed to send pane info: {}", e))?;
+    }
+    {
+        let mut output = handle.output.lock().unwrap();
+        let replay = if output.scrollback_limit > 0 {
+            output.replay()
+        } else {
+            std::mem::take(&mut output.backlog)
+        };
+        if !replay.is_empty() {
+            channel
+                .send(PtyMessage::Data { data: replay })
+                .map_err(|e| format!("Failed to replay output: {}", e))?;
+        }
+        for message in output.osc_state() {
+            let _ = channel.send(message);
+        }
+        output.set_channel(channel);
+        output.backlog = Vec::new();
+    }
+    handle.flow.notify_all();
+    handle.resize(cols, rows).await
 }
 
 /// Connect to a tmux pane via PTY and stream output through a Tauri Channel.
+/// A `read_only` client (`tmux attach -r`) can watch but not type. When the
+/// target already has a live PTY of the same kind, its scrollback is
+/// replayed to the new channel instead; a detached one is always resumed.
 pub async fn connect(
     target: String,
     cols: u16,
     rows: u16,
-    channel: Channel<PtyMessage>,
+    read_only: bool,
+    channel: PtyChannel,
     sessions: Arc<Mutex<PtySessionMap>>,
 ) -> Result<(), String> {
     // Verify pane exists and get info
-    let pane_info = super::tmux::get_pane_info(&target)
+    let multiplexer = super::multiplexer::current();
+    let pane_info = multiplexer
+        .pane_info(&target)
         .await
         .ok_or_else(|| format!("Pane not found: {}", target))?;
 
+    super::activity::record(super::activity::Activity::SessionOpened {
+        ts: super::activity::now_unix(),
+        session: pane_info.session_name.clone(),
+    });
+    super::previews::set_viewing(super::tmux::current_server(), &pane_info.session_name);
+
+    let focus_events = super::settings::get_settings().settings.terminal.focus_events;
+    if multiplexer.name() == "tmux" && focus_events {
+        super::tmux::enable_focus_events().await;
+    }
+
+    let key = session_key(super::tmux::current_server().as_deref(), &target);
+    let scrollback_limit = super::settings::get_settings()
+        .settings
+        .terminal
+        .scrollback_bytes;
+    {
+        let map = sessions.lock().await;
+        let live = map.get(&key).filter(|h| h.read_only == read_only);
+        if let Some(handle) = live.filter(|h| scrollback_limit > 0 || h.is_detached()) {
+            log::info!("[pty] Resuming {}", target);
+            return resume(handle, Some(pane_info), cols, rows, channel).await;
+        }
+    }
+
     // Send initial pane info
     channel
-        .send(PtyMessage::PaneInfo { pane: pane_info })
+        .send(PtyMessage::PaneInfo {
+            pane: Box::new(pane_info),
+        })
         .map_err(|e| format!("Failed to send pane info: {}", e))?;
 
+    // Build command: tmux attach-session [-r] -t TARGET (or the backend's equivalent)
+    let attach = multiplexer.attach_command(&target, read_only)?;
+    let mut cmd = CommandBuilder::new(&attach.program);
+    cmd.args(&attach.args);
+    for (key, value) in attach.env {
+        cmd.env(key, value);
+    }
+    let cwd = super::tmux::get_pane_cwd(&target).await;
+
+    spawn(
+        Spawn {
+            key,
+            target,
+            cmd,
+            what: format!("{} attach", multiplexer.name()),
+            server: super::tmux::current_server(),
+            cols,
+            rows,
+            read_only,
+            direct: false,
+            cwd,
+        },
+        channel,
+        sessions,
+    )
+    .await
+}
+
+/// Run `command` (the user's login shell when empty) in a PTY of its own,
+/// without a multiplexer, e.g. for a scratch terminal or where tmux isn't
+/// installed. It's addressed as target `shell:<name>` on no server, and
+/// otherwise behaves like an attached PTY: `pty_send`, `pty_detach`,
+/// scrollback replay and `gc` all apply.
+pub async fn connect_direct(
+    name: &str,
+    command: Vec<String>,
+    cwd: Option<String>,
+    cols: u16,
+    rows: u16,
+    channel: PtyChannel,
+    sessions: Arc<Mutex<PtySessionMap>>,
+) -> Result<(), String> {
+    let target = format!("shell:{}", name);
+    let key = session_key(None, &target);
+    let scrollback_limit = super::settings::get_settings()
+        .settings
+        .terminal
+        .scrollback_bytes;
+    {
+        let map = sessions.lock().await;
+        let live = map.get(&key).filter(|h| h.direct);
+        if let Some(handle) = live.filter(|h| scrollback_limit > 0 || h.is_detached()) {
+            log::info!("[pty] Resuming {}", target);
+            return resume(handle, None, cols, rows, channel).await;
+        }
+    }
+
+    let mut cmd = match command.split_first() {
+        Some((program, args)) => {
+            let mut cmd = CommandBuilder::new(program);
+            cmd.args(args);
+            cmd
+        }
+        None => CommandBuilder::new_default_prog(),
+    };
+    let cwd = cwd
+        .map(|dir| super::settings::expand_tilde(&dir))
+        .or_else(dirs::home_dir);
+    if let Some(dir) = &cwd {
+        cmd.cwd(dir);
+    }
+    let what = command.first().cloned().unwrap_or_else(|| "shell".to_string());
+
+    spawn(
+        Spawn {
+            key,
+            target,
+            cmd,
+            what,
+            server: None,
+            cols,
+            rows,
+            read_only: false,
+            direct: true,
+            cwd: cwd.map(|dir| dir.to_string_lossy().to_string()),
+        },
+        channel,
+        sessions,
+    )
+    .await
+}
+
+/// A PTY to open
+struct Spawn {
+    key: String,
+    target: String,
+    cmd: CommandBuilder,
+    /// What is spawned, for errors
+    what: String,
+    server: Option<String>,
+    cols: u16,
+    rows: u16,
+    read_only: bool,
+    direct: bool,
+    /// Working directory of the program, for resolving links in its output
+    cwd: Option<String>,
+}
+
+/// Open a PTY running `spawn.cmd`, stream its output to `channel` and track
+/// it in `sessions`, replacing any PTY already at its key
+async fn spawn(
+    spawn: Spawn,
+    channel: PtyChannel,
+    sessions: Arc<Mutex<PtySessionMap>>,
+) -> Result<(), String> {
+    let Spawn {
+        key,
+        target,
+        mut cmd,
+        what,
+        server,
+        cols,
+        rows,
+        read_only,
+        direct,
+        cwd,
+    } = spawn;
+    let scrollback_limit = super::settings::get_settings()
+        .settings
+        .terminal
+        .scrollback_bytes;
+
     // Create PTY
     let pty_system = NativePtySystem::default();
     let pair = pty_system
@@ -94,10 +1385,6 @@ pub async fn connect(
         })
         .map_err(|e| format!("Failed to open PTY: {}", e))?;
 
-    // Build command: tmux attach-session -t TARGET
-    let mut cmd = CommandBuilder::new("tmux");
-    cmd.args(["attach-session", "-t", &target]);
-
     // Set environment
     cmd.env("TERM", "xterm-256color");
     cmd.env("COLORTERM", "truecolor");
@@ -113,10 +1400,12 @@ pub async fn connect(
     }
 
     // Spawn child process
-    let _child = pair
+    let mut child = pair
         .slave
         .spawn_command(cmd)
-        .map_err(|e| format!("Failed to spawn tmux attach: {}", e))?;
+        .map_err(|e| format!("Failed to spawn {}: {}", what, e))?;
+    let client_pid = child.process_id();
+    let killer = std::sync::Mutex::new(child.clone_killer());
 
     // Drop slave immediately — we communicate through master
     drop(pair.slave);
@@ -126,54 +1415,199 @@ pub async fn connect(
         .take_writer()
         .map_err(|e| format!("Failed to take PTY writer: {}", e))?;
 
-    let mut reader = pair
+    let reader = pair
         .master
         .try_clone_reader()
         .map_err(|e| format!("Failed to clone PTY reader: {}", e))?;
 
-    let writer = Arc::new(Mutex::new(writer));
+    let writer = spawn_writer(writer, target.clone());
     let master: Box<dyn portable_pty::MasterPty + Send> = pair.master;
     let master = Arc::new(Mutex::new(master));
 
+    // An evicted PTY's output comes back first, ahead of the fresh attach
+    let mut scrollback = VecDeque::new();
+    if let Some(kept) = take_evicted(&key).filter(|kept| !kept.is_empty()) {
+        let _ = channel.send(PtyMessage::Data { data: kept.clone() });
+        if scrollback_limit > 0 {
+            scrollback.extend(kept);
+        }
+    }
+
     // Spawn reader task
-    let channel_clone = channel.clone();
+    let output = Arc::new(std::sync::Mutex::new(Output {
+        channel,
+        orphaned_since: None,
+        backlog: Vec::new(),
+        last_used: std::time::Instant::now(),
+        scrollback,
+        scrollback_limit,
+        scrollback_truncated: false,
+        detached_since: None,
+        title: None,
+        cwd: None,
+        unacked: 0,
+        acking: false,
+        closed: false,
+        attached_at: std::time::Instant::now(),
+        last_pong: None,
+        window_size: None,
+    }));
+    let flow = Arc::new(std::sync::Condvar::new());
+    let flow_clone = flow.clone();
+    let output_clone = output.clone();
     let target_clone = target.clone();
+    let key_clone = key.clone();
+    let server_clone = server.clone();
+    // Command history and watches are kept per pane, whichever target attached it
+    let history_key = if direct {
+        key.clone()
+    } else {
+        session_key(
+            server.as_deref(),
+            &super::tmux::pane_of(&target).unwrap_or_else(|| target.clone()),
+        )
+    };
+    OUTPUTS
+        .lock()
+        .unwrap()
+        .insert(key.clone(), Arc::downgrade(&output));
+    super::screen::opened(&key, &history_key, client_pid, cols, rows);
     let sessions_clone = sessions.clone();
+    let tmux_client = !direct && super::multiplexer::current().name() == "tmux";
 
+    let rt = tokio::runtime::Handle::current();
     let reader_task = tokio::task::spawn_blocking(move || {
-        let mut buf = [0u8; 8192];
-        loop {
-            match reader.read(&mut buf) {
-                Ok(0) => {
-                    // EOF
-                    let _ = channel_clone.send(PtyMessage::Exit { code: Some(0) });
-                    break;
-                }
-                Ok(n) => {
-                    if channel_clone
-                        .send(PtyMessage::Data {
-                            data: buf[..n].to_vec(),
-                        })
-                        .is_err()
-                    {
-                        // Channel closed (frontend disconnected)
+        let _task = super::diagnostics::track("pty-reader");
+        let chunks = spawn_pump(reader);
+        let mut bytes_out: u64 = 0;
+        let mut messages: u64 = 0;
+        let mut last_stats = std::time::Instant::now();
+        // Backlog and scrollback held at the last stats tick
+        let mut last_kept = 0;
+        // Output since the last link scan
+        let mut unscanned: Vec<u8> = Vec::new();
+        let mut osc = super::osc::Scanner::default();
+        // Links are resolved against the latest OSC 7 directory
+        let mut cwd = cwd;
+        // Output read but not yet sent, and when output was last sent
+        let mut pending: Vec<u8> = Vec::new();
+        let mut last_flush: Option<std::time::Instant> = None;
+        // Last output, which ends with tmux's exit message
+        let mut tail: VecDeque<u8> = VecDeque::new();
+        let mut end: Option<PtyMessage> = None;
+        while end.is_none() {
+            wait_for_acks(&output_clone, &flow_clone, &target_clone);
+            let mut next = chunks.recv().ok();
+            // A small read after a pause is most likely echoed typing; send it
+            // right away. Otherwise gather the burst into one message.
+            let interactive = last_flush.map_or(true, |at| at.elapsed() >= COALESCE_DELAY * 4);
+            loop {
+                match next {
+                    None => {
+                        let reason = tmux_client
+                            .then(|| super::tmux::attach_exit_reason(tail.make_contiguous()))
+                            .flatten();
+                        end = Some(exit_message(child.wait(), reason));
                         break;
                     }
+                    Some(Err(e)) => {
+                        let message = format!("PTY read error: {}", e);
+                        super::diagnostics::record_error(
+                            server_clone.clone(),
+                            &target_clone,
+                            &message,
+                        );
+                        end = Some(PtyMessage::Error { message });
+                        break;
+                    }
+                    Some(Ok(data)) => {
+                        for change in osc.feed(&data) {
+                            if let super::osc::Osc::Cwd(dir) = &change {
+                                cwd = Some(dir.clone());
+                            }
+                            output_clone.lock().unwrap().send_osc(change);
+                        }
+                        super::recording::observe(&key_clone, &data);
+                        super::screen::observe(&key_clone, &data);
+                        if !super::pane_pipe::is_piped(&history_key) {
+                            super::shell_integration::observe(&history_key, &data);
+                            super::watches::observe(&history_key, &data);
+                        }
+                        bytes_out += data.len() as u64;
+                        if unscanned.len() < MAX_UNSCANNED {
+                            unscanned.extend_from_slice(&data);
+                        }
+                        pending.extend_from_slice(&data);
+                        if tmux_client {
+                            tail.extend(&data);
+                            let excess = tail.len().saturating_sub(EXIT_TAIL);
+                            tail.drain(..excess);
+                        }
+                    }
                 }
-                Err(e) => {
-                    let _ = channel_clone.send(PtyMessage::Error {
-                        message: format!("PTY read error: {}", e),
-                    });
+                if pending.len() >= COALESCE_BYTES
+                    || (interactive && pending.len() <= INTERACTIVE_BYTES)
+                {
                     break;
                 }
+                next = match chunks.recv_timeout(COALESCE_DELAY) {
+                    Ok(chunk) => Some(chunk),
+                    Err(std::sync::mpsc::RecvTimeoutError::Timeout) => break,
+                    Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => None,
+                };
+            }
+
+            // A character split across reads waits for the rest of it, so
+            // each message decodes on its own; at the end, send what's left
+            let complete = super::wire::flush_len(&pending, end.is_some());
+            if complete > 0 {
+                let data: Vec<u8> = pending.drain(..complete).collect();
+                last_flush = Some(std::time::Instant::now());
+                messages += 1;
+                if output_clone.lock().unwrap().send_data(&data) {
+                    // Frontend gone; keep the PTY for a `reclaim` within the grace period
+                    log::info!("[pty] Channel for {} closed; awaiting reclaim", target_clone);
+                }
+            }
+            if last_stats.elapsed() >= STATS_INTERVAL {
+                last_stats = std::time::Instant::now();
+                scan_links(
+                    server_clone.as_deref(),
+                    &target_clone,
+                    &mut unscanned,
+                    cwd.as_deref(),
+                );
+                // Attached PTYs grow their scrollback too, not just orphans their backlog
+                let kept = {
+                    let output = output_clone.lock().unwrap();
+                    output.backlog.len() + output.scrollback.len()
+                };
+                if kept > last_kept {
+                    super::memory::enforce();
+                }
+                last_kept = kept;
+                super::events::emit(super::events::BackendEvent::PtyStats {
+                    target: target_clone.clone(),
+                    bytes_out,
+                    messages,
+                });
+            }
+        }
+        if let Some(message) = end {
+            let output = output_clone.lock().unwrap();
+            if !output.closed {
+                let _ = output.channel.send(message);
             }
         }
 
-        // Cleanup
-        let rt = tokio::runtime::Handle::current();
+        // Cleanup, unless a reconnect already replaced this PTY
+        super::recording::closed(&key_clone);
+        super::screen::closed(&key_clone, client_pid);
         rt.block_on(async {
             let mut map = sessions_clone.lock().await;
-            map.remove(&target_clone);
+            if map.get(&key_clone).is_some_and(|h| h.client_pid == client_pid) {
+                map.remove(&key_clone);
+            }
         });
     });
 
@@ -181,17 +1615,136 @@ pub async fn connect(
         writer,
         master,
         abort: reader_task.abort_handle(),
+        killer,
+        client_pid,
+        server,
+        target: target.clone(),
+        read_only,
+        flow,
+        direct,
+        output,
+        opened_at: std::time::Instant::now(),
     };
 
     // Store in session map
     {
         let mut map = sessions.lock().await;
         // Close existing session for this target if any
-        if let Some(old) = map.remove(&target) {
+        if let Some(old) = map.remove(&key) {
             old.close();
         }
-        map.insert(target, handle);
+        map.insert(key.clone(), handle);
     }
+    enforce_cap(&sessions, &key).await;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A private `tmux -L` server, killed when dropped
+    struct TestServer(String);
+
+    impl TestServer {
+        fn tmux(&self, args: &[&str]) -> std::process::Output {
+            std::process::Command::new("tmux")
+                .arg("-L")
+                .arg(&self.0)
+                .args(args)
+                .output()
+                .unwrap()
+        }
+
+        fn clients(&self) -> String {
+            String::from_utf8_lossy(&self.tmux(&["list-clients"]).stdout).into_owned()
+        }
+    }
+
+    impl Drop for TestServer {
+        fn drop(&mut self) {
+            self.tmux(&["kill-server"]);
+        }
+    }
+
+    fn is_running(pid: u32) -> bool {
+        // SAFETY: signal 0 only checks that the process exists
+        unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+    }
+
+    /// A private server per test with session "main", or `None` without tmux
+    fn start_server(test: &str) -> Option<TestServer> {
+        let installed = std::process::Command::new("tmux").arg("-V").output();
+        if installed.is_err() {
+            eprintln!("tmux not installed; skipping");
+            return None;
+        }
+        let server = TestServer(format!("muxtunnel-{}-{}", test, std::process::id()));
+        let created = server.tmux(&["new-session", "-d", "-s", "main", "-x", "80", "-y", "24"]);
+        assert!(created.status.success());
+        Some(server)
+    }
+
+    /// Attach a PTY to session "main" of `server`
+    async fn attach(server: &TestServer, read_only: bool) -> Arc<Mutex<PtySessionMap>> {
+        let sessions: Arc<Mutex<PtySessionMap>> = Arc::default();
+        let channel = PtyChannel::new(Channel::new(|_| Ok(())));
+        let connecting = connect("main".into(), 80, 24, read_only, channel, sessions.clone());
+        super::super::tmux::with_server(Some(server.0.clone()), connecting)
+            .await
+            .unwrap();
+        sessions
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn close_leaves_no_tmux_client() {
+        let Some(server) = start_server("close") else {
+            return;
+        };
+        let sessions = attach(&server, false).await;
+
+        let pid = {
+            let map = sessions.lock().await;
+            let handle = map.values().next().unwrap();
+            let pid = handle.client_pid().unwrap();
+            let attached = tokio::time::Instant::now() + CLOSE_TIMEOUT;
+            while server.clients().is_empty() && tokio::time::Instant::now() < attached {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            }
+            assert!(!server.clients().is_empty(), "client never attached");
+            handle.close();
+            pid
+        };
+
+        let deadline = tokio::time::Instant::now() + CLOSE_TIMEOUT;
+        while (is_running(pid) || !server.clients().is_empty())
+            && tokio::time::Instant::now() < deadline
+        {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert_eq!(server.clients(), "");
+        assert!(!is_running(pid), "client {} still running", pid);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn paste_into_read_only_attach_fails() {
+        let Some(server) = start_server("paste") else {
+            return;
+        };
+        let sessions = attach(&server, true).await;
+        let key = session_key(Some(&server.0), "main");
+
+        let pasted = super::super::tmux::with_server(
+            Some(server.0.clone()),
+            paste(&key, "main", "echo pasted", &sessions),
+        )
+        .await;
+        assert!(pasted.is_err());
+        let screen = server.tmux(&["capture-pane", "-t", "main", "-p"]).stdout;
+        assert!(!String::from_utf8_lossy(&screen).contains("echo pasted"));
+        for handle in sessions.lock().await.values() {
+            handle.close();
+        }
+    }
+}
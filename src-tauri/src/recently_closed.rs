@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use super::tmux::TmuxSession;
+
+/// How many closed sessions are remembered
+const MAX_ENTRIES: usize = 30;
+
+/// Sessions seen by the last `observe`, keyed by `session_key`; `None` until the first listing
+static LAST_SEEN: once_cell::sync::Lazy<Mutex<Option<HashMap<String, ClosedSession>>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClosedSession {
+    pub name: String,
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    /// Unix seconds; 0 while the session is still alive
+    pub closed_at: u64,
+    /// Window names in index order
+    pub windows: Vec<String>,
+}
+
+fn closed_file() -> PathBuf {
+    super::paths::data_dir().join("recently-closed.json")
+}
+
+fn load() -> Vec<ClosedSession> {
+    match fs::read_to_string(closed_file()) {
+        Ok(raw) => serde_json::from_str(&raw).unwrap_or_default(),
+        Err(_) => vec![],
+    }
+}
+
+fn save(entries: &[ClosedSession]) {
+    let path = closed_file();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Err(e) = fs::write(
+        &path,
+        serde_json::to_string_pretty(entries).unwrap_or_default(),
+    ) {
+        log::error!("[recently-closed] Failed to save: {}", e);
+    }
+}
+
+fn summarize(session: &TmuxSession) -> ClosedSession {
+    ClosedSession {
+        name: session.name.clone(),
+        path: session.path.clone(),
+        server: session.server.clone(),
+        closed_at: 0,
+        windows: session.windows.iter().map(|w| w.name.clone()).collect(),
+    }
+}
+
+/// Compare a full session listing with the previous one and remember
+/// sessions that disappeared. A server that listed nothing though it had
+/// sessions before most likely failed to list (restarting, timed out), so
+/// its sessions are kept for the next comparison rather than marked closed.
+pub fn observe(sessions: &[TmuxSession]) {
+    let mut current: HashMap<String, ClosedSession> = sessions
+        .iter()
+        .map(|s| {
+            let key = super::pty_manager::session_key(s.server.as_deref(), &s.name);
+            (key, summarize(s))
+        })
+        .collect();
+    let listed: HashSet<Option<String>> = sessions.iter().map(|s| s.server.clone()).collect();
+
+    let now = super::activity::now_unix();
+    let mut closed = Vec::new();
+    {
+        let mut last_seen = LAST_SEEN.lock().unwrap();
+        let previous = last_seen.take().unwrap_or_default();
+        for (key, mut session) in previous {
+            if current.contains_key(&key) {
+                continue;
+            }
+            if !listed.contains(&session.server) {
+                current.insert(key, session);
+                continue;
+            }
+            session.closed_at = now;
+            closed.push(session);
+        }
+        *last_seen = Some(current);
+    }
+    if closed.is_empty() {
+        return;
+    }
+
+    let mut entries = load();
+    for session in closed {
+        log::info!("[recently-closed] {} closed", session.name);
+        entries.retain(|e| !(e.name == session.name && e.server == session.server));
+        entries.insert(0, session);
+    }
+    entries.truncate(MAX_ENTRIES);
+    save(&entries);
+}
+
+/// Most recently closed first
+pub fn list() -> Vec<ClosedSession> {
+    load()
+}
+
+/// Recreate a closed session with its cwd and window names (or from `template`),
+/// and drop it from the list
+pub async fn reopen(
+    name: &str,
+    template: Option<&super::templates::SessionTemplate>,
+) -> Result<String, String> {
+    let server = super::tmux::current_server();
+    let mut entries = load();
+    let position = entries
+        .iter()
+        .position(|e| e.name == name && e.server == server)
+        .ok_or_else(|| format!("No recently closed session named: {}", name))?;
+    let session = entries.remove(position);
+
+    let cwd = session.path.clone().unwrap_or_else(|| {
+        dirs::home_dir()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string()
+    });
+    if let Some(template) = template {
+        super::templates::create_session(template, &session.name, &cwd).await?;
+        save(&entries);
+        return Ok(session.name);
+    }
+    super::tmux::create_session(&session.name, &cwd).await?;
+
+    let mut windows = session.windows.iter();
+    if let Some(first) = windows.next() {
+        super::tmux::rename_window(&format!("{}:", session.name), first).await?;
+    }
+    for window in windows {
+        super::tmux::new_window(&session.name, window, &cwd).await?;
+    }
+
+    save(&entries);
+    Ok(session.name)
+}
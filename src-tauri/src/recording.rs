@@ -0,0 +1,252 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::pty_manager::{PtyChannel, PtyMessage};
+
+/// Recordings by `session_key` of the PTY they tee
+static RECORDINGS: once_cell::sync::Lazy<Mutex<HashMap<String, Recording>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct Recording {
+    path: String,
+    out: BufWriter<File>,
+    started: Instant,
+    /// Trailing bytes of an incomplete UTF-8 character, held for the next read
+    pending: Vec<u8>,
+    bytes: u64,
+    last_used: Instant,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingInfo {
+    pub path: String,
+    /// Seconds recorded so far
+    pub duration: f64,
+    /// Output bytes recorded so far
+    pub bytes: u64,
+}
+
+impl Recording {
+    fn info(&self) -> RecordingInfo {
+        RecordingInfo {
+            path: self.path.clone(),
+            duration: self.started.elapsed().as_secs_f64(),
+            bytes: self.bytes,
+        }
+    }
+
+    /// Append an event line: `[seconds, code, data]`
+    fn event(&mut self, code: &str, data: &str) -> std::io::Result<()> {
+        let line = serde_json::json!([self.started.elapsed().as_secs_f64(), code, data]);
+        writeln!(self.out, "{}", line)
+    }
+
+    fn output(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.last_used = Instant::now();
+        self.pending.extend_from_slice(data);
+        // Cast files hold text; hold back a character split across reads
+        let complete = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+        if complete == 0 {
+            return Ok(());
+        }
+        let chunk: Vec<u8> = self.pending.drain(..complete).collect();
+        self.bytes += chunk.len() as u64;
+        self.event("o", &String::from_utf8_lossy(&chunk))
+    }
+}
+
+/// Start teeing the PTY at `key` into an asciinema v2 cast file at `path`
+pub fn start(
+    key: &str,
+    target: &str,
+    path: &str,
+    cols: u16,
+    rows: u16,
+) -> Result<RecordingInfo, String> {
+    let mut recordings = RECORDINGS.lock().unwrap();
+    if let Some(existing) = recordings.get(key) {
+        return Err(format!(
+            "{} is already being recorded to {}",
+            target, existing.path
+        ));
+    }
+    let path = super::settings::expand_tilde(path);
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let file =
+        File::create(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut out = BufWriter::new(file);
+    let header = serde_json::json!({
+        "version": 2,
+        "width": cols,
+        "height": rows,
+        "timestamp": super::activity::now_unix(),
+        "title": target,
+        "env": { "TERM": "xterm-256color" },
+    });
+    writeln!(out, "{}", header)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    let recording = Recording {
+        path: path.to_string_lossy().to_string(),
+        out,
+        started: Instant::now(),
+        pending: Vec::new(),
+        bytes: 0,
+        last_used: Instant::now(),
+    };
+    let info = recording.info();
+    recordings.insert(key.to_string(), recording);
+    Ok(info)
+}
+
+/// Finish the recording of the PTY at `key`
+pub fn stop(key: &str) -> Result<RecordingInfo, String> {
+    let mut recording = RECORDINGS
+        .lock()
+        .unwrap()
+        .remove(key)
+        .ok_or_else(|| format!("Not recording: {}", key))?;
+    let pending = std::mem::take(&mut recording.pending);
+    if !pending.is_empty() {
+        let _ = recording.event("o", &String::from_utf8_lossy(&pending));
+    }
+    recording
+        .out
+        .flush()
+        .map_err(|e| format!("Failed to write {}: {}", recording.path, e))?;
+    Ok(recording.info())
+}
+
+/// Output read from the PTY at `key`
+pub fn observe(key: &str, data: &[u8]) {
+    let mut recordings = RECORDINGS.lock().unwrap();
+    let Some(recording) = recordings.get_mut(key) else {
+        return;
+    };
+    if let Err(e) = recording.output(data) {
+        log::warn!("[recording] Stopped {}: {}", recording.path, e);
+        recordings.remove(key);
+    }
+}
+
+/// Write buffers of recordings in progress, for `memory`
+pub(crate) fn buffers() -> Vec<super::memory::Buffer> {
+    RECORDINGS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(key, recording)| super::memory::Buffer {
+            key: key.clone(),
+            bytes: recording.out.capacity() + recording.pending.capacity(),
+            last_used: recording.last_used,
+        })
+        .collect()
+}
+
+/// The PTY at `key` was resized
+pub fn resized(key: &str, cols: u16, rows: u16) {
+    if let Some(recording) = RECORDINGS.lock().unwrap().get_mut(key) {
+        let _ = recording.event("r", &format!("{}x{}", cols, rows));
+    }
+}
+
+/// The PTY at `key` closed; finish its recording
+pub fn closed(key: &str) {
+    if RECORDINGS.lock().unwrap().contains_key(key) {
+        if let Err(e) = stop(key) {
+            log::warn!("[recording] {}", e);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackInfo {
+    pub width: u32,
+    pub height: u32,
+    /// Seconds at speed 1, after the cast's `idle_time_limit`
+    pub duration: f64,
+    pub events: usize,
+}
+
+/// Parse a cast file: its header and the timed output events, gaps capped
+/// at the header's `idle_time_limit`
+fn parse_cast(raw: &str) -> Result<(PlaybackInfo, Vec<(f64, String)>), String> {
+    let mut lines = raw.lines().filter(|l| !l.trim().is_empty());
+    let header: serde_json::Value = lines
+        .next()
+        .ok_or("Empty cast file")
+        .and_then(|l| serde_json::from_str(l).map_err(|_| "Invalid cast header"))?;
+    if header["version"].as_u64() != Some(2) {
+        return Err("Only asciinema v2 cast files are supported".to_string());
+    }
+    let idle_limit = header["idle_time_limit"].as_f64();
+
+    let mut events = Vec::new();
+    let (mut last, mut at) = (0.0, 0.0);
+    for (n, line) in lines.enumerate() {
+        let (time, code, data): (f64, String, String) = serde_json::from_str(line)
+            .map_err(|e| format!("Invalid event on line {}: {}", n + 2, e))?;
+        let gap = (time - last).max(0.0);
+        at += idle_limit.map_or(gap, |limit| gap.min(limit));
+        last = time;
+        if code == "o" {
+            events.push((at, data));
+        }
+    }
+    let info = PlaybackInfo {
+        width: header["width"].as_u64().unwrap_or(80) as u32,
+        height: header["height"].as_u64().unwrap_or(24) as u32,
+        duration: at,
+        events: events.len(),
+    };
+    Ok((info, events))
+}
+
+/// Stream a cast file's output to `channel` as `data` messages with its
+/// original timing divided by `speed`, then `exit`. Playback stops early if
+/// the channel closes.
+pub async fn play(
+    path: &str,
+    speed: f64,
+    channel: PtyChannel,
+) -> Result<PlaybackInfo, String> {
+    if !(speed > 0.0 && speed.is_finite()) {
+        return Err(format!("Invalid speed: {}", speed));
+    }
+    let path = super::settings::expand_tilde(path);
+    let raw = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let (info, events) = parse_cast(&raw)?;
+
+    tauri::async_runtime::spawn(async move {
+        let _task = super::diagnostics::track("cast-player");
+        let started = tokio::time::Instant::now();
+        for (at, data) in events {
+            tokio::time::sleep_until(started + Duration::from_secs_f64(at / speed)).await;
+            let data = data.into_bytes();
+            if channel.send(PtyMessage::Data { data }).is_err() {
+                return;
+            }
+        }
+        let _ = channel.send(PtyMessage::Exit {
+            code: Some(0),
+            signal: None,
+            reason: None,
+        });
+    });
+    Ok(info)
+}
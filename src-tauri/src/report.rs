@@ -0,0 +1,207 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::activity::{self, Activity};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    pub since: u64,
+    pub until: u64,
+    /// Session name → number of times it was opened
+    pub sessions: BTreeMap<String, u32>,
+    pub projects: Vec<ProjectTurns>,
+    pub commands: Vec<CommandSent>,
+    pub triggers: Vec<TriggerHit>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectTurns {
+    pub project: String,
+    pub turns: Vec<AgentTurn>,
+    pub total_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentTurn {
+    pub ended_at: u64,
+    pub duration_secs: Option<u64>,
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandSent {
+    pub ts: u64,
+    pub target: String,
+    /// Absent unless `activity.logInput` was on when it was sent
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggerHit {
+    pub ts: u64,
+    pub trigger_id: String,
+    pub target: String,
+    pub matched: String,
+}
+
+/// Parse a range: "day" (last 24h), "week" (last 7 days) or "<N>h"
+pub(crate) fn range_secs(range: &str) -> Result<u64, String> {
+    match range {
+        "day" => Ok(24 * 3600),
+        "week" => Ok(7 * 24 * 3600),
+        other => other
+            .strip_suffix('h')
+            .and_then(|n| n.parse::<u64>().ok())
+            .ok_or_else(|| format!("Unknown report range: {}", other))?
+            .checked_mul(3600)
+            .ok_or_else(|| format!("Report range too large: {}", other)),
+    }
+}
+
+pub fn build(range: &str) -> Result<Report, String> {
+    let until = activity::now_unix();
+    let since = until.saturating_sub(range_secs(range)?);
+
+    let mut sessions = BTreeMap::new();
+    let mut projects: BTreeMap<String, Vec<AgentTurn>> = BTreeMap::new();
+    let mut commands = Vec::new();
+    let mut triggers = Vec::new();
+
+    for entry in activity::read_range(since, until) {
+        match entry {
+            Activity::SessionOpened { session, .. } => {
+                *sessions.entry(session).or_insert(0) += 1;
+            }
+            Activity::CommandSent { ts, target, text } => {
+                commands.push(CommandSent { ts, target, text });
+            }
+            Activity::AgentTurn {
+                ts,
+                project,
+                duration_secs,
+                summary,
+                ..
+            } => {
+                projects.entry(project).or_default().push(AgentTurn {
+                    ended_at: ts,
+                    duration_secs,
+                    summary,
+                });
+            }
+            Activity::Trigger {
+                ts,
+                trigger_id,
+                target,
+                matched,
+            } => {
+                triggers.push(TriggerHit {
+                    ts,
+                    trigger_id,
+                    target,
+                    matched,
+                });
+            }
+            Activity::Focus { .. } => {}
+        }
+    }
+
+    let projects = projects
+        .into_iter()
+        .map(|(project, turns)| ProjectTurns {
+            total_secs: turns.iter().filter_map(|t| t.duration_secs).sum(),
+            project,
+            turns,
+        })
+        .collect();
+
+    Ok(Report {
+        since,
+        until,
+        sessions,
+        projects,
+        commands,
+        triggers,
+    })
+}
+
+fn format_duration(secs: u64) -> String {
+    if secs >= 3600 {
+        format!("{}h {}m", secs / 3600, (secs % 3600) / 60)
+    } else if secs >= 60 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+pub fn to_markdown(report: &Report) -> String {
+    let mut out = String::from("# MuxTunnel activity report\n\n");
+
+    out.push_str("## Sessions\n\n");
+    if report.sessions.is_empty() {
+        out.push_str("_No sessions opened._\n");
+    }
+    for (name, opens) in &report.sessions {
+        out.push_str(&format!("- **{}** — opened {}×\n", name, opens));
+    }
+
+    out.push_str("\n## Agent turns\n\n");
+    if report.projects.is_empty() {
+        out.push_str("_No agent turns completed._\n");
+    }
+    for project in &report.projects {
+        out.push_str(&format!(
+            "### {} ({} turns, {})\n\n",
+            project.project,
+            project.turns.len(),
+            format_duration(project.total_secs)
+        ));
+        for turn in &project.turns {
+            let duration = turn
+                .duration_secs
+                .map(format_duration)
+                .unwrap_or_else(|| "?".to_string());
+            out.push_str(&format!("- [{}] {}\n", duration, turn.summary));
+        }
+        out.push('\n');
+    }
+
+    out.push_str("\n## Commands sent\n\n");
+    if report.commands.is_empty() {
+        out.push_str("_No commands sent._\n");
+    }
+    for command in &report.commands {
+        match &command.text {
+            Some(text) => out.push_str(&format!("- `{}`: {}\n", command.target, text)),
+            None => out.push_str(&format!("- `{}`\n", command.target)),
+        }
+    }
+
+    if !report.triggers.is_empty() {
+        out.push_str("\n## Triggers\n\n");
+        for trigger in &report.triggers {
+            out.push_str(&format!(
+                "- {} on `{}`: {}\n",
+                trigger.trigger_id, trigger.target, trigger.matched
+            ));
+        }
+    }
+
+    out
+}
+
+/// Render a report as "markdown" or "json"
+pub fn generate(range: &str, format: &str) -> Result<String, String> {
+    let report = build(range)?;
+    match format {
+        "markdown" | "md" => Ok(to_markdown(&report)),
+        "json" => serde_json::to_string_pretty(&report)
+            .map_err(|e| format!("Failed to serialize report: {}", e)),
+        other => Err(format!("Unknown report format: {}", other)),
+    }
+}
@@ -2,11 +2,10 @@ use serde::Serialize;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-static RESOLVER_STATE: once_cell::sync::Lazy<Mutex<ResolverState>> =
-    once_cell::sync::Lazy::new(|| Mutex::new(ResolverState::default()));
+use super::settings::SettingsStore;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ProjectResult {
@@ -28,41 +27,14 @@ const DAY: u64 = 86400;
 const WEEK: u64 = 604800;
 const RESCAN_INTERVAL_MS: u64 = 5 * 60 * 1000;
 
-fn muxtunnel_dir() -> PathBuf {
-    dirs::home_dir()
-        .unwrap_or_default()
-        .join(".muxtunnel")
-}
-
-fn history_file() -> PathBuf {
-    muxtunnel_dir().join("history.json")
-}
-
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
-struct HistoryEntry {
+pub(crate) struct HistoryEntry {
     rank: f64,
     #[serde(rename = "lastAccessed")]
     last_accessed: u64,
 }
 
-type HistoryDB = HashMap<String, HistoryEntry>;
-
-fn load_history() -> HistoryDB {
-    match fs::read_to_string(history_file()) {
-        Ok(raw) => serde_json::from_str(&raw).unwrap_or_default(),
-        Err(_) => HashMap::new(),
-    }
-}
-
-fn save_history(db: &HistoryDB) {
-    let dir = muxtunnel_dir();
-    let _ = fs::create_dir_all(&dir);
-    if let Ok(json) = serde_json::to_string_pretty(db) {
-        if let Err(e) = fs::write(history_file(), json) {
-            log::error!("[resolver] Failed to save history: {}", e);
-        }
-    }
-}
+pub(crate) type HistoryDB = HashMap<String, HistoryEntry>;
 
 fn frecency_score(entry: &HistoryEntry, now: u64) -> f64 {
     let elapsed = now.saturating_sub(entry.last_accessed);
@@ -92,133 +64,59 @@ fn now_millis() -> u64 {
 }
 
 /// Discover projects by walking $HOME
-fn discover_projects() -> Vec<String> {
-    let settings = super::settings::get_settings();
+fn discover_projects(settings: &SettingsStore) -> Vec<String> {
+    let settings = settings.get();
     let ignore: std::collections::HashSet<String> =
         settings.settings.projects.ignore.into_iter().collect();
     let max_depth = settings.settings.projects.max_depth;
 
     let home = dirs::home_dir().unwrap_or_default();
     let mut projects = Vec::new();
-
-    fn walk(
-        dir: &Path,
-        depth: u32,
-        max_depth: u32,
-        ignore: &std::collections::HashSet<String>,
-        projects: &mut Vec<String>,
-    ) {
-        if depth > max_depth {
-            return;
-        }
-
-        let entries = match fs::read_dir(dir) {
-            Ok(e) => e,
-            Err(_) => return,
-        };
-
-        // Check if this dir has .git
-        if dir.join(".git").exists() {
-            projects.push(dir.to_string_lossy().to_string());
-            return; // Don't recurse into project subdirs
-        }
-
-        for entry in entries.flatten() {
-            let file_type = match entry.file_type() {
-                Ok(ft) => ft,
-                Err(_) => continue,
-            };
-            if !file_type.is_dir() {
-                continue;
-            }
-            let name = entry.file_name();
-            let name_str = name.to_string_lossy();
-            if name_str.starts_with('.') && name_str != ".config" {
-                continue;
-            }
-            if ignore.contains(name_str.as_ref()) {
-                continue;
-            }
-            walk(&entry.path(), depth + 1, max_depth, ignore, projects);
-        }
-    }
-
     walk(&home, 0, max_depth, &ignore, &mut projects);
     projects
 }
 
-fn get_discovered_projects(state: &mut ResolverState) -> &[String] {
-    let now = now_millis();
-    if state.discovered_projects.is_empty() || now - state.last_scan_time > RESCAN_INTERVAL_MS {
-        let start = std::time::Instant::now();
-        state.discovered_projects = discover_projects();
-        log::info!(
-            "[resolver] Discovered {} projects in {:?}",
-            state.discovered_projects.len(),
-            start.elapsed()
-        );
-        state.last_scan_time = now;
+/// Collect git repositories under `dir`, not descending into repos themselves
+fn walk(
+    dir: &Path,
+    depth: u32,
+    max_depth: u32,
+    ignore: &std::collections::HashSet<String>,
+    projects: &mut Vec<String>,
+) {
+    if depth > max_depth {
+        return;
     }
-    &state.discovered_projects
-}
 
-/// Resolve projects using the built-in resolver
-fn resolve_builtin(query: &str) -> Vec<ProjectResult> {
-    let mut state = RESOLVER_STATE.lock().unwrap();
-    let history = load_history();
-    let now = now_unix();
-    let lq = query.to_lowercase();
-
-    let mut seen = std::collections::HashSet::new();
-    let mut results = Vec::new();
-
-    // History entries
-    for (project_path, entry) in &history {
-        seen.insert(project_path.clone());
-        let name = Path::new(project_path)
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-        if !lq.is_empty()
-            && !name.to_lowercase().contains(&lq)
-            && !project_path.to_lowercase().contains(&lq)
-        {
-            continue;
-        }
-        results.push(ProjectResult {
-            name,
-            path: project_path.clone(),
-            score: frecency_score(entry, now),
-        });
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+
+    // Check if this dir has .git
+    if dir.join(".git").exists() {
+        projects.push(dir.to_string_lossy().to_string());
+        return; // Don't recurse into project subdirs
     }
 
-    // Discovered projects not in history
-    let discovered = get_discovered_projects(&mut state).to_vec();
-    for project_path in &discovered {
-        if seen.contains(project_path) {
+    for entry in entries.flatten() {
+        let file_type = match entry.file_type() {
+            Ok(ft) => ft,
+            Err(_) => continue,
+        };
+        if !file_type.is_dir() {
+            continue;
+        }
+        let name = entry.file_name();
+        let name_str = name.to_string_lossy();
+        if name_str.starts_with('.') && name_str != ".config" {
             continue;
         }
-        let name = Path::new(project_path)
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-        if !lq.is_empty()
-            && !name.to_lowercase().contains(&lq)
-            && !project_path.to_lowercase().contains(&lq)
-        {
+        if ignore.contains(name_str.as_ref()) {
             continue;
         }
-        results.push(ProjectResult {
-            name,
-            path: project_path.clone(),
-            score: 0.1,
-        });
+        walk(&entry.path(), depth + 1, max_depth, ignore, projects);
     }
-
-    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
-    results
 }
 
 /// Resolve projects using zoxide
@@ -286,74 +184,275 @@ async fn resolve_one_zoxide(name: &str) -> Option<ProjectResult> {
     })
 }
 
-pub fn record_selection(project_path: &str) {
-    let state = RESOLVER_STATE.lock().unwrap();
-    if state.active_resolver == "zoxide" {
-        return; // zoxide manages its own frecency
-    }
-    drop(state);
-
-    let mut history = load_history();
-    let now = now_unix();
-    let entry = history
-        .entry(project_path.to_string())
-        .or_insert(HistoryEntry {
-            rank: 0.0,
-            last_accessed: now,
-        });
-    entry.rank += 1.0;
-    entry.last_accessed = now;
-    save_history(&history);
+/// Resolve a path to the root of its enclosing git repository, or the path itself
+pub fn project_root(path: &str) -> PathBuf {
+    let path = Path::new(path);
+    path.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .unwrap_or(path)
+        .to_path_buf()
 }
 
-pub fn init(resolver_setting: &str) {
-    let mut state = RESOLVER_STATE.lock().unwrap();
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    /// Repositories found under the directory
+    pub found: usize,
+    /// Repositories newly added to history
+    pub imported: Vec<String>,
+    /// Repositories that were already in history
+    pub already_known: usize,
+    /// Templates written for imported repositories (see `projects_import_dir`)
+    pub templates: Vec<String>,
+}
 
-    // Check zoxide availability
-    state.zoxide_available = std::process::Command::new("zoxide")
-        .arg("--version")
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false);
+/// Project resolution and selection history, owned by `AppState`
+pub struct Resolver {
+    /// Holds `history.json`
+    dir: PathBuf,
+    settings: Arc<SettingsStore>,
+    state: Mutex<ResolverState>,
+}
 
-    if state.zoxide_available {
-        log::info!("[resolver] zoxide available");
-    } else {
-        log::info!("[resolver] zoxide not found");
+impl Resolver {
+    pub fn new(dir: PathBuf, settings: Arc<SettingsStore>) -> Self {
+        Self {
+            dir,
+            settings,
+            state: Mutex::new(ResolverState::default()),
+        }
     }
 
-    if resolver_setting == "zoxide" && state.zoxide_available {
-        state.active_resolver = "zoxide".to_string();
-    } else {
-        state.active_resolver = "muxtunnel.projects".to_string();
+    fn history_file(&self) -> PathBuf {
+        self.dir.join("history.json")
     }
 
-    log::info!("[resolver] Active: {}", state.active_resolver);
-}
+    fn load_history(&self) -> HistoryDB {
+        match fs::read_to_string(self.history_file()) {
+            Ok(raw) => serde_json::from_str(&raw).unwrap_or_default(),
+            Err(_) => HashMap::new(),
+        }
+    }
 
-pub async fn resolve(query: &str) -> Vec<ProjectResult> {
-    let resolver = {
-        let state = RESOLVER_STATE.lock().unwrap();
-        state.active_resolver.clone()
-    };
+    fn save_history(&self, db: &HistoryDB) {
+        let _ = fs::create_dir_all(&self.dir);
+        if let Ok(json) = serde_json::to_string_pretty(db) {
+            if let Err(e) = fs::write(self.history_file(), json) {
+                log::error!("[resolver] Failed to save history: {}", e);
+            }
+        }
+    }
 
-    match resolver.as_str() {
-        "zoxide" => resolve_zoxide(query).await,
-        _ => resolve_builtin(query),
+    fn discovered_projects<'a>(&self, state: &'a mut ResolverState) -> &'a [String] {
+        let now = now_millis();
+        if state.discovered_projects.is_empty() || now - state.last_scan_time > RESCAN_INTERVAL_MS {
+            let start = std::time::Instant::now();
+            state.discovered_projects = discover_projects(&self.settings);
+            log::info!(
+                "[resolver] Discovered {} projects in {:?}",
+                state.discovered_projects.len(),
+                start.elapsed()
+            );
+            state.last_scan_time = now;
+        }
+        &state.discovered_projects
     }
-}
 
-pub async fn resolve_one(name: &str) -> Option<ProjectResult> {
-    let resolver = {
-        let state = RESOLVER_STATE.lock().unwrap();
-        state.active_resolver.clone()
-    };
+    /// Resolve projects using the built-in resolver
+    fn resolve_builtin(&self, query: &str) -> Vec<ProjectResult> {
+        let mut state = self.state.lock().unwrap();
+        let history = self.load_history();
+        let now = now_unix();
+        let lq = query.to_lowercase();
+
+        let mut seen = std::collections::HashSet::new();
+        let mut results = Vec::new();
+
+        // History entries
+        for (project_path, entry) in &history {
+            seen.insert(project_path.clone());
+            let name = Path::new(project_path)
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            if !lq.is_empty()
+                && !name.to_lowercase().contains(&lq)
+                && !project_path.to_lowercase().contains(&lq)
+            {
+                continue;
+            }
+            results.push(ProjectResult {
+                name,
+                path: project_path.clone(),
+                score: frecency_score(entry, now),
+            });
+        }
 
-    match resolver.as_str() {
-        "zoxide" => resolve_one_zoxide(name).await,
-        _ => {
-            let results = resolve_builtin(name);
-            results.into_iter().next()
+        // Discovered projects not in history
+        let discovered = self.discovered_projects(&mut state).to_vec();
+        for project_path in &discovered {
+            if seen.contains(project_path) {
+                continue;
+            }
+            let name = Path::new(project_path)
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            if !lq.is_empty()
+                && !name.to_lowercase().contains(&lq)
+                && !project_path.to_lowercase().contains(&lq)
+            {
+                continue;
+            }
+            results.push(ProjectResult {
+                name,
+                path: project_path.clone(),
+                score: 0.1,
+            });
+        }
+
+        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        results
+    }
+
+    /// Register every repository under `dir` with a baseline score, so the project
+    /// picker is populated before the user has opened anything
+    pub async fn import_dir(&self, dir: &str) -> Result<ImportSummary, String> {
+        let root = super::settings::expand_tilde(dir);
+        if !root.is_dir() {
+            return Err(format!("Not a directory: {}", root.display()));
+        }
+
+        let settings = self.settings.get();
+        let ignore: std::collections::HashSet<String> =
+            settings.settings.projects.ignore.into_iter().collect();
+        let max_depth = settings.settings.projects.max_depth;
+
+        let mut found = Vec::new();
+        walk(&root, 0, max_depth, &ignore, &mut found);
+
+        let active_resolver = self.state.lock().unwrap().active_resolver.clone();
+        if active_resolver == "zoxide" {
+            for path in &found {
+                let _ = tokio::process::Command::new("zoxide")
+                    .args(["add", "--", path])
+                    .output()
+                    .await;
+            }
+            return Ok(ImportSummary {
+                found: found.len(),
+                imported: found,
+                already_known: 0,
+                templates: vec![],
+            });
+        }
+
+        // Baseline: old enough to rank below anything actually opened,
+        // but above projects that were only discovered by scanning
+        let mut history = self.load_history();
+        let baseline_accessed = now_unix().saturating_sub(WEEK);
+        let mut imported = Vec::new();
+        for path in &found {
+            if history.contains_key(path) {
+                continue;
+            }
+            history.insert(
+                path.clone(),
+                HistoryEntry {
+                    rank: 0.5,
+                    last_accessed: baseline_accessed,
+                },
+            );
+            imported.push(path.clone());
+        }
+        self.save_history(&history);
+
+        log::info!(
+            "[resolver] Imported {} of {} projects from {}",
+            imported.len(),
+            found.len(),
+            root.display()
+        );
+
+        Ok(ImportSummary {
+            found: found.len(),
+            already_known: found.len() - imported.len(),
+            imported,
+            templates: vec![],
+        })
+    }
+
+    pub fn record_selection(&self, project_path: &str) {
+        let state = self.state.lock().unwrap();
+        if state.active_resolver == "zoxide" {
+            return; // zoxide manages its own frecency
+        }
+        drop(state);
+
+        let mut history = self.load_history();
+        let now = now_unix();
+        let entry = history
+            .entry(project_path.to_string())
+            .or_insert(HistoryEntry {
+                rank: 0.0,
+                last_accessed: now,
+            });
+        entry.rank += 1.0;
+        entry.last_accessed = now;
+        self.save_history(&history);
+    }
+
+    pub fn init(&self, resolver_setting: &str) {
+        let mut state = self.state.lock().unwrap();
+
+        // Check zoxide availability
+        state.zoxide_available = super::capabilities::has(super::capabilities::Tool::Zoxide);
+
+        if state.zoxide_available {
+            log::info!("[resolver] zoxide available");
+        } else {
+            log::info!("[resolver] zoxide not found");
+        }
+
+        if super::features::enabled(super::features::Feature::DemoData) {
+            state.active_resolver = "muxtunnel.demo".to_string();
+        } else if resolver_setting == "zoxide" && state.zoxide_available {
+            state.active_resolver = "zoxide".to_string();
+        } else {
+            state.active_resolver = "muxtunnel.projects".to_string();
+        }
+
+        log::info!("[resolver] Active: {}", state.active_resolver);
+    }
+
+    pub async fn resolve(&self, query: &str) -> Vec<ProjectResult> {
+        let resolver = {
+            let state = self.state.lock().unwrap();
+            state.active_resolver.clone()
+        };
+
+        match resolver.as_str() {
+            "muxtunnel.demo" => super::demo::resolve(query),
+            "zoxide" => resolve_zoxide(query).await,
+            _ => self.resolve_builtin(query),
+        }
+    }
+
+    pub async fn resolve_one(&self, name: &str) -> Option<ProjectResult> {
+        let resolver = {
+            let state = self.state.lock().unwrap();
+            state.active_resolver.clone()
+        };
+
+        match resolver.as_str() {
+            "muxtunnel.demo" => super::demo::resolve(name).into_iter().next(),
+            "zoxide" => resolve_one_zoxide(name).await,
+            _ => {
+                let results = self.resolve_builtin(name);
+                results.into_iter().next()
+            }
         }
     }
 }
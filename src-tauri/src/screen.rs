@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// The screen of every PTY by `session_key`, kept by feeding its output
+/// through a VT parser
+static SCREENS: once_cell::sync::Lazy<Mutex<HashMap<String, Screen>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct Screen {
+    parser: vt100::Parser,
+    /// Pane-level key of the pane the PTY attached to (the PTY's own key for
+    /// direct PTYs)
+    pane_key: String,
+    /// Client of the PTY this screen belongs to, so a replaced PTY's reader
+    /// doesn't drop its successor's screen
+    client_pid: Option<u32>,
+    /// When output was last applied
+    last_used: Instant,
+}
+
+/// A rectangle of the screen, in cells
+#[derive(Debug, Clone, Copy)]
+pub struct Region {
+    pub left: u16,
+    pub top: u16,
+    pub cols: u16,
+    pub rows: u16,
+}
+
+impl Region {
+    /// The last `lines` rows of a tmux pane, as a client showing its window
+    /// draws it
+    pub fn pane_tail(pane: &super::tmux::TmuxPane, lines: u32) -> Region {
+        let rows = pane.rows.min(lines);
+        Region {
+            left: pane.left as u16,
+            top: (pane.top + pane.rows - rows) as u16,
+            cols: pane.cols as u16,
+            rows: rows as u16,
+        }
+    }
+}
+
+/// Start tracking the screen of a PTY that just opened
+pub fn opened(key: &str, pane_key: &str, client_pid: Option<u32>, cols: u16, rows: u16) {
+    SCREENS.lock().unwrap().insert(
+        key.to_string(),
+        Screen {
+            parser: vt100::Parser::new(rows, cols, 0),
+            pane_key: pane_key.to_string(),
+            client_pid,
+            last_used: Instant::now(),
+        },
+    );
+}
+
+/// Apply output read from the PTY at `key`
+pub fn observe(key: &str, data: &[u8]) {
+    if let Some(screen) = SCREENS.lock().unwrap().get_mut(key) {
+        screen.parser.process(data);
+        screen.last_used = Instant::now();
+    }
+}
+
+pub fn resized(key: &str, cols: u16, rows: u16) {
+    if let Some(screen) = SCREENS.lock().unwrap().get_mut(key) {
+        screen.parser.set_size(rows, cols);
+    }
+}
+
+/// The PTY at `key` whose client was `client_pid` ended
+pub fn closed(key: &str, client_pid: Option<u32>) {
+    let mut screens = SCREENS.lock().unwrap();
+    if screens.get(key).is_some_and(|s| s.client_pid == client_pid) {
+        screens.remove(key);
+    }
+}
+
+/// Cells of every screen's main and alternate grids, for `memory`
+pub(crate) fn buffers() -> Vec<super::memory::Buffer> {
+    SCREENS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(key, screen)| {
+            let (rows, cols) = screen.parser.screen().size();
+            let cells = 2 * rows as usize * cols as usize;
+            super::memory::Buffer {
+                key: key.clone(),
+                bytes: cells * std::mem::size_of::<vt100::Cell>(),
+                last_used: screen.last_used,
+            }
+        })
+        .collect()
+}
+
+/// Key of a PTY attached to the pane at `pane_key`
+pub fn for_pane(pane_key: &str) -> Option<String> {
+    SCREENS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(_, screen)| screen.pane_key == pane_key)
+        .map(|(key, _)| key.clone())
+}
+
+/// Rows of `region` of the screen at `key` as text, trailing blanks trimmed.
+/// `None` without a screen.
+pub fn lines(key: &str, region: Region) -> Option<Vec<String>> {
+    let screens = SCREENS.lock().unwrap();
+    let screen = screens.get(key)?.parser.screen();
+    Some(
+        screen
+            .rows(region.left, region.cols)
+            .skip(region.top as usize)
+            .take(region.rows as usize)
+            .map(|row| row.trim_end().to_string())
+            .collect(),
+    )
+}
+
+/// Whether the program behind the PTY at `key` turned on bracketed paste.
+/// `None` without a screen.
+pub fn bracketed_paste(key: &str) -> Option<bool> {
+    let screens = SCREENS.lock().unwrap();
+    Some(screens.get(key)?.parser.screen().bracketed_paste())
+}
+
+/// Whether `region` of the screen at `key` shows Claude Code's thinking
+/// status: orange text along with an ellipsis. `None` without a screen.
+pub fn is_thinking(key: &str, region: Region) -> Option<bool> {
+    let screens = SCREENS.lock().unwrap();
+    let screen = screens.get(key)?.parser.screen();
+    let mut orange = false;
+    let mut ellipsis = false;
+    for row in region.top..region.top.saturating_add(region.rows) {
+        for col in region.left..region.left.saturating_add(region.cols) {
+            let Some(cell) = screen.cell(row, col) else {
+                continue;
+            };
+            // Orange/salmon range used by the thinking status
+            if let vt100::Color::Rgb(200..=239, 100..=159, 80..=129) = cell.fgcolor() {
+                orange = true;
+            }
+            if cell.contents() == "\u{2026}" {
+                ellipsis = true;
+            }
+        }
+    }
+    Some(orange && ellipsis)
+}
@@ -0,0 +1,110 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Lines of context captured on each side of a match
+const CONTEXT_LINES: usize = 2;
+
+/// Stop collecting once this many matches were found across all panes;
+/// panes reached after that aren't captured
+const MAX_MATCHES: usize = 1000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
+    pub target: String,
+    /// tmux line number: negative values are history, 0 is the top of the screen
+    pub line: i64,
+    pub text: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+fn build_regex(query: &str, regex: bool, case_sensitive: bool) -> Result<regex::Regex, String> {
+    let pattern = if regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    regex::RegexBuilder::new(&pattern)
+        .case_insensitive(!case_sensitive)
+        .build()
+        .map_err(|e| format!("Invalid search pattern: {}", e))
+}
+
+/// Matches in one pane's scrollback, at most `remaining` of them, which is
+/// reduced by what was found
+async fn search_pane(
+    target: String,
+    re: &regex::Regex,
+    remaining: &AtomicUsize,
+) -> Vec<SearchMatch> {
+    let budget = remaining.load(Ordering::Relaxed);
+    if budget == 0 {
+        return vec![];
+    }
+    let capture = match super::tmux::capture_history(&target).await {
+        Ok(c) => c,
+        Err(_) => return vec![],
+    };
+    let lines: Vec<&str> = capture.content.lines().collect();
+    let history_size = capture.history_size as i64;
+
+    let matches: Vec<SearchMatch> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| re.is_match(line))
+        .map(|(i, line)| SearchMatch {
+            target: target.clone(),
+            line: i as i64 - history_size,
+            text: line.to_string(),
+            before: lines[i.saturating_sub(CONTEXT_LINES)..i]
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+            after: lines[i + 1..(i + 1 + CONTEXT_LINES).min(lines.len())]
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+        })
+        .take(budget)
+        .collect();
+    let _ = remaining.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+        Some(left.saturating_sub(matches.len()))
+    });
+    matches
+}
+
+/// Grep the scrollback of the given panes, or of every pane when `targets` is empty
+pub async fn search_panes(
+    query: &str,
+    targets: Vec<String>,
+    regex: bool,
+    case_sensitive: bool,
+) -> Result<Vec<SearchMatch>, String> {
+    let re = build_regex(query, regex, case_sensitive)?;
+
+    let targets = if targets.is_empty() {
+        super::tmux::list_sessions()
+            .await
+            .into_iter()
+            .flat_map(|s| s.windows)
+            .flat_map(|w| w.panes)
+            .map(|p| p.target)
+            .collect()
+    } else {
+        targets
+    };
+
+    let remaining = AtomicUsize::new(MAX_MATCHES);
+    let results = futures::future::join_all(
+        targets
+            .into_iter()
+            .map(|t| super::probes::limited(search_pane(t, &re, &remaining))),
+    )
+    .await;
+
+    let mut matches: Vec<SearchMatch> = results.into_iter().flatten().collect();
+    // Panes scanned at the same time can each take what was left
+    matches.truncate(MAX_MATCHES);
+    Ok(matches)
+}
@@ -1,40 +1,106 @@
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::Mutex;
 
-static ORDER: once_cell::sync::Lazy<Mutex<Vec<String>>> =
-    once_cell::sync::Lazy::new(|| Mutex::new(vec![]));
-
-fn order_file() -> PathBuf {
-    dirs::home_dir()
-        .unwrap_or_default()
-        .join(".muxtunnel")
-        .join("session-order.json")
+/// Session and window order as arranged in the UI, owned by `AppState`
+pub struct SessionOrder {
+    dir: PathBuf,
+    order: Mutex<Vec<String>>,
+    /// Window names per session, in UI order
+    window_order: Mutex<HashMap<String, Vec<String>>>,
 }
 
-pub fn load() {
-    let path = order_file();
-    let order = match fs::read_to_string(&path) {
-        Ok(raw) => match serde_json::from_str::<Vec<String>>(&raw) {
-            Ok(v) => v,
+impl SessionOrder {
+    /// Load `session-order.json` and `window-order.json` from `dir`
+    /// No saved order; for safe mode, which doesn't read state files
+    pub fn empty(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            order: Mutex::new(vec![]),
+            window_order: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn load(dir: PathBuf) -> Self {
+        let order = match fs::read_to_string(dir.join("session-order.json")) {
+            Ok(raw) => serde_json::from_str::<Vec<String>>(&raw).unwrap_or_default(),
             Err(_) => vec![],
-        },
-        Err(_) => vec![],
-    };
-    *ORDER.lock().unwrap() = order;
-}
+        };
+        let window_order = match fs::read_to_string(dir.join("window-order.json")) {
+            Ok(raw) => serde_json::from_str(&raw).unwrap_or_default(),
+            Err(_) => HashMap::new(),
+        };
+        Self {
+            dir,
+            order: Mutex::new(order),
+            window_order: Mutex::new(window_order),
+        }
+    }
 
-pub fn get() -> Vec<String> {
-    ORDER.lock().unwrap().clone()
-}
+    pub fn get(&self) -> Vec<String> {
+        self.order.lock().unwrap().clone()
+    }
 
-pub fn save(order: Vec<String>) {
-    *ORDER.lock().unwrap() = order.clone();
-    let path = order_file();
-    if let Some(parent) = path.parent() {
-        let _ = fs::create_dir_all(parent);
+    pub fn save(&self, order: Vec<String>) {
+        *self.order.lock().unwrap() = order.clone();
+        let _ = fs::create_dir_all(&self.dir);
+        if let Err(e) = fs::write(
+            self.dir.join("session-order.json"),
+            serde_json::to_string_pretty(&order).unwrap_or_default(),
+        ) {
+            log::error!("[session-order] Failed to save: {}", e);
+        }
     }
-    if let Err(e) = fs::write(&path, serde_json::to_string_pretty(&order).unwrap_or_default()) {
-        log::error!("[session-order] Failed to save: {}", e);
+
+    pub fn save_windows(&self, session: &str, names: Vec<String>) {
+        let snapshot = {
+            let mut order = self.window_order.lock().unwrap();
+            order.insert(session.to_string(), names);
+            order.clone()
+        };
+        let _ = fs::create_dir_all(&self.dir);
+        if let Err(e) = fs::write(
+            self.dir.join("window-order.json"),
+            serde_json::to_string_pretty(&snapshot).unwrap_or_default(),
+        ) {
+            log::error!("[session-order] Failed to save window order: {}", e);
+        }
+    }
+
+    /// Reorder tmux windows so every session matches its saved window order.
+    /// Windows missing from the saved order keep their place after the known ones.
+    pub async fn apply_window_order(&self) {
+        let saved = self.window_order.lock().unwrap().clone();
+        for (session, names) in saved {
+            let mut position = 0;
+            for name in &names {
+                let windows = match super::tmux::list_windows(&session).await {
+                    Ok(w) => w,
+                    Err(_) => break, // session no longer exists
+                };
+                let current = windows
+                    .iter()
+                    .skip(position)
+                    .position(|(_, n)| n == name)
+                    .map(|p| p + position);
+                let Some(current) = current else {
+                    continue;
+                };
+                if current != position {
+                    if let Err(e) = super::tmux::move_window(
+                        &session,
+                        windows[current].0,
+                        windows[position].0,
+                    )
+                    .await
+                    {
+                        log::warn!("[session-order] Failed to reorder {}: {}", session, e);
+                        break;
+                    }
+                }
+                position += 1;
+            }
+        }
     }
 }
@@ -1,15 +1,15 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
-static SETTINGS: once_cell::sync::Lazy<Mutex<SettingsState>> =
-    once_cell::sync::Lazy::new(|| {
-        Mutex::new(SettingsState {
-            settings: default_settings(),
-            version: 0,
-        })
-    });
+/// The process-wide store, for everything but the `AppState` services.
+///
+/// Only the resolver, Claude tracker and session order get their store
+/// injected. The ~40 other readers (tmux binary and sockets, PTYs, link
+/// patterns, notifications, ...) go through `get_settings`, so one process
+/// still has exactly one settings root; see `install`.
+static ACTIVE: once_cell::sync::OnceCell<Arc<SettingsStore>> = once_cell::sync::OnceCell::new();
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -19,6 +19,22 @@ pub struct MuxTunnelSettings {
     pub background: BackgroundSettings,
     pub terminal: TerminalSettings,
     pub window: WindowSettings,
+    pub groups: GroupsSettings,
+    pub tmux: TmuxSettings,
+    pub multiplexer: MultiplexerSettings,
+    pub notifications: NotificationSettings,
+    pub links: LinkSettings,
+    pub paths: PathSettings,
+    pub clipboard: ClipboardSettings,
+    pub previews: PreviewSettings,
+    pub hooks: HookSettings,
+    pub memory: MemorySettings,
+    pub diagnostics: DiagnosticsSettings,
+    pub logs: LogSettings,
+    pub activity: ActivitySettings,
+    pub advanced: AdvancedSettings,
+    /// Experimental subsystem → enabled; read once at startup (see `features`)
+    pub features: std::collections::BTreeMap<String, bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,11 +60,253 @@ pub struct TerminalSettings {
     pub font_size: u32,
     #[serde(rename = "fontFamily")]
     pub font_family: String,
+    /// Forward focus in/out to attached panes (and turn on tmux's
+    /// `focus-events`) so editors can autoread and autosave
+    #[serde(rename = "focusEvents")]
+    pub focus_events: bool,
+    /// Terminal app `focus_external_client` brings forward (e.g. "iTerm",
+    /// or a window class for wmctrl on Linux)
+    #[serde(rename = "externalApp")]
+    pub external_app: Option<String>,
+    /// Output kept per PTY and replayed when the same target is connected
+    /// again, so switching panes keeps the screen; 0 reattaches instead
+    #[serde(rename = "scrollbackBytes")]
+    pub scrollback_bytes: usize,
+    /// How long a `pty_detach`ed PTY waits for a reconnect before it is closed
+    #[serde(rename = "detachedTimeoutSecs")]
+    pub detached_timeout_secs: u64,
+    /// How PTY output is sent to terminal views: "json" (arrays of
+    /// numbers), "base64" or "raw" (`ArrayBuffer`s); applies to new PTYs
+    #[serde(rename = "dataTransport")]
+    pub data_transport: String,
+    /// Output at least this large is LZ4-compressed for views that connect
+    /// with `compression: "lz4"`
+    #[serde(rename = "compressionThreshold")]
+    pub compression_threshold: usize,
+    /// Most `tmux attach` PTYs kept at once; past it the least recently
+    /// connected one is closed, keeping its scrollback for the next connect.
+    /// 0 for no limit.
+    #[serde(rename = "maxPtys")]
+    pub max_ptys: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct WindowSettings {
     pub padding: u32,
+    /// Native window title, e.g. `"{session}{attention}"`; see `window_title`.
+    /// Empty leaves the title alone.
+    pub title_format: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupsSettings {
+    /// Path prefixes (e.g. "~/work") whose sessions are grouped together
+    pub prefixes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TmuxSettings {
+    /// Path to the tmux binary; discovered automatically when unset
+    pub binary: Option<String>,
+    /// Server socket name, passed as `tmux -L`
+    pub socket_name: Option<String>,
+    /// Server socket path, passed as `tmux -S` (takes precedence over `socket_name`)
+    pub socket_path: Option<String>,
+    /// Additional tmux servers whose sessions are listed alongside the default one
+    pub servers: Vec<TmuxServer>,
+    /// Reorder tmux windows on startup to match the saved UI order
+    pub apply_window_order: bool,
+    /// Options set with `set-option -t` on sessions MuxTunnel creates,
+    /// e.g. `{"status": "off", "mouse": true, "history-limit": 50000}`
+    pub session_options: std::collections::BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiplexerSettings {
+    /// `"tmux"` or `"zellij"`; see `multiplexer`
+    pub backend: String,
+    /// Path to the zellij binary; looked up on `$PATH` when unset
+    pub zellij_binary: Option<String>,
+    /// List WezTerm workspaces next to the tmux sessions (server `"wezterm"`)
+    pub wezterm: bool,
+    /// Path to the wezterm binary; looked up on `$PATH` when unset
+    pub wezterm_binary: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardSettings {
+    /// Larger copies and pastes are rejected
+    pub max_bytes: usize,
+    /// Drop terminal escape sequences from copied text
+    pub strip_ansi: bool,
+    /// Replace likely secrets (tokens, keys, passwords) with `[redacted]` when copying
+    pub redact_secrets: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewSettings {
+    /// Session previews kept in memory (least recently used are dropped)
+    pub cache_size: usize,
+    /// Sessions on each side of the viewed one to pre-capture; 0 disables warming
+    pub warm_count: usize,
+    /// Default zoom (percent) when the frontend hasn't asked for one
+    pub zoom: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemorySettings {
+    /// Cap on PTY backlogs and cached previews combined; the least recently
+    /// used are evicted first. 0 disables the cap.
+    pub max_buffer_bytes: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsSettings {
+    /// Attach the last `outputLines` lines of a pane to errors involving it
+    pub capture_output: bool,
+    pub output_lines: usize,
+}
+
+/// Pane logs started with `panes_log_start`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogSettings {
+    /// Default directory for logs; unset uses `logs` in the data dir
+    pub dir: Option<String>,
+    /// Rotate a log when it reaches this size; 0 never rotates
+    pub max_bytes: u64,
+    /// Rotated files kept per log (`.1` is the newest)
+    pub keep: usize,
+}
+
+/// The activity log behind `report_build` and focus time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivitySettings {
+    /// Record the text of commands sent to panes, not just that one was sent
+    pub log_input: bool,
+    /// Start a new activity.jsonl when it reaches this size, keeping one
+    /// previous file; 0 never rotates
+    pub max_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdvancedSettings {
+    /// Per-pane subprocesses (Claude status, captures, searches) running at
+    /// once across all enrichment; 0 is unlimited
+    pub max_concurrent_probes: usize,
+    /// Polling of pane activity while a server's panes are on screen, while
+    /// none are, and while the app is in the background (see `visibility`)
+    pub visible_poll_ms: u64,
+    pub hidden_poll_ms: u64,
+    pub unfocused_poll_ms: u64,
+}
+
+/// Shell commands run around opening a session or restoring a workspace snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookSettings {
+    /// Session name → hooks run when MuxTunnel first attaches to it
+    pub sessions: std::collections::BTreeMap<String, HookSet>,
+    /// Snapshot name → hooks run around `workspace_restore`
+    pub workspaces: std::collections::BTreeMap<String, HookSet>,
+    /// Hooks still running after this long are killed and reported as failed
+    pub timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HookSet {
+    /// Awaited before the snapshot is restored, or for up to a few seconds
+    /// before the session is attached
+    #[serde(default)]
+    pub pre: Vec<String>,
+    /// Started in the background afterwards
+    #[serde(default)]
+    pub post: Vec<String>,
+}
+
+/// Overridden by `MUXTUNNEL_DATA_DIR` / `CLAUDE_CONFIG_DIR`; see `paths`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathSettings {
+    /// Where history, layouts, snapshots and other state are kept
+    pub data_dir: Option<String>,
+    /// Claude's config root (holding `projects/`)
+    pub claude_dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkSettings {
+    /// Extra patterns applied to every pane
+    pub patterns: Vec<LinkPattern>,
+    /// Project path (e.g. "~/src/app") → patterns for panes whose cwd is inside it
+    pub projects: std::collections::HashMap<String, Vec<LinkPattern>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkPattern {
+    /// "url", "path", "issue" or any custom label
+    pub kind: String,
+    pub regex: String,
+    /// URL template with `$1`-style capture references, e.g. for issue numbers
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationSettings {
+    /// Deliver desktop notifications at all (in-app events are always emitted)
+    pub enabled: bool,
+    /// Local time window ("HH:MM") during which desktop notifications are held back
+    pub quiet_hours: Option<QuietHours>,
+    /// Identical notifications within this many seconds are dropped
+    pub dedup_secs: u64,
+    pub sounds: SoundSettings,
+}
+
+/// Sounds played on attention events ("turn-finished", "trigger-matched"),
+/// held back in quiet hours like desktop notifications
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoundSettings {
+    pub enabled: bool,
+    /// 0.0–1.0, for sounds that don't set their own
+    pub volume: f64,
+    /// Event → sound
+    pub events: std::collections::BTreeMap<String, Sound>,
+    /// Project path → event → sound, for sessions under that path
+    pub projects: std::collections::BTreeMap<String, std::collections::BTreeMap<String, Sound>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sound {
+    pub file: String,
+    pub volume: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TmuxServer {
+    pub name: String,
+    #[serde(default)]
+    pub socket_name: Option<String>,
+    #[serde(default)]
+    pub socket_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,14 +320,24 @@ struct SettingsState {
     version: u32,
 }
 
-fn settings_dir() -> PathBuf {
-    dirs::home_dir()
-        .unwrap_or_default()
-        .join(".muxtunnel")
-}
-
-fn settings_file() -> PathBuf {
-    settings_dir().join("settings.json")
+fn default_sounds() -> std::collections::BTreeMap<String, Sound> {
+    let file = if cfg!(target_os = "macos") {
+        "/System/Library/Sounds/Glass.aiff"
+    } else {
+        "/usr/share/sounds/freedesktop/stereo/complete.oga"
+    };
+    ["turn-finished", "trigger-matched"]
+        .into_iter()
+        .map(|event| {
+            (
+                event.to_string(),
+                Sound {
+                    file: file.to_string(),
+                    volume: None,
+                },
+            )
+        })
+        .collect()
 }
 
 fn default_settings() -> MuxTunnelSettings {
@@ -98,8 +366,90 @@ fn default_settings() -> MuxTunnelSettings {
         terminal: TerminalSettings {
             font_size: 14,
             font_family: "monospace".to_string(),
+            focus_events: true,
+            external_app: None,
+            scrollback_bytes: 256 * 1024,
+            detached_timeout_secs: 600,
+            data_transport: "json".to_string(),
+            compression_threshold: 8 * 1024,
+            max_ptys: 32,
+        },
+        window: WindowSettings {
+            padding: 0,
+            title_format: "{session}{attention}".to_string(),
+        },
+        groups: GroupsSettings { prefixes: vec![] },
+        tmux: TmuxSettings {
+            binary: None,
+            socket_name: None,
+            socket_path: None,
+            servers: vec![],
+            apply_window_order: false,
+            session_options: std::collections::BTreeMap::new(),
+        },
+        multiplexer: MultiplexerSettings {
+            backend: "tmux".to_string(),
+            zellij_binary: None,
+            wezterm: false,
+            wezterm_binary: None,
+        },
+        notifications: NotificationSettings {
+            enabled: true,
+            quiet_hours: None,
+            dedup_secs: 30,
+            sounds: SoundSettings {
+                enabled: false,
+                volume: 0.8,
+                events: default_sounds(),
+                projects: std::collections::BTreeMap::new(),
+            },
+        },
+        links: LinkSettings {
+            patterns: vec![],
+            projects: std::collections::HashMap::new(),
+        },
+        paths: PathSettings {
+            data_dir: None,
+            claude_dir: None,
+        },
+        clipboard: ClipboardSettings {
+            max_bytes: 1024 * 1024,
+            strip_ansi: true,
+            redact_secrets: false,
+        },
+        previews: PreviewSettings {
+            cache_size: 32,
+            warm_count: 2,
+            zoom: 100,
+        },
+        hooks: HookSettings {
+            sessions: std::collections::BTreeMap::new(),
+            workspaces: std::collections::BTreeMap::new(),
+            timeout_secs: 60,
+        },
+        memory: MemorySettings {
+            max_buffer_bytes: 64 * 1024 * 1024,
         },
-        window: WindowSettings { padding: 0 },
+        diagnostics: DiagnosticsSettings {
+            capture_output: false,
+            output_lines: 50,
+        },
+        logs: LogSettings {
+            dir: None,
+            max_bytes: 10 * 1024 * 1024,
+            keep: 5,
+        },
+        activity: ActivitySettings {
+            log_input: false,
+            max_bytes: 8 * 1024 * 1024,
+        },
+        advanced: AdvancedSettings {
+            max_concurrent_probes: 16,
+            visible_poll_ms: 1000,
+            hidden_poll_ms: 5000,
+            unfocused_poll_ms: 60_000,
+        },
+        features: super::features::defaults(),
     }
 }
 
@@ -150,101 +500,174 @@ fn expand_dot_keys(obj: &serde_json::Map<String, serde_json::Value>) -> serde_js
     serde_json::Value::Object(result)
 }
 
-fn load_settings_inner() -> MuxTunnelSettings {
-    let defaults = default_settings();
-    let defaults_json = serde_json::to_value(&defaults).unwrap();
-
-    let user_json = match fs::read_to_string(settings_file()) {
-        Ok(raw) => match serde_json::from_str::<serde_json::Value>(&raw) {
-            Ok(serde_json::Value::Object(obj)) => expand_dot_keys(&obj),
-            _ => return defaults,
-        },
-        Err(_) => return defaults,
+/// The settings in a settings.json's contents, merged over the defaults
+fn parse_settings(raw: &str) -> Result<MuxTunnelSettings, String> {
+    let user_json = match serde_json::from_str::<serde_json::Value>(raw) {
+        Ok(serde_json::Value::Object(obj)) => expand_dot_keys(&obj),
+        Ok(_) => return Err("Expected an object".to_string()),
+        Err(e) => return Err(e.to_string()),
     };
-
+    let defaults_json = serde_json::to_value(default_settings()).unwrap();
     let merged = merge_settings(&defaults_json, &user_json);
-    let mut settings: MuxTunnelSettings =
-        serde_json::from_value(merged).unwrap_or(defaults);
+    serde_json::from_value(merged).map_err(|e| e.to_string())
+}
+
+/// Whether settings.json's contents load, rather than falling back to defaults
+pub(crate) fn validate(raw: &str) -> Result<(), String> {
+    parse_settings(raw).map(|_| ())
+}
+
+fn load_settings_inner(path: &Path) -> MuxTunnelSettings {
+    let mut settings = fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|raw| parse_settings(&raw))
+        .unwrap_or_else(|_| default_settings());
 
     // Clamp values
     settings.background.opacity = settings.background.opacity.clamp(0.0, 1.0);
-    settings.window.padding = settings.window.padding.max(0);
 
     settings
 }
 
-pub fn load_settings() {
-    let settings = load_settings_inner();
-    let mut state = SETTINGS.lock().unwrap();
-    state.settings = settings;
-    state.version += 1;
+/// Settings loaded from `<dir>/settings.json`, owned by `AppState`
+pub struct SettingsStore {
+    dir: PathBuf,
+    state: Mutex<SettingsState>,
 }
 
-pub fn get_settings() -> SettingsResponse {
-    let state = SETTINGS.lock().unwrap();
-    SettingsResponse {
-        settings: state.settings.clone(),
-        version: state.version,
+impl SettingsStore {
+    /// A store rooted at `dir`, holding defaults until `load` is called
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            state: Mutex::new(SettingsState {
+                settings: default_settings(),
+                version: 0,
+            }),
+        }
     }
-}
 
-pub fn get_background_image_path() -> Option<PathBuf> {
-    let state = SETTINGS.lock().unwrap();
-    let image = state.settings.background.image.as_deref()?;
+    fn file(&self) -> PathBuf {
+        self.dir.join("settings.json")
+    }
 
-    if image.starts_with("http://") || image.starts_with("https://") {
-        return None;
+    pub fn load(&self) {
+        let settings = load_settings_inner(&self.file());
+        let version = {
+            let mut state = self.state.lock().unwrap();
+            state.settings = settings;
+            state.version += 1;
+            state.version
+        };
+        super::events::emit(super::events::BackendEvent::SettingsChanged {
+            settings_version: version,
+        });
+        super::window_title::refresh();
     }
 
-    let resolved = if image.starts_with('~') {
-        dirs::home_dir()
-            .unwrap_or_default()
-            .join(&image[1..].trim_start_matches('/'))
-    } else {
-        PathBuf::from(image)
-    };
+    pub fn get(&self) -> SettingsResponse {
+        let state = self.state.lock().unwrap();
+        SettingsResponse {
+            settings: state.settings.clone(),
+            version: state.version,
+        }
+    }
 
-    if resolved.is_file() {
-        Some(resolved)
-    } else {
-        None
+    /// Bumped on every reload, for caches of values derived from the settings
+    pub fn version(&self) -> u32 {
+        self.state.lock().unwrap().version
     }
-}
 
-pub fn start_watching() {
-    let dir = settings_dir();
-    let _ = fs::create_dir_all(&dir);
+    pub fn background_image_path(&self) -> Option<PathBuf> {
+        let state = self.state.lock().unwrap();
+        let image = state.settings.background.image.as_deref()?;
 
-    // Initial load
-    load_settings();
+        if image.starts_with("http://") || image.starts_with("https://") {
+            return None;
+        }
+
+        let resolved = expand_tilde(image);
 
-    // Watch for changes using a simple polling approach in a background thread
-    // (notify crate is used for Claude sessions; here we use a lighter approach)
-    std::thread::spawn(move || {
-        use std::time::{Duration, Instant};
-        let mut last_modified = fs::metadata(settings_file())
-            .and_then(|m| m.modified())
-            .ok();
-        let mut last_check = Instant::now();
+        if resolved.is_file() {
+            Some(resolved)
+        } else {
+            None
+        }
+    }
 
-        loop {
-            std::thread::sleep(Duration::from_millis(500));
+    pub fn start_watching(self: &Arc<Self>) {
+        let _ = fs::create_dir_all(&self.dir);
 
-            // Only check every 500ms
-            if last_check.elapsed() < Duration::from_millis(500) {
-                continue;
-            }
-            last_check = Instant::now();
+        // Initial load
+        self.load();
 
-            let current_modified = fs::metadata(settings_file())
+        // Watch for changes using a simple polling approach in a background thread
+        // (notify crate is used for Claude sessions; here we use a lighter approach)
+        let store = self.clone();
+        std::thread::spawn(move || {
+            use std::time::{Duration, Instant};
+            let _task = super::diagnostics::track("settings-watcher");
+            let mut last_modified = fs::metadata(store.file())
                 .and_then(|m| m.modified())
                 .ok();
+            let mut last_check = Instant::now();
+
+            loop {
+                std::thread::sleep(Duration::from_millis(500));
+
+                // Only check every 500ms
+                if last_check.elapsed() < Duration::from_millis(500) {
+                    continue;
+                }
+                last_check = Instant::now();
 
-            if current_modified != last_modified {
-                last_modified = current_modified;
-                log::info!("[settings] Reloading settings.json");
-                load_settings();
+                let current_modified = fs::metadata(store.file())
+                    .and_then(|m| m.modified())
+                    .ok();
+
+                if current_modified != last_modified {
+                    last_modified = current_modified;
+                    log::info!("[settings] Reloading settings.json");
+                    store.load();
+                }
             }
-        }
-    });
+        });
+    }
+}
+
+/// Make `store` the one `get_settings` reads. Called once from `run()` with
+/// the store `AppState` holds; later calls are ignored.
+pub fn install(store: Arc<SettingsStore>) {
+    if ACTIVE.set(store).is_err() {
+        log::warn!("[settings] A settings store is already installed");
+    }
+}
+
+/// Settings of the installed store, or the defaults before one is installed.
+/// Code that has an `AppState` (or a service holding the store) should read
+/// that instead.
+pub fn get_settings() -> SettingsResponse {
+    match ACTIVE.get() {
+        Some(store) => store.get(),
+        None => SettingsResponse {
+            settings: default_settings(),
+            version: 0,
+        },
+    }
+}
+
+/// Version of the installed store's settings (0 before one is installed)
+pub fn settings_version() -> u32 {
+    ACTIVE.get().map(|store| store.version()).unwrap_or(0)
+}
+
+/// Expand a leading `~` to the user's home directory
+pub fn expand_tilde(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix('~') {
+        dirs::home_dir()
+            .unwrap_or_default()
+            .join(rest.trim_start_matches('/'))
+    } else {
+        PathBuf::from(path)
+    }
 }
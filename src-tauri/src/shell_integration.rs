@@ -0,0 +1,205 @@
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use super::pty_manager::session_key;
+
+/// Commands remembered per pane
+const MAX_COMMANDS: usize = 200;
+
+/// Echoed input kept for one command line
+const MAX_COMMAND_LINE: usize = 4096;
+
+/// An unterminated OSC 133 sequence longer than this is treated as output
+const MAX_PARTIAL: usize = 256;
+
+const MARKER: &[u8] = b"\x1b]133;";
+
+/// Per pane, keyed by `session_key` of the pane id (or the PTY target when
+/// it isn't a pane)
+static TRACKERS: once_cell::sync::Lazy<Mutex<HashMap<String, Tracker>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandRecord {
+    /// The command line as echoed between the prompt and `OSC 133;C`
+    pub command: String,
+    /// Unix time the command started
+    pub started_at: u64,
+    /// Unset while running
+    pub duration_ms: Option<u64>,
+    /// From `OSC 133;D`; unset while running or when the shell didn't report it
+    pub exit_code: Option<i32>,
+    pub running: bool,
+}
+
+/// OSC 133 state of one pane: `A` prompt, `B` input, `C` output, `D;status` done
+#[derive(Default)]
+struct Tracker {
+    commands: VecDeque<CommandRecord>,
+    /// When the last command started, while it runs
+    started: Option<Instant>,
+    /// Echoed input since `OSC 133;B`
+    input: Option<Vec<u8>>,
+    /// Start of a sequence split across reads
+    partial: Vec<u8>,
+}
+
+impl Tracker {
+    fn feed(&mut self, data: &[u8]) {
+        let joined;
+        let mut rest = if self.partial.is_empty() {
+            data
+        } else {
+            let mut buf = std::mem::take(&mut self.partial);
+            buf.extend_from_slice(data);
+            joined = buf;
+            &joined[..]
+        };
+        loop {
+            let Some(start) = rest.windows(MARKER.len()).position(|w| w == MARKER) else {
+                // Hold back what could be the start of a marker
+                let keep = (1..MARKER.len())
+                    .rev()
+                    .find(|&n| rest.ends_with(&MARKER[..n]))
+                    .unwrap_or(0);
+                let (text, tail) = rest.split_at(rest.len() - keep);
+                self.output(text);
+                self.partial.extend_from_slice(tail);
+                return;
+            };
+            self.output(&rest[..start]);
+            let body = &rest[start + MARKER.len()..];
+            match terminator(body) {
+                Some((end, len)) => {
+                    self.marker(&String::from_utf8_lossy(&body[..end]));
+                    rest = &body[end + len..];
+                }
+                None if body.len() < MAX_PARTIAL => {
+                    self.partial.extend_from_slice(&rest[start..]);
+                    return;
+                }
+                None => rest = body,
+            }
+        }
+    }
+
+    fn output(&mut self, text: &[u8]) {
+        if let Some(input) = &mut self.input {
+            let room = MAX_COMMAND_LINE.saturating_sub(input.len());
+            input.extend_from_slice(&text[..text.len().min(room)]);
+        }
+    }
+
+    fn marker(&mut self, payload: &str) {
+        let mut params = payload.split(';');
+        match params.next().unwrap_or_default() {
+            "A" => {
+                // Shells without `D` only tell us a command ended by prompting again
+                self.finish(None);
+                self.input = None;
+            }
+            "B" => self.input = Some(Vec::new()),
+            "C" => {
+                self.finish(None);
+                let command = self.input.take().map(|i| command_line(&i));
+                self.commands.push_back(CommandRecord {
+                    command: command.unwrap_or_default(),
+                    started_at: super::activity::now_unix(),
+                    duration_ms: None,
+                    exit_code: None,
+                    running: true,
+                });
+                if self.commands.len() > MAX_COMMANDS {
+                    self.commands.pop_front();
+                }
+                self.started = Some(Instant::now());
+            }
+            "D" => {
+                let status = params.next().and_then(|s| s.trim().parse().ok());
+                self.finish(status);
+            }
+            _ => {}
+        }
+    }
+
+    /// End the running command, if any
+    fn finish(&mut self, exit_code: Option<i32>) {
+        let Some(started) = self.started.take() else {
+            return;
+        };
+        if let Some(last) = self.commands.back_mut() {
+            last.running = false;
+            last.duration_ms = Some(started.elapsed().as_millis() as u64);
+            last.exit_code = exit_code;
+        }
+    }
+}
+
+/// Where an OSC body ends and the terminator's length (BEL or ST); an ESC
+/// starting another sequence ends it too
+pub(crate) fn terminator(body: &[u8]) -> Option<(usize, usize)> {
+    let end = body.iter().position(|b| *b == 0x07 || *b == 0x1b)?;
+    match (body[end], body.get(end + 1)) {
+        (0x07, _) => Some((end, 1)),
+        (_, Some(b'\\')) => Some((end, 2)),
+        (_, Some(_)) => Some((end, 0)),
+        (_, None) => None,
+    }
+}
+
+/// The command line in echoed input, after applying backspaces and redraws
+fn command_line(input: &[u8]) -> String {
+    let text = String::from_utf8_lossy(input);
+    let text = super::links::strip_ansi(&text);
+    let mut line = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x08' | '\x7f' => {
+                line.pop();
+            }
+            // Line editors redraw from the first column
+            '\r' if chars.peek() != Some(&'\n') => line.clear(),
+            '\n' => line.push(' '),
+            c if c.is_control() => {}
+            c => line.push(c),
+        }
+    }
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Feed a pane's output to its command tracker
+pub fn observe(key: &str, data: &[u8]) {
+    TRACKERS
+        .lock()
+        .unwrap()
+        .entry(key.to_string())
+        .or_default()
+        .feed(data);
+}
+
+/// Commands `target` ran, oldest first, from OSC 133 markers its shell
+/// printed. On tmux this starts piping the pane, so history begins with the
+/// first call for panes that aren't attached.
+pub async fn history(target: &str) -> Result<Vec<CommandRecord>, String> {
+    let server = super::tmux::current_server();
+    let key = if super::multiplexer::current().name() == "tmux" {
+        let pane_id = super::tmux::pane_id(target).await?;
+        let key = session_key(server.as_deref(), &pane_id);
+        if let Err(e) = super::pane_pipe::ensure(&pane_id, &key).await {
+            log::warn!("[shell-integration] Not piping {}: {}", target, e);
+        }
+        key
+    } else {
+        session_key(server.as_deref(), target)
+    };
+    Ok(TRACKERS
+        .lock()
+        .unwrap()
+        .get(&key)
+        .map(|t| t.commands.iter().cloned().collect())
+        .unwrap_or_default())
+}
@@ -0,0 +1,348 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+
+/// Schema version of each state file, by path relative to its root
+const VERSIONS_FILE: &str = "state-versions.json";
+
+/// Rewrites a file's JSON from one version to the next
+type Migration = fn(serde_json::Value) -> Result<serde_json::Value, String>;
+
+/// Which directory a state file lives in
+#[derive(Clone, Copy)]
+enum Root {
+    /// `paths::data_dir`, for state MuxTunnel writes itself
+    Data,
+    /// `paths::config_dir`, for user-authored config
+    Config,
+}
+
+/// How a state file is stored
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// One JSON document
+    Json,
+    /// One JSON document per line; migrations and validation apply per line
+    JsonLines,
+    /// A FIFO recreated on use; anything else at its path is quarantined
+    Fifo,
+}
+
+/// A file (or directory of them) MuxTunnel keeps under `paths::data_dir` or
+/// `paths::config_dir`
+struct StateFile {
+    root: Root,
+    /// Relative to its root; a trailing `/` means every `.json` in it
+    path: &'static str,
+    format: Format,
+    /// Current schema version; files without a stamp are version 1
+    version: u32,
+    /// `migrations[i]` upgrades version `i + 1` to `i + 2`
+    migrations: &'static [Migration],
+    /// Whether the contents deserialize as the type the owning module loads
+    validate: fn(&str) -> Result<(), String>,
+}
+
+fn parses<T: DeserializeOwned>(raw: &str) -> Result<(), String> {
+    serde_json::from_str::<T>(raw)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn no_contents(_: &str) -> Result<(), String> {
+    Ok(())
+}
+
+/// A JSON file in the data directory at version 1
+const fn data(path: &'static str, validate: fn(&str) -> Result<(), String>) -> StateFile {
+    StateFile {
+        root: Root::Data,
+        path,
+        format: Format::Json,
+        version: 1,
+        migrations: &[],
+        validate,
+    }
+}
+
+/// A JSON file in the config directory at version 1
+const fn config(path: &'static str, validate: fn(&str) -> Result<(), String>) -> StateFile {
+    StateFile {
+        root: Root::Config,
+        ..data(path, validate)
+    }
+}
+
+const FILES: &[StateFile] = &[
+    config("settings.json", super::settings::validate),
+    config("layouts.json", parses::<BTreeMap<String, String>>),
+    config("templates/", parses::<super::templates::SessionTemplate>),
+    data("session-order.json", parses::<Vec<String>>),
+    data("window-order.json", parses::<HashMap<String, Vec<String>>>),
+    data("history.json", parses::<super::resolver::HistoryDB>),
+    data(
+        "recently-closed.json",
+        parses::<Vec<super::recently_closed::ClosedSession>>,
+    ),
+    data(
+        "session-locks.json",
+        parses::<Vec<super::input_lock::SessionLock>>,
+    ),
+    data(
+        "pane-meta.json",
+        parses::<BTreeMap<String, super::pane_meta::PaneMeta>>,
+    ),
+    data("pairs.json", parses::<Vec<super::pairs::Pair>>),
+    data("pane-logs.json", parses::<Vec<super::pane_log::SavedLog>>),
+    data("snapshots/", parses::<super::workspace::Snapshot>),
+    StateFile {
+        format: Format::JsonLines,
+        ..data("activity.jsonl", parses::<super::activity::Activity>)
+    },
+    StateFile {
+        format: Format::Fifo,
+        ..data("pipes/follow-focus.fifo", no_contents)
+    },
+];
+
+// Bumping a version without adding its migration would make `check_file`
+// slice past the end of `migrations`
+const _: () = {
+    let mut i = 0;
+    while i < FILES.len() {
+        assert!(
+            FILES[i].migrations.len() + 1 == FILES[i].version as usize,
+            "every state file needs one migration per version after the first"
+        );
+        i += 1;
+    }
+};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileCheck {
+    pub path: String,
+    /// "ok", "migrated", "quarantined" or "newer" (written by a newer
+    /// MuxTunnel; left alone)
+    pub status: String,
+    pub version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Where a quarantined file was moved
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moved_to: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateReport {
+    pub data_dir: String,
+    /// Existing state files, in check order
+    pub files: Vec<FileCheck>,
+}
+
+fn load_versions(dir: &Path) -> BTreeMap<String, u32> {
+    fs::read_to_string(dir.join(VERSIONS_FILE))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_versions(dir: &Path, versions: &BTreeMap<String, u32>) {
+    if let Err(e) = fs::write(
+        dir.join(VERSIONS_FILE),
+        serde_json::to_string_pretty(versions).unwrap_or_default(),
+    ) {
+        log::error!("[state] Failed to save {}: {}", VERSIONS_FILE, e);
+    }
+}
+
+/// Move a corrupt file aside as `<name>.corrupt`, keeping earlier quarantines
+fn quarantine(path: &Path) -> Result<PathBuf, String> {
+    let mut moved = PathBuf::from(format!("{}.corrupt", path.display()));
+    if moved.exists() {
+        moved = PathBuf::from(format!(
+            "{}.corrupt.{}",
+            path.display(),
+            super::activity::now_unix()
+        ));
+    }
+    fs::rename(path, &moved)
+        .map_err(|e| format!("Failed to quarantine {}: {}", path.display(), e))?;
+    Ok(moved)
+}
+
+/// Bring one JSON document up to `file.version`: `None` when it's current
+/// and valid, else the migrated value
+fn upgrade(file: &StateFile, raw: &str, stamped: u32) -> Result<Option<serde_json::Value>, String> {
+    if stamped == file.version {
+        return (file.validate)(raw).map(|_| None);
+    }
+    let mut value: serde_json::Value = serde_json::from_str(raw).map_err(|e| e.to_string())?;
+    for migrate in &file.migrations[(stamped - 1) as usize..(file.version - 1) as usize] {
+        value = migrate(value)?;
+    }
+    (file.validate)(&value.to_string())?;
+    Ok(Some(value))
+}
+
+/// `upgrade` every line of a JSON Lines file, returning the rewritten file if
+/// any line changed
+fn upgrade_lines(file: &StateFile, raw: &str, stamped: u32) -> Result<Option<String>, String> {
+    let count = raw.lines().count();
+    let mut lines = Vec::new();
+    let mut changed = false;
+    for (i, line) in raw.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match upgrade(file, line, stamped) {
+            Ok(Some(value)) => {
+                lines.push(value.to_string());
+                changed = true;
+            }
+            Ok(None) => lines.push(line.to_string()),
+            // An append cut off by a crash; readers skip it too
+            Err(_) if i + 1 == count && !raw.ends_with('\n') => lines.push(line.to_string()),
+            Err(e) => return Err(format!("Line {}: {}", i + 1, e)),
+        }
+    }
+    Ok(changed.then(|| lines.iter().map(|line| format!("{}\n", line)).collect()))
+}
+
+/// Bring one file up to `file.version`, returning its status
+fn check_file(file: &StateFile, rel: String, path: &Path, stamped: u32) -> FileCheck {
+    let mut check = FileCheck {
+        path: rel,
+        status: "ok".to_string(),
+        version: stamped,
+        message: None,
+        moved_to: None,
+    };
+    if stamped > file.version {
+        check.status = "newer".to_string();
+        check.message = Some(format!("Supported up to version {}", file.version));
+        return check;
+    }
+
+    let outcome = match file.format {
+        Format::Fifo => {
+            let is_fifo = fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_fifo());
+            if is_fifo {
+                Ok(None)
+            } else {
+                Err("Not a FIFO".to_string())
+            }
+        }
+        Format::Json => fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|raw| upgrade(file, &raw, stamped))
+            .and_then(|value| {
+                value
+                    .map(|v| serde_json::to_string_pretty(&v).map_err(|e| e.to_string()))
+                    .transpose()
+            }),
+        Format::JsonLines => fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|raw| upgrade_lines(file, &raw, stamped)),
+    };
+    match outcome {
+        Ok(None) => {}
+        Ok(Some(migrated)) => match fs::write(path, migrated) {
+            Ok(()) => {
+                check.status = "migrated".to_string();
+                check.message = Some(format!("From version {}", stamped));
+                check.version = file.version;
+            }
+            Err(e) => check.message = Some(format!("Failed to write migration: {}", e)),
+        },
+        Err(e) => {
+            check.message = Some(e);
+            match quarantine(path) {
+                Ok(moved) => {
+                    check.status = "quarantined".to_string();
+                    check.moved_to = Some(moved.display().to_string());
+                }
+                Err(e) => check.message = Some(e),
+            }
+        }
+    }
+    check
+}
+
+/// Validate every state file against the type its module loads, migrating
+/// older schema versions and renaming corrupt files to `.corrupt` (loaders
+/// would otherwise start empty and overwrite them on the next save)
+pub fn check() -> StateReport {
+    let dir = super::paths::data_dir();
+    let config_dir = super::paths::config_dir();
+    let mut versions = load_versions(&dir);
+    let mut files = Vec::new();
+
+    for file in FILES {
+        let root = match file.root {
+            Root::Data => &dir,
+            Root::Config => &config_dir,
+        };
+        let paths: Vec<(String, PathBuf)> = match file.path.strip_suffix('/') {
+            Some(sub) => fs::read_dir(root.join(sub))
+                .map(|entries| {
+                    entries
+                        .flatten()
+                        .map(|e| e.path())
+                        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                        .map(|p| {
+                            let name = p.file_name().unwrap_or_default().to_string_lossy();
+                            (format!("{}/{}", sub, name), p)
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            None => vec![(file.path.to_string(), root.join(file.path))],
+        };
+        let exists = |p: &PathBuf| match file.format {
+            Format::Fifo => fs::symlink_metadata(p).is_ok(),
+            _ => p.is_file(),
+        };
+        for (rel, path) in paths.into_iter().filter(|(_, p)| exists(p)) {
+            let stamped = versions.get(&rel).copied().unwrap_or(1).max(1);
+            let check = check_file(file, rel.clone(), &path, stamped);
+            let message = check.message.as_deref().unwrap_or("");
+            match check.status.as_str() {
+                "ok" | "migrated" => {
+                    if check.status == "migrated" {
+                        log::info!("[state] Migrated {} to version {}", rel, check.version);
+                    }
+                    // A FIFO has no contents to stamp
+                    if file.format != Format::Fifo {
+                        versions.insert(rel, check.version);
+                    }
+                }
+                "quarantined" => {
+                    log::warn!("[state] Quarantined {}: {}", rel, message);
+                    versions.remove(&rel);
+                }
+                _ => log::warn!("[state] Left {} alone: {}", rel, message),
+            }
+            files.push(check);
+        }
+    }
+
+    // Forget stamps of files that were deleted (or just quarantined)
+    let present: HashSet<&str> = files
+        .iter()
+        .filter(|f| f.status != "quarantined")
+        .map(|f| f.path.as_str())
+        .collect();
+    versions.retain(|rel, _| present.contains(rel.as_str()));
+    if !files.is_empty() {
+        save_versions(&dir, &versions);
+    }
+    StateReport {
+        data_dir: dir.display().to_string(),
+        files,
+    }
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A reusable session shape, stored as `<config dir>/templates/<name>.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionTemplate {
+    pub windows: Vec<TemplateWindow>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateWindow {
+    pub name: String,
+    /// Relative to the project path (absolute and `~` paths are used as-is)
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// tmux layout name or string, applied after all panes are created
+    #[serde(default)]
+    pub layout: Option<String>,
+    /// The first entry is the window's initial pane
+    #[serde(default)]
+    pub panes: Vec<TemplatePane>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplatePane {
+    /// Relative to the window's cwd
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Typed into the pane's shell once it starts
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Split side by side instead of stacked
+    #[serde(default)]
+    pub horizontal: bool,
+}
+
+fn templates_dir() -> PathBuf {
+    super::paths::config_dir().join("templates")
+}
+
+/// Template names (file stems), sorted
+pub fn list() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(templates_dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map(|e| e == "json").unwrap_or(false))
+        .filter_map(|path| Some(path.file_stem()?.to_string_lossy().to_string()))
+        .collect();
+    names.sort();
+    names
+}
+
+fn template_file(name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() || name.contains('/') || name.starts_with('.') {
+        return Err(format!("Invalid template name: {}", name));
+    }
+    Ok(templates_dir().join(format!("{}.json", name)))
+}
+
+pub fn load(name: &str) -> Result<SessionTemplate, String> {
+    let path = template_file(name)?;
+    let raw = fs::read_to_string(&path).map_err(|_| format!("No template named: {}", name))?;
+    serde_json::from_str(&raw).map_err(|e| format!("Invalid template {}: {}", name, e))
+}
+
+/// Save `template` as `name`, leaving an existing template by that name
+/// alone. Returns whether it was written.
+pub fn create(name: &str, template: &SessionTemplate) -> Result<bool, String> {
+    let path = template_file(name)?;
+    if path.exists() {
+        return Ok(false);
+    }
+    fs::create_dir_all(templates_dir())
+        .map_err(|e| format!("Failed to create templates dir: {}", e))?;
+    let json = serde_json::to_string_pretty(template).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(true)
+}
+
+/// `cwd` of a template entry: absolute and `~` paths as-is, others relative to `base`
+pub fn resolve(base: &Path, cwd: Option<&str>) -> PathBuf {
+    match cwd {
+        Some(cwd) if cwd.starts_with('/') || cwd.starts_with('~') => {
+            super::settings::expand_tilde(cwd)
+        }
+        Some(cwd) => base.join(cwd),
+        None => base.to_path_buf(),
+    }
+}
+
+/// Session name for a project directory: its basename with characters tmux
+/// rejects in session names replaced
+pub fn session_name_for(project_path: &str) -> String {
+    let path = super::settings::expand_tilde(project_path);
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "session".to_string())
+        .replace(['.', ':'], "_")
+}
+
+async fn build_window(target: &str, window: &TemplateWindow, cwd: &Path) -> Result<(), String> {
+    for pane in window.panes.iter().skip(1) {
+        let pane_cwd = resolve(cwd, pane.cwd.as_deref());
+        super::tmux::split_window(target, &pane_cwd.to_string_lossy(), pane.horizontal).await?;
+    }
+    if let Some(layout) = &window.layout {
+        super::tmux::select_layout(target, layout).await?;
+    }
+
+    let pane_ids = super::tmux::list_pane_ids(target).await?;
+    for (pane_id, pane) in pane_ids.iter().zip(&window.panes) {
+        if let Some(command) = pane.command.as_deref().filter(|c| !c.is_empty()) {
+            super::tmux::send_keys_literal(pane_id, command).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Create session `name` for `project_path` with the windows, splits and
+/// startup commands of `template`
+pub async fn create_session(
+    template: &SessionTemplate,
+    name: &str,
+    project_path: &str,
+) -> Result<(), String> {
+    let existing = super::tmux::list_sessions().await;
+    if existing.iter().any(|s| s.name == name) {
+        return Err(format!("Session already exists: {}", name));
+    }
+    let project = super::settings::expand_tilde(project_path);
+    let mut windows = template.windows.iter();
+    let first = windows
+        .next()
+        .ok_or_else(|| "Template has no windows".to_string())?;
+
+    let window_cwd = |window: &TemplateWindow| resolve(&project, window.cwd.as_deref());
+    let first_cwd = window_cwd(first);
+    let first_pane_cwd = resolve(
+        &first_cwd,
+        first.panes.first().and_then(|p| p.cwd.as_deref()),
+    );
+    super::tmux::create_session(name, &first_pane_cwd.to_string_lossy()).await?;
+
+    let first_target = super::tmux::list_window_ids(name)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("Session has no windows: {}", name))?;
+    super::tmux::rename_window(&first_target, &first.name).await?;
+    build_window(&first_target, first, &first_cwd).await?;
+
+    for window in windows {
+        add_window(name, window, &project).await?;
+    }
+    Ok(())
+}
+
+/// Append `window` (with its splits and commands) to an existing session,
+/// resolving its cwd against `project`
+pub async fn add_window(
+    session: &str,
+    window: &TemplateWindow,
+    project: &Path,
+) -> Result<(), String> {
+    let cwd = resolve(project, window.cwd.as_deref());
+    let pane_cwd = resolve(&cwd, window.panes.first().and_then(|p| p.cwd.as_deref()));
+    let window_id =
+        super::tmux::new_window(session, &window.name, &pane_cwd.to_string_lossy()).await?;
+    build_window(&window_id, window, &cwd).await
+}
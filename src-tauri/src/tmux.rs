@@ -1,7 +1,28 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use tokio::process::Command;
 
+use super::capabilities::Tool;
+
+/// Field separator in `-F` formats. tmux prints control characters in names
+/// and titles escaped (`\037`), so it can't occur inside a value the way
+/// tabs and colons can.
+const SEP: &str = "\x1f";
+
+/// Pane, window and session ids and name-based pane targets → what they address
+type AddressTable = HashMap<String, Address>;
+
+/// What `list_sessions` last saw, per server
+static ADDRESSES: once_cell::sync::Lazy<std::sync::Mutex<HashMap<Option<String>, AddressTable>>> =
+    once_cell::sync::Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone)]
+struct Address {
+    session: String,
+    /// Set for pane-level addresses
+    pane_id: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TmuxPane {
@@ -10,6 +31,11 @@ pub struct TmuxPane {
     pub window_name: String,
     pub pane_index: u32,
     pub pane_id: String,
+    /// `@N`, stable across renames and moves
+    pub window_id: String,
+    /// `$N`, stable across renames
+    pub session_id: String,
+    /// `session:window.pane`; any command also accepts `pane_id` instead
     pub target: String,
     pub active: bool,
     pub cols: u32,
@@ -18,8 +44,40 @@ pub struct TmuxPane {
     pub top: u32,
     pub pid: u32,
     pub process: String,
+    /// The pane's process exited and tmux kept the pane (`remain-on-exit`)
+    pub dead: bool,
+    /// Exit status of a dead pane's process
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dead_status: Option<i32>,
+    /// `#{pane_title}`, set by the program or `panes_set_title`
+    pub title: String,
+    /// In copy mode (or another mode such as choose-tree)
+    pub in_mode: bool,
+    /// Running a full-screen program on the alternate screen
+    pub alternate_on: bool,
+    /// Lines scrolled back from the bottom while in copy mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scroll_position: Option<u32>,
+    /// Unix time the pane last produced output (see `pane_activity`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_output: Option<u64>,
+    /// Recent output in lines per second
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_rate: Option<f64>,
+    /// CPU% of the effective process and its children (unset without `ps`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_percent: Option<f32>,
+    /// Resident memory of the effective process and its children
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rss_bytes: Option<u64>,
+    /// Tags, pin and watches set through `panes_meta_set`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<super::pane_meta::PaneMeta>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub claude_session: Option<super::claude_sessions::ClaudeSession>,
+    /// The pane's side of a `pairs_create` pair
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pair: Option<super::pairs::PanePair>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +92,8 @@ pub struct TmuxWindow {
 #[serde(rename_all = "camelCase")]
 pub struct TmuxSession {
     pub name: String,
+    /// `$N`, stable across renames
+    pub id: String,
     pub windows: Vec<TmuxWindow>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub dimensions: Option<SessionDimensions>,
@@ -41,6 +101,14 @@ pub struct TmuxSession {
     pub activity: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inferred_group: Option<String>,
+    /// Registered tmux server this session lives on (`None` for the default server)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    /// tmux session group this session shares its windows with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_group: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +117,34 @@ pub struct SessionDimensions {
     pub height: u32,
 }
 
+/// Pane number shown by `display-panes` in the session's current window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaneLabel {
+    pub index: u32,
+    pub pane_id: String,
+    pub target: String,
+}
+
+/// A terminal attached to a session, as reported by `list-clients`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TmuxClient {
+    /// Name to pass to `detach-client -t` (usually its tty)
+    pub name: String,
+    pub session: String,
+    pub tty: String,
+    pub pid: Option<u32>,
+    pub width: u32,
+    pub height: u32,
+    pub termname: String,
+    /// Unix time of the client's last input
+    pub activity: Option<u64>,
+    pub read_only: bool,
+    /// One of MuxTunnel's own PTY attaches
+    pub muxtunnel: bool,
+}
+
 /// Extract clean command name from ps output (basename of path)
 fn extract_cmd_name(ps_output: &str) -> &str {
     let first_word = ps_output.split_whitespace().next().unwrap_or(ps_output);
@@ -63,10 +159,46 @@ const WRAPPERS: &[&str] = &[
     "zsh", "bash", "sh", "fish", "tcsh", "csh", "-zsh", "-bash", "-sh", "npm", "npx", "node",
 ];
 
+/// One row of the process table
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub ppid: u32,
+    pub command: String,
+    /// `ps` %CPU (may exceed 100 on multicore)
+    pub cpu: f32,
+    pub rss_kb: u64,
+}
+
+/// Split off the first whitespace-delimited field (`ps` pads its columns)
+fn next_field(s: &str) -> Option<(&str, &str)> {
+    let s = s.trim_start();
+    let end = s.find(char::is_whitespace)?;
+    Some((&s[..end], s[end..].trim_start()))
+}
+
+/// Parse: PID PPID %CPU RSS COMMAND (the command may contain spaces)
+fn parse_ps_line(line: &str) -> Option<(u32, ProcessInfo)> {
+    let (pid, rest) = next_field(line)?;
+    let (ppid, rest) = next_field(rest)?;
+    let (cpu, rest) = next_field(rest)?;
+    let (rss, command) = next_field(rest)?;
+    let info = ProcessInfo {
+        ppid: ppid.parse().ok()?,
+        command: command.trim().to_string(),
+        cpu: cpu.parse().unwrap_or(0.0),
+        rss_kb: rss.parse().unwrap_or(0),
+    };
+    Some((pid.parse().ok()?, info))
+}
+
 /// Fetch the entire process table in a single `ps` call.
-async fn get_process_table() -> HashMap<u32, (u32, String)> {
+pub async fn get_process_table() -> HashMap<u32, ProcessInfo> {
+    // Without ps, panes report tmux's pane_current_command as-is
+    if !super::capabilities::has(Tool::Ps) {
+        return HashMap::new();
+    }
     let output = Command::new("ps")
-        .args(["-eo", "pid=,ppid=,comm="])
+        .args(["-eo", "pid=,ppid=,pcpu=,rss=,comm="])
         .output()
         .await;
 
@@ -74,30 +206,23 @@ async fn get_process_table() -> HashMap<u32, (u32, String)> {
     if let Ok(output) = output {
         let stdout = String::from_utf8_lossy(&output.stdout);
         for line in stdout.lines() {
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
-                continue;
-            }
-            // Parse: PID PPID COMMAND
-            let parts: Vec<&str> = trimmed.splitn(3, char::is_whitespace).collect();
-            if parts.len() >= 3 {
-                if let (Ok(pid), Ok(ppid)) = (parts[0].trim().parse::<u32>(), parts[1].trim().parse::<u32>()) {
-                    table.insert(pid, (ppid, parts[2].trim().to_string()));
-                }
+            if let Some((pid, info)) = parse_ps_line(line) {
+                table.insert(pid, info);
             }
         }
     }
     table
 }
 
-/// Walk the process tree to find the real command (skip shells and wrappers)
+/// Walk the process tree to find the real command (skip shells and wrappers).
+/// Returns the command name and the pid it was found at.
 fn get_effective_process_from_table(
     pid: u32,
     current_command: &str,
-    table: &HashMap<u32, (u32, String)>,
-) -> String {
+    table: &HashMap<u32, ProcessInfo>,
+) -> (String, u32) {
     if !WRAPPERS.contains(&current_command) {
-        return current_command.to_string();
+        return (current_command.to_string(), pid);
     }
 
     let mut current_pid = pid;
@@ -105,77 +230,601 @@ fn get_effective_process_from_table(
         // Find children of current_pid
         let children: Vec<u32> = table
             .iter()
-            .filter(|(_, (ppid, _))| *ppid == current_pid)
+            .filter(|(_, info)| info.ppid == current_pid)
             .map(|(child_pid, _)| *child_pid)
             .collect();
 
         if children.is_empty() {
             if current_pid != pid {
-                if let Some((_, comm)) = table.get(&current_pid) {
-                    let cmd = extract_cmd_name(comm);
+                if let Some(info) = table.get(&current_pid) {
+                    let cmd = extract_cmd_name(&info.command);
                     if !cmd.is_empty() {
-                        return cmd.to_string();
+                        return (cmd.to_string(), current_pid);
                     }
                 }
             }
-            return current_command.to_string();
+            return (current_command.to_string(), pid);
         }
 
         let child_pid = children[0];
         let child_info = match table.get(&child_pid) {
             Some(info) => info,
-            None => return current_command.to_string(),
+            None => return (current_command.to_string(), pid),
         };
 
-        let cmd_name = extract_cmd_name(&child_info.1);
+        let cmd_name = extract_cmd_name(&child_info.command);
         if !WRAPPERS.contains(&cmd_name) {
             let prefixed = format!("-{}", cmd_name);
             if !WRAPPERS.contains(&prefixed.as_str()) {
-                return cmd_name.to_string();
+                return (cmd_name.to_string(), child_pid);
             }
         }
 
         current_pid = child_pid;
     }
 
-    current_command.to_string()
+    (current_command.to_string(), pid)
 }
 
-/// Check if tmux server is running
-pub async fn is_tmux_running() -> bool {
-    Command::new("tmux")
-        .args(["list-sessions"])
-        .output()
+/// Summed CPU% and RSS (bytes) of `pid` and all its descendants,
+/// or `None` without a process table
+fn process_tree_usage(pid: u32, table: &HashMap<u32, ProcessInfo>) -> Option<(f32, u64)> {
+    let root = table.get(&pid)?;
+    let (mut cpu, mut rss_kb) = (root.cpu, root.rss_kb);
+    let mut pending = vec![pid];
+    while let Some(parent) = pending.pop() {
+        for (child, info) in table.iter().filter(|(_, info)| info.ppid == parent) {
+            cpu += info.cpu;
+            rss_kb += info.rss_kb;
+            pending.push(*child);
+        }
+    }
+    Some(((cpu * 10.0).round() / 10.0, rss_kb * 1024))
+}
+
+tokio::task_local! {
+    /// Registered server (from `tmux.servers`) that tmux calls in this task go to;
+    /// `None` means the default server
+    static SERVER: Option<String>;
+}
+
+/// Run `f` with every tmux invocation inside it routed to `server`
+pub async fn with_server<F: std::future::Future>(server: Option<String>, f: F) -> F::Output {
+    SERVER.scope(server, f).await
+}
+
+/// The server the current task is scoped to
+pub fn current_server() -> Option<String> {
+    SERVER.try_with(|s| s.clone()).ok().flatten()
+}
+
+/// Names of the registered non-default servers
+pub fn server_names() -> Vec<String> {
+    super::settings::get_settings()
+        .settings
+        .tmux
+        .servers
+        .into_iter()
+        .map(|s| s.name)
+        .collect()
+}
+
+/// `socket_args` by server, for the settings version they were built from
+static SOCKET_ARGS: once_cell::sync::Lazy<std::sync::Mutex<SocketArgsCache>> =
+    once_cell::sync::Lazy::new(|| std::sync::Mutex::new((0, HashMap::new())));
+
+type SocketArgsCache = (u32, HashMap<Option<String>, Vec<String>>);
+
+fn socket_flags(socket_name: Option<String>, socket_path: Option<String>) -> Vec<String> {
+    if let Some(path) = socket_path.filter(|p| !p.is_empty()) {
+        let path = super::settings::expand_tilde(&path);
+        vec!["-S".to_string(), path.to_string_lossy().to_string()]
+    } else if let Some(name) = socket_name.filter(|n| !n.is_empty()) {
+        vec!["-L".to_string(), name]
+    } else {
+        vec![]
+    }
+}
+
+/// Global flags selecting the tmux server socket (`-L name` or `-S path`)
+/// for the server the current task is scoped to
+pub fn socket_args() -> Vec<String> {
+    let version = super::settings::settings_version();
+    let server = current_server();
+    let mut cache = SOCKET_ARGS.lock().unwrap();
+    if cache.0 != version {
+        *cache = (version, HashMap::new());
+    }
+    cache
+        .1
+        .entry(server.clone())
+        .or_insert_with(|| socket_args_for(server))
+        .clone()
+}
+
+fn socket_args_for(server: Option<String>) -> Vec<String> {
+    let settings = super::settings::get_settings().settings.tmux;
+    match server {
+        Some(name) => match settings.servers.into_iter().find(|s| s.name == name) {
+            Some(server) => {
+                let socket_name = server.socket_name.or(Some(server.name));
+                socket_flags(socket_name, server.socket_path)
+            }
+            // Unknown names are treated as a socket name
+            None => vec!["-L".to_string(), name],
+        },
+        None => socket_flags(settings.socket_name, settings.socket_path),
+    }
+}
+
+/// What a login shell knows about tmux: where it is and its environment
+#[derive(Debug, Default)]
+struct ShellTmux {
+    binary: Option<String>,
+    tmpdir: Option<String>,
+}
+
+/// What the user's login shell reported, once `resolve_shell_tmux` ran
+static SHELL_TMUX: once_cell::sync::OnceCell<ShellTmux> = once_cell::sync::OnceCell::new();
+
+/// `tmux_binary`'s answer and the settings version it was resolved under
+static BINARY: std::sync::Mutex<Option<(u32, String)>> = std::sync::Mutex::new(None);
+
+fn ask_login_shell() -> ShellTmux {
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+    let output = std::process::Command::new(shell)
+        .args([
+            "-lc",
+            "printf 'binary=%s\\ntmpdir=%s\\n' \"$(command -v tmux)\" \"$TMUX_TMPDIR\"",
+        ])
+        .stdin(std::process::Stdio::null())
+        .output();
+    let Ok(output) = output else {
+        return ShellTmux::default();
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let field = |key: &str| {
+        stdout
+            .lines()
+            .find_map(|l| l.strip_prefix(key))
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_string())
+    };
+    let binary = field("binary=").filter(|b| b.starts_with('/'));
+    let tmpdir = field("tmpdir=");
+    ShellTmux { binary, tmpdir }
+}
+
+/// Ask the user's login shell where tmux is, since an app launched from the
+/// dock doesn't inherit their shell profile. Called once at startup; the
+/// shell runs on a blocking thread.
+pub async fn resolve_shell_tmux() {
+    let shell_tmux = tokio::task::spawn_blocking(ask_login_shell)
         .await
-        .map(|o| o.status.success())
-        .unwrap_or(false)
+        .unwrap_or_default();
+    let _ = SHELL_TMUX.set(shell_tmux);
+    // A fallback chosen before the shell answered is stale now
+    *BINARY.lock().unwrap() = None;
+}
+
+/// Path to the tmux binary: `tmux.binary`, then `$PATH`, then common
+/// install prefixes, then whatever the login shell resolves. Cached until
+/// the settings change.
+pub fn tmux_binary() -> String {
+    let version = super::settings::settings_version();
+    if let Some((cached_version, binary)) = BINARY.lock().unwrap().as_ref() {
+        if *cached_version == version {
+            return binary.clone();
+        }
+    }
+    let binary = find_tmux_binary();
+    *BINARY.lock().unwrap() = Some((version, binary.clone()));
+    binary
+}
+
+fn find_tmux_binary() -> String {
+    if let Some(binary) = super::settings::get_settings()
+        .settings
+        .tmux
+        .binary
+        .filter(|b| !b.is_empty())
+    {
+        return super::settings::expand_tilde(&binary)
+            .to_string_lossy()
+            .to_string();
+    }
+
+    super::capabilities::find_program("tmux")
+        .map(|path| path.to_string_lossy().to_string())
+        .or_else(|| SHELL_TMUX.get().and_then(|s| s.binary.clone()))
+        .unwrap_or_else(|| "tmux".to_string())
+}
+
+/// Environment tmux needs to find the same server as the user's shell
+pub fn tmux_env() -> Vec<(String, String)> {
+    let tmpdir = std::env::var("TMUX_TMPDIR")
+        .ok()
+        .filter(|d| !d.is_empty())
+        .or_else(|| SHELL_TMUX.get().and_then(|s| s.tmpdir.clone()));
+    tmpdir
+        .map(|d| vec![("TMUX_TMPDIR".to_string(), d)])
+        .unwrap_or_default()
+}
+
+/// A `tmux` command pointed at the configured server socket
+pub(crate) fn tmux_command() -> Command {
+    let mut cmd = Command::new(tmux_binary());
+    cmd.envs(tmux_env());
+    cmd.args(socket_args());
+    cmd
+}
+
+/// Run a tmux command, returning stdout or tmux's stderr as the error.
+/// Goes over the control connection when `controlMode` is enabled.
+async fn run(args: &[&str]) -> Result<String, String> {
+    super::capabilities::require(Tool::Tmux)?;
+    let subcommand = args.first().copied().unwrap_or("tmux");
+    let result = match super::tmux_control::run(args).await {
+        Some(result) => result.map_err(|e| format!("tmux {} failed: {}", subcommand, e)),
+        None => {
+            let output = tmux_command()
+                .args(args)
+                .output()
+                .await
+                .map_err(|e| format!("Failed to run tmux {}: {}", subcommand, e))?;
+            if output.status.success() {
+                Ok(String::from_utf8_lossy(&output.stdout).to_string())
+            } else {
+                Err(format!(
+                    "tmux {} failed: {}",
+                    subcommand,
+                    String::from_utf8_lossy(&output.stderr)
+                ))
+            }
+        }
+    };
+    if let Err(e) = &result {
+        super::diagnostics::record_tmux_error(args, e);
+    }
+    result
+}
+
+/// Run a tmux command that reads `input` from stdin (`load-buffer -`).
+/// Always spawns tmux, since control mode has no stdin to give a command.
+async fn run_with_input(args: &[&str], input: &[u8]) -> Result<String, String> {
+    use tokio::io::AsyncWriteExt;
+
+    super::capabilities::require(Tool::Tmux)?;
+    let subcommand = args.first().copied().unwrap_or("tmux");
+    let result = async {
+        let mut child = tmux_command()
+            .args(args)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run tmux {}: {}", subcommand, e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(input)
+                .await
+                .map_err(|e| format!("Failed to write to tmux {}: {}", subcommand, e))?;
+        }
+        let output = child
+            .wait_with_output()
+            .await
+            .map_err(|e| format!("Failed to run tmux {}: {}", subcommand, e))?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            Err(format!(
+                "tmux {} failed: {}",
+                subcommand,
+                String::from_utf8_lossy(&output.stderr)
+            ))
+        }
+    }
+    .await;
+    if let Err(e) = &result {
+        super::diagnostics::record_tmux_error(args, e);
+    }
+    result
+}
+
+/// Set a global hook, or unset it when `command` is `None`
+pub async fn set_hook(hook: &str, command: Option<&str>) -> Result<(), String> {
+    match command {
+        Some(command) => run(&["set-hook", "-g", hook, command]).await.map(|_| ()),
+        None => run(&["set-hook", "-gu", hook]).await.map(|_| ()),
+    }
+}
+
+/// Global server, session and window options merged into one map
+pub async fn global_options() -> Result<HashMap<String, String>, String> {
+    let mut options = HashMap::new();
+    for scope in ["-gs", "-g", "-gw"] {
+        let stdout = run(&["show-options", scope]).await?;
+        for line in stdout.lines() {
+            let mut parts = line.splitn(2, ' ');
+            let name = match parts.next() {
+                Some(n) if !n.is_empty() => n,
+                _ => continue,
+            };
+            let value = parts.next().unwrap_or("").trim().trim_matches('"');
+            options.insert(name.to_string(), value.to_string());
+        }
+    }
+    Ok(options)
+}
+
+/// tmux release, parsed from `tmux -V` (e.g. "tmux 3.3a", "tmux next-3.4")
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct TmuxVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl TmuxVersion {
+    fn parse(output: &str) -> Option<TmuxVersion> {
+        let version = output.split_whitespace().nth(1)?;
+        let version = version.rsplit('-').next()?;
+        let (major, rest) = version.split_once('.')?;
+        let minor: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        Some(TmuxVersion {
+            major: major.parse().ok()?,
+            minor: minor.parse().ok()?,
+        })
+    }
+}
+
+/// tmux behavior that depends on the installed version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// `session_activity` and `session_path` formats
+    SessionFormats,
+    /// `display-panes -d`
+    DisplayPanesDuration,
+    /// Hooks stored as array options (`set-hook -g name[N]`)
+    HookArrays,
+    /// `refresh-client -f no-output`, which keeps a control client from receiving pane output
+    ControlFlags,
+    /// Pane-scoped options (`set-option -p`)
+    PaneOptions,
+    /// `display-popup`
+    Popups,
+}
+
+impl Feature {
+    const ALL: [Feature; 6] = [
+        Feature::SessionFormats,
+        Feature::DisplayPanesDuration,
+        Feature::HookArrays,
+        Feature::ControlFlags,
+        Feature::PaneOptions,
+        Feature::Popups,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Feature::SessionFormats => "session-formats",
+            Feature::DisplayPanesDuration => "display-panes-duration",
+            Feature::HookArrays => "hook-arrays",
+            Feature::ControlFlags => "control-flags",
+            Feature::PaneOptions => "pane-options",
+            Feature::Popups => "popups",
+        }
+    }
+
+    fn min_version(self) -> TmuxVersion {
+        let (major, minor) = match self {
+            Feature::SessionFormats => (2, 1),
+            Feature::DisplayPanesDuration => (2, 6),
+            Feature::HookArrays => (3, 0),
+            Feature::ControlFlags => (3, 2),
+            Feature::PaneOptions => (3, 0),
+            Feature::Popups => (3, 2),
+        };
+        TmuxVersion { major, minor }
+    }
+}
+
+/// What `tmux -V` and `show-options -g` reported at startup
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerInfo {
+    /// Raw `tmux -V` output, empty if tmux couldn't be run
+    pub version_string: String,
+    pub version: Option<TmuxVersion>,
+    pub server_running: bool,
+    pub options: HashMap<String, String>,
+    /// Features the installed tmux is too old for
+    pub unsupported: Vec<String>,
+}
+
+static SERVER_INFO: once_cell::sync::Lazy<std::sync::Mutex<Option<ServerInfo>>> =
+    once_cell::sync::Lazy::new(|| std::sync::Mutex::new(None));
+
+/// Cached tmux version and global options, queried on first use
+pub async fn server_info() -> ServerInfo {
+    let cached = SERVER_INFO.lock().unwrap().clone();
+    match cached {
+        Some(info) => info,
+        None => refresh_server_info().await,
+    }
+}
+
+/// Query the tmux version and global options again
+pub async fn refresh_server_info() -> ServerInfo {
+    let version_string = match tmux_command().arg("-V").output().await {
+        Ok(o) if o.status.success() => String::from_utf8_lossy(&o.stdout).trim().to_string(),
+        _ => String::new(),
+    };
+    let version = TmuxVersion::parse(&version_string);
+    if version.is_none() && !version_string.is_empty() {
+        log::warn!("[tmux] Unrecognized version: {}", version_string);
+    }
+    let options = global_options().await;
+
+    let info = ServerInfo {
+        version_string,
+        version,
+        server_running: options.is_ok(),
+        options: options.unwrap_or_default(),
+        unsupported: Feature::ALL
+            .into_iter()
+            .filter(|f| version.map(|v| v < f.min_version()).unwrap_or(false))
+            .map(|f| f.name().to_string())
+            .collect(),
+    };
+    log::info!(
+        "[tmux] {} (server {})",
+        info.version_string,
+        if info.server_running { "running" } else { "not running" }
+    );
+    *SERVER_INFO.lock().unwrap() = Some(info.clone());
+    info
+}
+
+/// Whether the installed tmux has `feature`; unknown versions are assumed recent
+pub fn supports(feature: Feature) -> bool {
+    SERVER_INFO
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|info| info.version)
+        .map(|v| v >= feature.min_version())
+        .unwrap_or(true)
+}
+
+/// Error for operations that need a newer tmux
+pub fn require_feature(feature: Feature) -> Result<(), String> {
+    if supports(feature) {
+        return Ok(());
+    }
+    let min = feature.min_version();
+    Err(format!(
+        "tmux {}.{} or newer is required for {}",
+        min.major,
+        min.minor,
+        feature.name()
+    ))
+}
+
+fn lookup_address(target: &str) -> Option<Address> {
+    ADDRESSES
+        .lock()
+        .unwrap()
+        .get(&current_server())?
+        .get(target)
+        .cloned()
+}
+
+/// Session name of any target: a session name, `session:window[.pane]`, or a
+/// `%pane` / `@window` / `$session` id (resolved from the last `list_sessions`,
+/// or by asking tmux for ids it hasn't listed yet)
+pub async fn session_of(target: &str) -> String {
+    if target.starts_with(['%', '@', '$']) {
+        if let Some(address) = lookup_address(target) {
+            return address.session;
+        }
+        if let Ok(name) = run(&["display-message", "-t", target, "-p", "#{session_name}"]).await {
+            let name = name.trim();
+            if !name.is_empty() {
+                return name.to_string();
+            }
+        }
+    }
+    target.split(':').next().unwrap_or(target).to_string()
+}
+
+/// Pane id of a pane-level target (`%N` or `session:window.pane`);
+/// `None` for session and window targets or panes not seen yet
+pub fn pane_of(target: &str) -> Option<String> {
+    if target.starts_with('%') {
+        return Some(target.to_string());
+    }
+    lookup_address(target).and_then(|a| a.pane_id)
+}
+
+/// Why a `tmux attach` client ended, from the message it prints on the way
+/// out (`[detached (from session x)]`, `[server exited]`, ...): "detached",
+/// "server-exited", "exited" (the session ended) or "terminated"
+pub fn attach_exit_reason(output: &[u8]) -> Option<&'static str> {
+    const MESSAGES: [(&str, &str); 5] = [
+        ("[detached", "detached"),
+        ("[server exited", "server-exited"),
+        ("[exited]", "exited"),
+        ("[lost tty]", "terminated"),
+        ("[terminated]", "terminated"),
+    ];
+    let text = String::from_utf8_lossy(output);
+    MESSAGES
+        .iter()
+        .filter_map(|(message, reason)| Some((text.rfind(message)?, *reason)))
+        .max_by_key(|(at, _)| *at)
+        .map(|(_, reason)| reason)
 }
 
 /// List all tmux sessions with full pane info (async, non-blocking)
 pub async fn list_sessions() -> Vec<TmuxSession> {
-    let format_str = "#{session_name}:#{window_index}:#{window_name}:#{pane_index}:#{pane_id}:#{pane_active}:#{pane_width}:#{pane_height}:#{pane_left}:#{pane_top}:#{pane_pid}:#{pane_current_command}:#{session_activity}:#{session_path}";
+    let mut fields = vec![
+        "#{session_name}",
+        "#{window_index}",
+        "#{window_name}",
+        "#{pane_index}",
+        "#{pane_id}",
+        "#{pane_active}",
+        "#{pane_width}",
+        "#{pane_height}",
+        "#{pane_left}",
+        "#{pane_top}",
+        "#{pane_pid}",
+        "#{pane_dead}",
+        "#{pane_dead_status}",
+        "#{pane_current_command}",
+        "#{session_group}",
+        "#{pane_title}",
+        "#{window_active}",
+        "#{window_width}",
+        "#{window_height}",
+        "#{window_id}",
+        "#{session_id}",
+        "#{pane_in_mode}",
+        "#{alternate_on}",
+        "#{scroll_position}",
+        "#{@muxtunnel_meta}",
+    ];
+    // Older tmux prints unknown formats literally, which would end up as the path
+    if supports(Feature::SessionFormats) {
+        fields.extend(["#{session_activity}", "#{session_path}"]);
+    }
+    let format_str = fields.join(SEP);
 
-    let (tmux_result, process_table) = tokio::join!(
-        Command::new("tmux")
-            .args(["list-panes", "-a", "-F", format_str])
-            .output(),
-        get_process_table()
-    );
+    let args = ["list-panes", "-a", "-F", &format_str];
+    let (tmux_result, process_table) = tokio::join!(run(&args), get_process_table());
 
-    let tmux_output = match tmux_result {
-        Ok(o) if o.status.success() => String::from_utf8_lossy(&o.stdout).to_string(),
-        _ => return vec![],
+    let Ok(tmux_output) = tmux_result else {
+        return vec![];
     };
 
     let mut sessions: HashMap<String, TmuxSession> = HashMap::new();
+    let mut addresses = AddressTable::new();
 
+    // The local copy of pane metadata only stands in for pane options
+    let pane_options = supports(Feature::PaneOptions);
+    let local_meta = if pane_options {
+        BTreeMap::new()
+    } else {
+        super::pane_meta::load_local()
+    };
     for line in tmux_output.lines() {
         if line.is_empty() {
             continue;
         }
 
-        let parts: Vec<&str> = line.splitn(14, ':').collect();
-        if parts.len() < 12 {
+        let parts: Vec<&str> = line.splitn(27, SEP).collect();
+        if parts.len() < 25 {
             continue;
         }
 
@@ -190,17 +839,42 @@ pub async fn list_sessions() -> Vec<TmuxSession> {
         let left: u32 = parts[8].parse().unwrap_or(0);
         let top: u32 = parts[9].parse().unwrap_or(0);
         let pid: u32 = parts[10].parse().unwrap_or(0);
-        let current_command = parts[11];
-        let session_activity: u64 = parts.get(12).and_then(|s| s.parse().ok()).unwrap_or(0);
-        // session_path may contain colons, so rejoin everything after field 13
-        let session_path = if parts.len() > 13 {
-            Some(parts[13..].join(":"))
-        } else {
-            None
+        let dead = parts[11] == "1";
+        let dead_status: Option<i32> = parts[12].parse().ok();
+        let current_command = parts[13];
+        let session_group = Some(parts[14].to_string()).filter(|g| !g.is_empty());
+        let title = parts[15].to_string();
+        // The session's size is that of its current window
+        let dimensions = match (parts[16], parts[17].parse(), parts[18].parse()) {
+            ("1", Ok(width), Ok(height)) => Some(SessionDimensions { width, height }),
+            _ => None,
         };
+        let window_id = parts[19].to_string();
+        let session_id = parts[20].to_string();
+        let in_mode = parts[21] == "1";
+        let alternate_on = parts[22] == "1";
+        let scroll_position: Option<u32> = parts[23].parse().ok();
+        let meta = super::pane_meta::resolve(parts[24], &pane_id, &local_meta);
+        let session_activity: u64 = parts.get(25).and_then(|s| s.parse().ok()).unwrap_or(0);
+        let session_path = parts.get(26).map(|p| p.to_string());
 
-        let process = get_effective_process_from_table(pid, current_command, &process_table);
+        let (process, process_pid) =
+            get_effective_process_from_table(pid, current_command, &process_table);
+        let usage = process_tree_usage(process_pid, &process_table);
         let target = format!("{}:{}.{}", session_name, window_index, pane_index);
+        let pane_address = Address {
+            session: session_name.clone(),
+            pane_id: Some(pane_id.clone()),
+        };
+        addresses.insert(target.clone(), pane_address.clone());
+        addresses.insert(pane_id.clone(), pane_address);
+        for id in [&window_id, &session_id] {
+            let address = Address {
+                session: session_name.clone(),
+                pane_id: None,
+            };
+            addresses.insert(id.clone(), address);
+        }
 
         let pane = TmuxPane {
             session_name: session_name.clone(),
@@ -208,6 +882,8 @@ pub async fn list_sessions() -> Vec<TmuxSession> {
             window_name: window_name.clone(),
             pane_index,
             pane_id,
+            window_id,
+            session_id: session_id.clone(),
             target,
             active,
             cols,
@@ -216,11 +892,24 @@ pub async fn list_sessions() -> Vec<TmuxSession> {
             top,
             pid,
             process,
+            dead,
+            dead_status,
+            title,
+            in_mode,
+            alternate_on,
+            scroll_position,
+            last_output: None,
+            output_rate: None,
+            cpu_percent: usage.map(|(cpu, _)| cpu),
+            rss_bytes: usage.map(|(_, rss)| rss),
+            meta,
             claude_session: None,
+            pair: None,
         };
 
         let session = sessions.entry(session_name.clone()).or_insert_with(|| TmuxSession {
             name: session_name,
+            id: session_id,
             windows: vec![],
             dimensions: None,
             activity: if session_activity > 0 {
@@ -229,7 +918,13 @@ pub async fn list_sessions() -> Vec<TmuxSession> {
                 None
             },
             path: session_path.filter(|p| !p.is_empty()),
+            inferred_group: None,
+            server: current_server(),
+            session_group,
         });
+        if dimensions.is_some() {
+            session.dimensions = dimensions;
+        }
 
         if let Some(window) = session.windows.iter_mut().find(|w| w.index == window_index) {
             window.panes.push(pane);
@@ -242,6 +937,15 @@ pub async fn list_sessions() -> Vec<TmuxSession> {
         }
     }
 
+    if !pane_options {
+        let live = addresses
+            .values()
+            .filter_map(|a| a.pane_id.clone())
+            .collect();
+        super::pane_meta::prune(&live);
+    }
+    ADDRESSES.lock().unwrap().insert(current_server(), addresses);
+
     // Sort sessions by name (stable order — HashMap iteration is non-deterministic)
     let mut result: Vec<TmuxSession> = sessions.into_values().collect();
     result.sort_by(|a, b| a.name.cmp(&b.name));
@@ -255,65 +959,122 @@ pub async fn list_sessions() -> Vec<TmuxSession> {
     result
 }
 
-/// Get dimensions of a session's current window
-pub async fn get_session_dimensions(session_name: &str) -> Option<SessionDimensions> {
-    let output = Command::new("tmux")
-        .args([
-            "display-message",
-            "-t",
-            session_name,
-            "-p",
-            "#{window_width}:#{window_height}",
-        ])
-        .output()
-        .await
-        .ok()?;
+/// Create session `name` grouped with `group` (an existing session or group
+/// name): it shares the same windows but keeps its own size and current window
+pub async fn create_grouped_session(group: &str, name: &str) -> Result<(), String> {
+    run(&["new-session", "-d", "-t", group, "-s", name]).await?;
+    apply_session_options(name).await;
+    Ok(())
+}
 
-    if !output.status.success() {
-        return None;
-    }
+/// Session options tmux only reads when it creates a pane, so a session's
+/// first pane only gets them if they're in effect before `new-session`
+const CREATION_OPTIONS: &[&str] = &["history-limit"];
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let trimmed = stdout.trim();
-    let mut parts = trimmed.splitn(2, ':');
-    let width: u32 = parts.next()?.parse().ok()?;
-    let height: u32 = parts.next()?.parse().ok()?;
-    Some(SessionDimensions { width, height })
+/// `tmux.sessionOptions` as tmux option values
+fn session_options() -> Vec<(String, String)> {
+    let options = super::settings::get_settings().settings.tmux.session_options;
+    options
+        .into_iter()
+        .map(|(option, value)| {
+            let value = match value {
+                serde_json::Value::String(s) => s,
+                serde_json::Value::Bool(b) => if b { "on" } else { "off" }.to_string(),
+                other => other.to_string(),
+            };
+            (option, value)
+        })
+        .collect()
 }
 
-/// Create a new tmux session (idempotent)
-pub async fn create_session(name: &str, cwd: &str) -> Result<(), String> {
-    // Check if session already exists
-    let check = Command::new("tmux")
-        .args(["has-session", "-t", name])
-        .output()
-        .await;
+/// `new_session` wrapped in one tmux invocation that sets the
+/// `CREATION_OPTIONS` among `tmux.sessionOptions` globally and then puts the
+/// server's values back, so the first pane is created with them
+async fn new_session_args(new_session: &[&str]) -> Vec<String> {
+    let mut set = Vec::new();
+    let mut restore = Vec::new();
+    for (option, value) in session_options() {
+        if !CREATION_OPTIONS.contains(&option.as_str()) {
+            continue;
+        }
+        let Ok(previous) = run(&["show-options", "-gv", &option]).await else {
+            continue;
+        };
+        let (option, value, previous) = (option.as_str(), value.as_str(), previous.trim());
+        set.extend(["set-option", "-g", option, value, ";"].map(String::from));
+        restore.extend([";", "set-option", "-g", option, previous].map(String::from));
+    }
+    set.extend(new_session.iter().map(|a| a.to_string()));
+    set.extend(restore);
+    set
+}
 
-    if let Ok(o) = check {
-        if o.status.success() {
-            return Ok(()); // Already exists
+/// Apply `tmux.sessionOptions` to a session MuxTunnel just created, for its
+/// later panes as well. A rejected option is logged rather than failing the
+/// creation.
+async fn apply_session_options(session: &str) {
+    for (option, value) in session_options() {
+        if let Err(e) = run(&["set-option", "-t", session, &option, &value]).await {
+            log::warn!("[tmux] Failed to apply {} to {}: {}", option, session, e);
         }
     }
+}
 
-    let output = Command::new("tmux")
-        .args(["new-session", "-d", "-s", name, "-c", cwd])
+/// Turn on the server's `focus-events` so focus changes of our clients reach
+/// panes that asked for them; once per server
+pub async fn enable_focus_events() {
+    static ENABLED: once_cell::sync::Lazy<
+        std::sync::Mutex<std::collections::HashSet<Option<String>>>,
+    > = once_cell::sync::Lazy::new(Default::default);
+    let server = current_server();
+    if ENABLED.lock().unwrap().contains(&server) {
+        return;
+    }
+    match run(&["set-option", "-s", "focus-events", "on"]).await {
+        Ok(_) => {
+            ENABLED.lock().unwrap().insert(server);
+        }
+        Err(e) => log::warn!("[tmux] Failed to enable focus-events: {}", e),
+    }
+}
+
+/// Whether a session (or the session of a window/pane target) exists
+pub async fn has_target(target: &str) -> bool {
+    run(&["has-session", "-t", target]).await.is_ok()
+}
+
+/// Create a new tmux session (idempotent)
+pub async fn create_session(name: &str, cwd: &str) -> Result<(), String> {
+    new_session(name, cwd, None).await.map(|_| ())
+}
+
+/// Create a detached session rooted in `cwd` whose first window runs
+/// `command` (the default shell when `None`). `false` when a session named
+/// `name` already exists; tmux refuses the duplicate itself, so there's no
+/// window between checking and creating.
+pub async fn new_session(name: &str, cwd: &str, command: Option<&str>) -> Result<bool, String> {
+    let mut args = vec!["new-session", "-d", "-s", name, "-c", cwd];
+    args.extend(command);
+    let output = tmux_command()
+        .args(new_session_args(&args).await)
         .output()
         .await
         .map_err(|e| format!("Failed to create session: {}", e))?;
 
+    let stderr = String::from_utf8_lossy(&output.stderr);
     if output.status.success() {
-        Ok(())
+        apply_session_options(name).await;
+        Ok(true)
+    } else if stderr.contains("duplicate session") {
+        Ok(false)
     } else {
-        Err(format!(
-            "tmux new-session failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ))
+        Err(format!("tmux new-session failed: {}", stderr))
     }
 }
 
 /// Kill a tmux session
 pub async fn kill_session(name: &str) -> Result<(), String> {
-    let output = Command::new("tmux")
+    let output = tmux_command()
         .args(["kill-session", "-t", name])
         .output()
         .await
@@ -329,9 +1090,130 @@ pub async fn kill_session(name: &str) -> Result<(), String> {
     }
 }
 
+/// Window (index, name) pairs of a session, in index order
+pub async fn list_windows(session: &str) -> Result<Vec<(u32, String)>, String> {
+    let stdout = run(&[
+        "list-windows",
+        "-t",
+        session,
+        "-F",
+        &["#{window_index}", "#{window_name}"].join(SEP),
+    ])
+    .await?;
+
+    let mut windows: Vec<(u32, String)> = stdout
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, SEP);
+            let index: u32 = parts.next()?.parse().ok()?;
+            Some((index, parts.next().unwrap_or("").to_string()))
+        })
+        .collect();
+    windows.sort_by_key(|(index, _)| *index);
+    Ok(windows)
+}
+
+/// Move a window to the position of another, shifting the windows in between.
+///
+/// Implemented as adjacent `swap-window`s so the relative order of every
+/// other window is preserved and gaps in the index sequence stay intact.
+pub async fn move_window(session: &str, from_index: u32, to_index: u32) -> Result<(), String> {
+    let indices: Vec<u32> = list_windows(session)
+        .await?
+        .into_iter()
+        .map(|(index, _)| index)
+        .collect();
+    let position = |index: u32| {
+        indices
+            .iter()
+            .position(|i| *i == index)
+            .ok_or_else(|| format!("Window not found: {}:{}", session, index))
+    };
+    let from = position(from_index)?;
+    let to = position(to_index)?;
+
+    let steps: Vec<(u32, u32)> = if from < to {
+        (from..to).map(|p| (indices[p], indices[p + 1])).collect()
+    } else {
+        (to + 1..=from)
+            .rev()
+            .map(|p| (indices[p], indices[p - 1]))
+            .collect()
+    };
+    for (a, b) in steps {
+        let source = format!("{}:{}", session, a);
+        let target = format!("{}:{}", session, b);
+        run(&["swap-window", "-d", "-s", &source, "-t", &target]).await?;
+    }
+    Ok(())
+}
+
+/// Kill a tmux window
+pub async fn kill_window(session: &str, index: u32) -> Result<(), String> {
+    let target = format!("{}:{}", session, index);
+    run(&["kill-window", "-t", &target]).await.map(|_| ())
+}
+
+/// Create a window at the end of a session, returning its `@window_id`
+pub async fn new_window(session: &str, name: &str, cwd: &str) -> Result<String, String> {
+    let target = format!("{}:", session);
+    let stdout = run(&[
+        "new-window", "-d", "-P", "-F", "#{window_id}", "-t", &target, "-n", name, "-c", cwd,
+    ])
+    .await?;
+    match stdout.trim() {
+        id if id.starts_with('@') => Ok(id.to_string()),
+        other => Err(format!("Unexpected new-window output: {}", other)),
+    }
+}
+
+/// `@window_id`s of a session's windows, in index order
+pub async fn list_window_ids(session: &str) -> Result<Vec<String>, String> {
+    let stdout = run(&["list-windows", "-t", session, "-F", "#{window_id}"]).await?;
+    Ok(stdout
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// `%pane_id`s of a window's panes, in index order
+pub async fn list_pane_ids(window: &str) -> Result<Vec<String>, String> {
+    let stdout = run(&["list-panes", "-t", window, "-F", "#{pane_id}"]).await?;
+    Ok(stdout
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Split a window, starting the new pane in `cwd`; `horizontal` puts it side by side
+pub async fn split_window(target: &str, cwd: &str, horizontal: bool) -> Result<(), String> {
+    let direction = if horizontal { "-h" } else { "-v" };
+    run(&["split-window", "-d", direction, "-t", target, "-c", cwd])
+        .await
+        .map(|_| ())
+}
+
+/// Run `list-panes -a` with the given formats, one row of fields per pane
+pub async fn list_pane_fields(formats: &[&str]) -> Result<Vec<Vec<String>>, String> {
+    let format = formats.join(SEP);
+    let stdout = run(&["list-panes", "-a", "-F", &format]).await?;
+    Ok(stdout
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.splitn(formats.len(), SEP).map(String::from).collect())
+        .collect())
+}
+
+/// Rename a tmux window
+pub async fn rename_window(target: &str, name: &str) -> Result<(), String> {
+    run(&["rename-window", "-t", target, name]).await.map(|_| ())
+}
+
 /// Kill a tmux pane
 pub async fn kill_pane(target: &str) -> Result<(), String> {
-    let output = Command::new("tmux")
+    let output = tmux_command()
         .args(["kill-pane", "-t", target])
         .output()
         .await
@@ -347,23 +1229,241 @@ pub async fn kill_pane(target: &str) -> Result<(), String> {
     }
 }
 
+/// Layout string of a window, suitable for `select-layout`
+pub async fn window_layout(target: &str) -> Result<String, String> {
+    let stdout = run(&["display-message", "-t", target, "-p", "#{window_layout}"]).await?;
+    Ok(stdout.trim().to_string())
+}
+
+/// Apply a layout to a window: a built-in preset (`even-horizontal`,
+/// `even-vertical`, `main-horizontal`, `main-vertical`, `tiled`) or a raw
+/// layout string as printed by `#{window_layout}`
+pub async fn select_layout(target: &str, layout: &str) -> Result<(), String> {
+    run(&["select-layout", "-t", target, layout])
+        .await
+        .map(|_| ())
+}
+
+/// Move a pane out into a new window of its own
+pub async fn break_pane(target: &str) -> Result<(), String> {
+    run(&["break-pane", "-s", target]).await.map(|_| ())
+}
+
+/// Move a pane into another window, splitting next to `target`
+pub async fn join_pane(source: &str, target: &str, horizontal: bool) -> Result<(), String> {
+    let direction = if horizontal { "-h" } else { "-v" };
+    run(&["join-pane", direction, "-s", source, "-t", target])
+        .await
+        .map(|_| ())
+}
+
+/// Swap the positions of two panes
+pub async fn swap_pane(source: &str, target: &str) -> Result<(), String> {
+    run(&["swap-pane", "-s", source, "-t", target])
+        .await
+        .map(|_| ())
+}
+
+/// Names of the clients (real terminals) attached to a session
+async fn attached_clients(session: &str) -> Vec<String> {
+    let format = ["#{client_control_mode}", "#{client_name}"].join(SEP);
+    match run(&["list-clients", "-t", session, "-F", &format]).await {
+        Ok(stdout) => stdout
+            .lines()
+            .filter_map(|l| l.split_once(SEP))
+            // Control clients (ours included) have no screen to draw on
+            .filter(|(control, _)| *control != "1")
+            .map(|(_, name)| name.to_string())
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// Run `command` in a popup over `session` on `client`'s screen
+/// (`display-popup -E`), closing when the command exits. `width` and
+/// `height` are cells or percentages such as "80%".
+pub async fn display_popup(
+    client: &str,
+    session: &str,
+    command: &str,
+    width: Option<&str>,
+    height: Option<&str>,
+    cwd: Option<&str>,
+) -> Result<(), String> {
+    require_feature(Feature::Popups)?;
+    let mut args = vec!["display-popup", "-E", "-c", client, "-t", session];
+    if let Some(width) = width {
+        args.extend(["-w", width]);
+    }
+    if let Some(height) = height {
+        args.extend(["-h", height]);
+    }
+    if let Some(cwd) = cwd {
+        args.extend(["-d", cwd]);
+    }
+    args.push(command);
+    run(&args).await.map(|_| ())
+}
+
+/// Clients attached to `session`, or to any session when `None`.
+/// `muxtunnel` is left false for the caller to fill in.
+pub async fn list_clients(session: Option<&str>) -> Result<Vec<TmuxClient>, String> {
+    let format = [
+        "#{client_name}",
+        "#{client_session}",
+        "#{client_tty}",
+        "#{client_pid}",
+        "#{client_width}",
+        "#{client_height}",
+        "#{client_activity}",
+        "#{client_readonly}",
+        "#{client_termname}",
+    ]
+    .join(SEP);
+    let mut args = vec!["list-clients", "-F", &format];
+    if let Some(session) = session {
+        args.extend(["-t", session]);
+    }
+    let stdout = run(&args).await?;
+    Ok(stdout
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.splitn(9, SEP).collect();
+            if parts.len() < 9 {
+                return None;
+            }
+            Some(TmuxClient {
+                name: parts[0].to_string(),
+                session: parts[1].to_string(),
+                tty: parts[2].to_string(),
+                pid: parts[3].parse().ok(),
+                width: parts[4].parse().unwrap_or(0),
+                height: parts[5].parse().unwrap_or(0),
+                activity: parts[6].parse().ok(),
+                read_only: parts[7] == "1",
+                termname: parts[8].to_string(),
+                muxtunnel: false,
+            })
+        })
+        .collect())
+}
+
+/// Size each client's current window wants the client's terminal to be,
+/// by client pid: the window plus tmux's status lines
+pub async fn client_window_sizes() -> Result<HashMap<u32, (u16, u16)>, String> {
+    let format = [
+        "#{client_pid}",
+        "#{window_width}",
+        "#{window_height}",
+        "#{status}",
+    ]
+    .join(SEP);
+    let stdout = run(&["list-clients", "-F", &format]).await?;
+    Ok(stdout
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.splitn(4, SEP).collect();
+            let [pid, width, height, status] = <[&str; 4]>::try_from(parts).ok()?;
+            let status_lines = match status {
+                "off" => 0,
+                "on" => 1,
+                lines => lines.parse().unwrap_or(1),
+            };
+            let height: u16 = height.parse().ok()?;
+            Some((pid.parse().ok()?, (width.parse().ok()?, height + status_lines)))
+        })
+        .collect())
+}
+
+/// Detach a client by name (see `TmuxClient::name`)
+pub async fn detach_client(client: &str) -> Result<(), String> {
+    run(&["detach-client", "-t", client]).await.map(|_| ())
+}
+
+/// Turn a window's `synchronize-panes` on or off, or toggle it when `enabled`
+/// is `None`; returns the new state
+pub async fn synchronize_panes(window: &str, enabled: Option<bool>) -> Result<bool, String> {
+    let enabled = match enabled {
+        Some(enabled) => enabled,
+        None => pane_option(window, "synchronize-panes").await? != "1",
+    };
+    let value = if enabled { "on" } else { "off" };
+    run(&["set-window-option", "-t", window, "synchronize-panes", value]).await?;
+    Ok(enabled)
+}
+
+/// Move a client to a session, window or pane (`switch-client`)
+pub async fn switch_client(client: &str, target: &str) -> Result<(), String> {
+    run(&["switch-client", "-c", client, "-t", target])
+        .await
+        .map(|_| ())
+}
+
+/// Show the `display-panes` overlay on every client attached to a session
+/// and return the numbering it displays
+pub async fn identify_panes(session: &str, duration_ms: u32) -> Result<Vec<PaneLabel>, String> {
+    let clients = attached_clients(session).await;
+    if clients.is_empty() {
+        return Err(format!("No terminal attached to session: {}", session));
+    }
+
+    let format = [
+        "#{session_name}",
+        "#{window_index}",
+        "#{pane_index}",
+        "#{pane_id}",
+    ]
+    .join(SEP);
+    let stdout = run(&["list-panes", "-t", session, "-F", &format]).await?;
+    let labels: Vec<PaneLabel> = stdout
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(4, SEP);
+            let session_name = parts.next()?;
+            let window_index = parts.next()?;
+            let index: u32 = parts.next()?.parse().ok()?;
+            let pane_id = parts.next()?.to_string();
+            Some(PaneLabel {
+                index,
+                pane_id,
+                target: format!("{}:{}.{}", session_name, window_index, index),
+            })
+        })
+        .collect();
+
+    let duration = duration_ms.to_string();
+    let mut args = vec!["display-panes", "-t", ""];
+    // Before 2.6 the overlay uses the display-panes-time option instead
+    if supports(Feature::DisplayPanesDuration) {
+        args.extend(["-d", duration.as_str()]);
+    }
+    for client in &clients {
+        args[2] = client;
+        run(&args).await?;
+    }
+
+    Ok(labels)
+}
+
 /// Send keys to a tmux pane (literal text + Enter)
 pub async fn send_keys_literal(target: &str, text: &str) -> Result<(), String> {
-    let output = Command::new("tmux")
+    let output = tmux_command()
         .args(["send-keys", "-t", target, "-l", text])
         .output()
         .await
         .map_err(|e| format!("Failed to send keys: {}", e))?;
 
     if !output.status.success() {
-        return Err(format!(
+        let message = format!(
             "tmux send-keys failed: {}",
             String::from_utf8_lossy(&output.stderr)
-        ));
+        );
+        super::diagnostics::record_error(current_server(), target, &message);
+        return Err(message);
     }
 
     // Send Enter
-    Command::new("tmux")
+    tmux_command()
         .args(["send-keys", "-t", target, "Enter"])
         .output()
         .await
@@ -372,9 +1472,174 @@ pub async fn send_keys_literal(target: &str, text: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Send keys to a tmux pane without the implicit Enter of `send_keys_literal`.
+///
+/// With `literal`, each entry is typed as text; otherwise entries are tmux key
+/// names such as `C-d`, `Up`, `F5` or `Escape`.
+pub async fn send_keys(
+    target: &str,
+    keys: &[String],
+    literal: bool,
+    append_enter: bool,
+) -> Result<(), String> {
+    if !keys.is_empty() {
+        let mut args = vec!["send-keys", "-t", target];
+        if literal {
+            args.push("-l");
+        }
+        args.extend(keys.iter().map(String::as_str));
+        run(&args).await?;
+    }
+    if append_enter {
+        run(&["send-keys", "-t", target, "Enter"]).await?;
+    }
+    Ok(())
+}
+
+/// List paste buffers, most recent first
+pub async fn list_buffers() -> Result<Vec<TmuxBuffer>, String> {
+    let format = [
+        "#{buffer_name}",
+        "#{buffer_size}",
+        "#{buffer_created}",
+        "#{buffer_sample}",
+    ]
+    .join(SEP);
+    let stdout = run(&["list-buffers", "-F", &format]).await?;
+    Ok(stdout
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(4, SEP);
+            Some(TmuxBuffer {
+                name: parts.next()?.to_string(),
+                size: parts.next()?.parse().unwrap_or(0),
+                created: parts.next()?.parse().unwrap_or(0),
+                sample: parts.next().unwrap_or("").to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Full contents of a paste buffer
+pub async fn show_buffer(name: &str) -> Result<String, String> {
+    run(&["show-buffer", "-b", name]).await
+}
+
+/// Store text in a paste buffer; a new automatically named buffer when `name` is `None`.
+/// The text goes through `load-buffer`'s stdin, since tmux caps the length of
+/// a command's arguments.
+pub async fn set_buffer(name: Option<&str>, text: &str) -> Result<(), String> {
+    let mut args = vec!["load-buffer"];
+    if let Some(name) = name {
+        args.extend(["-b", name]);
+    }
+    args.push("-");
+    run_with_input(&args, text.as_bytes()).await.map(|_| ())
+}
+
+/// Remove a paste buffer
+pub async fn delete_buffer(name: &str) -> Result<(), String> {
+    run(&["delete-buffer", "-b", name]).await.map(|_| ())
+}
+
+/// Paste a buffer (the most recent when `name` is `None`) into a pane
+pub async fn paste_buffer(target: &str, name: Option<&str>, bracketed: bool) -> Result<(), String> {
+    let mut args = vec!["paste-buffer", "-t", target];
+    if let Some(name) = name {
+        args.extend(["-b", name]);
+    }
+    if bracketed {
+        args.push("-p");
+    }
+    run(&args).await.map(|_| ())
+}
+
+/// `%pane_id` of any pane target
+pub async fn pane_id(target: &str) -> Result<String, String> {
+    let stdout = run(&["display-message", "-t", target, "-p", "#{pane_id}"]).await?;
+    match stdout.trim() {
+        id if id.starts_with('%') => Ok(id.to_string()),
+        _ => Err(format!("Pane not found: {}", target)),
+    }
+}
+
+/// Pipe a pane's output to a shell command (`pipe-pane -O`), unless it
+/// already has a pipe; `None` closes the pane's pipe
+pub async fn pipe_pane(target: &str, command: Option<&str>) -> Result<(), String> {
+    match command {
+        Some(command) => run(&["pipe-pane", "-O", "-o", "-t", target, command]).await,
+        None => run(&["pipe-pane", "-t", target]).await,
+    }
+    .map(|_| ())
+}
+
+/// Value of a pane's user option (`@name`), empty when unset
+pub async fn pane_option(pane: &str, option: &str) -> Result<String, String> {
+    let format = format!("#{{{}}}", option);
+    let stdout = run(&["display-message", "-t", pane, "-p", &format]).await?;
+    Ok(stdout.trim_end_matches('\n').to_string())
+}
+
+/// Set a pane-scoped option, or unset it when `value` is `None`
+pub async fn set_pane_option(pane: &str, option: &str, value: Option<&str>) -> Result<(), String> {
+    match value {
+        Some(value) => run(&["set-option", "-p", "-t", pane, option, value]).await,
+        None => run(&["set-option", "-pu", "-t", pane, option]).await,
+    }
+    .map(|_| ())
+}
+
+/// Set a pane's title (`select-pane -T`)
+pub async fn set_pane_title(target: &str, title: &str) -> Result<(), String> {
+    run(&["select-pane", "-t", target, "-T", title])
+        .await
+        .map(|_| ())
+}
+
+/// Restart a pane's process with `respawn-pane -k`, optionally with a new command
+pub async fn respawn_pane(target: &str, command: Option<&str>) -> Result<(), String> {
+    let mut args = vec!["respawn-pane", "-k", "-t", target];
+    if let Some(command) = command.filter(|c| !c.is_empty()) {
+        args.push(command);
+    }
+    run(&args).await.map(|_| ())
+}
+
+/// What `copy_mode` does to a pane
+#[derive(Debug, Clone, Copy)]
+pub enum CopyMode {
+    Enter,
+    Exit,
+    /// Enter copy mode if needed and scroll by this many lines (negative is
+    /// up, into history); scrolling back to the bottom leaves copy mode
+    Scroll(i32),
+}
+
+pub async fn copy_mode(target: &str, action: CopyMode) -> Result<(), String> {
+    match action {
+        CopyMode::Enter => run(&["copy-mode", "-t", target]).await,
+        CopyMode::Exit => {
+            // `send-keys -X` fails outside a mode, and there's nothing to exit
+            let in_mode = run(&["display-message", "-t", target, "-p", "#{pane_in_mode}"]).await?;
+            if in_mode.trim() == "0" {
+                return Ok(());
+            }
+            run(&["send-keys", "-t", target, "-X", "cancel"]).await
+        }
+        CopyMode::Scroll(0) => return Ok(()),
+        CopyMode::Scroll(lines) => {
+            run(&["copy-mode", "-e", "-t", target]).await?;
+            let count = lines.unsigned_abs().to_string();
+            let command = if lines < 0 { "scroll-up" } else { "scroll-down" };
+            run(&["send-keys", "-t", target, "-X", "-N", &count, command]).await
+        }
+    }
+    .map(|_| ())
+}
+
 /// Send Ctrl+C to a tmux pane
 pub async fn send_interrupt(target: &str) -> Result<(), String> {
-    let output = Command::new("tmux")
+    let output = tmux_command()
         .args(["send-keys", "-t", target, "C-c"])
         .output()
         .await
@@ -392,29 +1657,44 @@ pub async fn send_interrupt(target: &str) -> Result<(), String> {
 
 /// Get pane info for a specific target
 pub async fn get_pane_info(target: &str) -> Option<TmuxPane> {
-    let format_str = "#{session_name}:#{window_index}:#{window_name}:#{pane_index}:#{pane_id}:#{pane_active}:#{pane_width}:#{pane_height}:#{pane_left}:#{pane_top}:#{pane_pid}:#{pane_current_command}";
+    let format_str = [
+        "#{session_name}",
+        "#{window_index}",
+        "#{window_name}",
+        "#{pane_index}",
+        "#{pane_id}",
+        "#{pane_active}",
+        "#{pane_width}",
+        "#{pane_height}",
+        "#{pane_left}",
+        "#{pane_top}",
+        "#{pane_pid}",
+        "#{pane_dead}",
+        "#{pane_dead_status}",
+        "#{pane_current_command}",
+        "#{pane_title}",
+        "#{window_id}",
+        "#{session_id}",
+        "#{pane_in_mode}",
+        "#{alternate_on}",
+        "#{scroll_position}",
+    ]
+    .join(SEP);
 
-    let output = Command::new("tmux")
-        .args(["display-message", "-t", target, "-p", format_str])
-        .output()
+    let stdout = run(&["display-message", "-t", target, "-p", &format_str])
         .await
         .ok()?;
-
-    if !output.status.success() {
-        return None;
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
     let line = stdout.trim();
-    let parts: Vec<&str> = line.splitn(12, ':').collect();
-    if parts.len() < 12 {
+    let parts: Vec<&str> = line.splitn(20, SEP).collect();
+    if parts.len() < 20 {
         return None;
     }
 
     let pid: u32 = parts[10].parse().unwrap_or(0);
     // For single pane lookup, do a quick process table fetch
     let table = get_process_table().await;
-    let process = get_effective_process_from_table(pid, parts[11], &table);
+    let (process, process_pid) = get_effective_process_from_table(pid, parts[13], &table);
+    let usage = process_tree_usage(process_pid, &table);
 
     Some(TmuxPane {
         session_name: parts[0].to_string(),
@@ -422,6 +1702,8 @@ pub async fn get_pane_info(target: &str) -> Option<TmuxPane> {
         window_name: parts[2].to_string(),
         pane_index: parts[3].parse().unwrap_or(0),
         pane_id: parts[4].to_string(),
+        window_id: parts[15].to_string(),
+        session_id: parts[16].to_string(),
         target: target.to_string(),
         active: parts[5] == "1",
         cols: parts[6].parse().unwrap_or(80),
@@ -430,29 +1712,72 @@ pub async fn get_pane_info(target: &str) -> Option<TmuxPane> {
         top: parts[9].parse().unwrap_or(0),
         pid,
         process,
+        dead: parts[11] == "1",
+        dead_status: parts[12].parse().ok(),
+        title: parts[14].to_string(),
+        in_mode: parts[17] == "1",
+        alternate_on: parts[18] == "1",
+        scroll_position: parts[19].parse().ok(),
+        last_output: None,
+        output_rate: None,
+        cpu_percent: usage.map(|(cpu, _)| cpu),
+        rss_bytes: usage.map(|(_, rss)| rss),
+        meta: None,
         claude_session: None,
+        pair: None,
     })
 }
 
+/// Session of `target`, its shell's PID and the PID/name of the process
+/// running in the foreground (the shell itself when idle)
+pub async fn get_pane_processes(target: &str) -> Result<(String, u32, u32, String), String> {
+    let stdout = run(&[
+        "display-message",
+        "-t",
+        target,
+        "-p",
+        &["#{session_name}", "#{pane_pid}", "#{pane_current_command}"].join(SEP),
+    ])
+    .await?;
+    let parts: Vec<&str> = stdout.trim_end().splitn(3, SEP).collect();
+    let [session, pid, command] = parts[..] else {
+        return Err(format!("Pane not found: {}", target));
+    };
+    let pid: u32 = pid
+        .parse()
+        .map_err(|_| format!("Pane has no process: {}", target))?;
+    let table = get_process_table().await;
+    let (process, process_pid) = get_effective_process_from_table(pid, command, &table);
+    Ok((session.to_string(), pid, process_pid, process))
+}
+
+/// `show-environment` for a session (or the global environment when `None`).
+/// Variables tmux removes from new panes (`-VAR`) map to `None`.
+pub async fn show_environment(
+    session: Option<&str>,
+) -> Result<BTreeMap<String, Option<String>>, String> {
+    let args = match session {
+        Some(session) => vec!["show-environment", "-t", session],
+        None => vec!["show-environment", "-g"],
+    };
+    let stdout = run(&args).await?;
+    Ok(stdout
+        .lines()
+        .filter_map(|line| match line.strip_prefix('-') {
+            Some(name) => Some((name.to_string(), None)),
+            None => line
+                .split_once('=')
+                .map(|(k, v)| (k.to_string(), Some(v.to_string()))),
+        })
+        .collect())
+}
+
 /// Get pane's current working directory
 pub async fn get_pane_cwd(target: &str) -> Option<String> {
-    let output = Command::new("tmux")
-        .args([
-            "display-message",
-            "-t",
-            target,
-            "-p",
-            "#{pane_current_path}",
-        ])
-        .output()
+    let stdout = run(&["display-message", "-t", target, "-p", "#{pane_current_path}"])
         .await
         .ok()?;
-
-    if !output.status.success() {
-        return None;
-    }
-
-    let cwd = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let cwd = stdout.trim().to_string();
     if cwd.is_empty() {
         None
     } else {
@@ -460,31 +1785,113 @@ pub async fn get_pane_cwd(target: &str) -> Option<String> {
     }
 }
 
-/// Capture last N lines of a pane with escape sequences
-pub async fn capture_pane_with_escapes(target: &str, start_line: i32) -> Option<String> {
-    let output = Command::new("tmux")
-        .args([
-            "capture-pane",
-            "-t",
-            target,
-            "-p",
-            "-e",
-            "-S",
-            &start_line.to_string(),
-        ])
-        .output()
-        .await
-        .ok()?;
+/// A range of a pane's scrollback
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaneCapture {
+    pub content: String,
+    /// Lines of history above the visible screen (how far back `start_line` can go)
+    pub history_size: u32,
+}
 
-    if output.status.success() {
-        Some(String::from_utf8_lossy(&output.stdout).to_string())
-    } else {
-        None
+/// A tmux paste buffer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TmuxBuffer {
+    pub name: String,
+    pub size: u64,
+    /// Unix seconds
+    pub created: u64,
+    /// Start of the contents, as tmux abbreviates it
+    pub sample: String,
+}
+
+/// Capture lines `start_line..=end_line` of a pane. Negative lines are history,
+/// 0 is the first visible line; `end_line` defaults to the bottom of the screen.
+pub async fn capture_pane_range(
+    target: &str,
+    start_line: i32,
+    end_line: Option<i32>,
+    with_escapes: bool,
+) -> Result<String, String> {
+    let start = start_line.to_string();
+    let end = end_line.map(|e| e.to_string());
+    let mut args = vec!["capture-pane", "-t", target, "-p", "-S", &start];
+    if let Some(end) = end.as_deref() {
+        args.extend(["-E", end]);
+    }
+    if with_escapes {
+        args.push("-e");
+    }
+    run(&args).await
+}
+
+/// Capture a pane's whole history and screen with wrapped lines joined
+pub async fn capture_all(target: &str, with_escapes: bool) -> Result<String, String> {
+    let mut args = vec!["capture-pane", "-t", target, "-p", "-J", "-S", "-"];
+    if with_escapes {
+        args.push("-e");
     }
+    run(&args).await
+}
+
+/// Number of scrollback lines above the visible screen
+pub async fn history_size(target: &str) -> Result<u32, String> {
+    let stdout = run(&["display-message", "-t", target, "-p", "#{history_size}"]).await?;
+    Ok(stdout.trim().parse().unwrap_or(0))
+}
+
+/// Capture a range of scrollback along with the pane's history size
+pub async fn capture_pane(
+    target: &str,
+    start_line: i32,
+    end_line: Option<i32>,
+    with_escapes: bool,
+) -> Result<PaneCapture, String> {
+    let (content, history_size) = tokio::join!(
+        capture_pane_range(target, start_line, end_line, with_escapes),
+        history_size(target)
+    );
+    Ok(PaneCapture {
+        content: content?,
+        history_size: history_size?,
+    })
 }
 
-/// Check if a pane is showing Claude's orange thinking indicator
+/// Capture a pane's entire history plus the visible screen, without escapes
+pub async fn capture_history(target: &str) -> Result<PaneCapture, String> {
+    let capture_args = ["capture-pane", "-t", target, "-p", "-S", "-"];
+    let (content, history_size) = tokio::join!(run(&capture_args), history_size(target));
+    Ok(PaneCapture {
+        content: content?,
+        history_size: history_size?,
+    })
+}
+
+/// Capture last N lines of a pane with escape sequences
+pub async fn capture_pane_with_escapes(target: &str, start_line: i32) -> Option<String> {
+    capture_pane_range(target, start_line, None, true).await.ok()
+}
+
+/// Check if a pane is showing Claude's orange thinking indicator. A PTY
+/// attached to the pane answers from its screen; otherwise the pane's last
+/// lines are captured.
 pub async fn is_pane_processing(target: &str) -> bool {
+    let pty = pane_of(target).and_then(|pane| {
+        super::screen::for_pane(&super::pty_manager::session_key(
+            current_server().as_deref(),
+            &pane,
+        ))
+    });
+    if let Some(key) = pty {
+        if let Some(pane) = super::multiplexer::current().pane_info(target).await {
+            let region = super::screen::Region::pane_tail(&pane, 10);
+            if let Some(thinking) = super::screen::is_thinking(&key, region) {
+                return thinking;
+            }
+        }
+    }
+
     let output = match capture_pane_with_escapes(target, -10).await {
         Some(o) => o,
         None => return false,
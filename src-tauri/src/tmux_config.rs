@@ -0,0 +1,92 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// A tmux setting that conflicts with how MuxTunnel drives tmux
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigIssue {
+    pub option: String,
+    pub value: String,
+    pub problem: String,
+    pub suggestion: String,
+}
+
+fn issue(option: &str, value: &str, problem: &str, suggestion: &str) -> ConfigIssue {
+    ConfigIssue {
+        option: option.to_string(),
+        value: value.to_string(),
+        problem: problem.to_string(),
+        suggestion: suggestion.to_string(),
+    }
+}
+
+/// Check global tmux options against MuxTunnel's assumptions
+pub fn check_options(options: &HashMap<String, String>) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+
+    if let Some(value) = options.get("aggressive-resize") {
+        if value == "off" {
+            issues.push(issue(
+                "aggressive-resize",
+                value,
+                "Windows are sized to the smallest attached client, so MuxTunnel views may be clipped",
+                "setw -g aggressive-resize on",
+            ));
+        }
+    }
+
+    if let Some(value) = options.get("escape-time") {
+        if value.parse::<u32>().map(|ms| ms > 50).unwrap_or(false) {
+            issues.push(issue(
+                "escape-time",
+                value,
+                "Escape key presses are delayed in editors and TUIs",
+                "set -sg escape-time 10",
+            ));
+        }
+    }
+
+    if let Some(value) = options.get("status") {
+        if value != "off" {
+            issues.push(issue(
+                "status",
+                value,
+                "The status line takes rows from every attached MuxTunnel view",
+                "set -g status off",
+            ));
+        }
+    }
+
+    if let Some(value) = options.get("default-terminal") {
+        if !value.ends_with("256color") {
+            issues.push(issue(
+                "default-terminal",
+                value,
+                "Programs inside tmux fall back to a limited color palette",
+                "set -g default-terminal tmux-256color",
+            ));
+        }
+    }
+
+    issues
+}
+
+/// Everything `health_check` reports about the tmux setup
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    pub tmux: super::tmux::ServerInfo,
+    pub capabilities: super::capabilities::Capabilities,
+    pub config_issues: Vec<ConfigIssue>,
+}
+
+pub async fn health_check() -> HealthReport {
+    let capabilities = super::capabilities::refresh();
+    let tmux = super::tmux::refresh_server_info().await;
+    let config_issues = check_options(&tmux.options);
+    HealthReport {
+        tmux,
+        capabilities,
+        config_issues,
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::{HashMap, VecDeque};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin};
+use tokio::sync::oneshot;
+
+use super::features::{self, Feature};
+
+/// How long to wait for the client to attach
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A command without a reply by then is treated as a hung connection
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// After a failed connect, spawn per call for this long before trying again
+const RETRY_AFTER: Duration = Duration::from_secs(10);
+
+/// Commands whose exact output matters and which control mode would alter
+/// (it always terminates the last line)
+const PASSTHROUGH: &[&str] = &["show-buffer"];
+
+type Reply = Result<String, String>;
+
+struct Connection {
+    stdin: tokio::sync::Mutex<ChildStdin>,
+    /// Replies owed, in the order commands were written
+    pending: Mutex<VecDeque<oneshot::Sender<Reply>>>,
+    alive: AtomicBool,
+    child: Mutex<Child>,
+    pid: Option<u32>,
+}
+
+impl Connection {
+    fn close(&self) {
+        self.alive.store(false, Ordering::SeqCst);
+        let _ = self.child.lock().unwrap().start_kill();
+        for reply in self.pending.lock().unwrap().drain(..) {
+            let _ = reply.send(Err("tmux control connection closed".to_string()));
+        }
+    }
+}
+
+enum Slot {
+    Connected(Arc<Connection>),
+    Failed(Instant),
+}
+
+/// Keyed by registered server name (`None` = default server)
+static CONNECTIONS: once_cell::sync::Lazy<Mutex<HashMap<Option<String>, Slot>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Serializes connecting so concurrent first calls share one client
+static CONNECTING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Quote an argument for tmux's command parser: double quotes, with
+/// everything the parser would otherwise expand or choke on escaped
+pub fn quote(arg: &str) -> String {
+    let mut out = String::with_capacity(arg.len() + 2);
+    out.push('"');
+    for (i, c) in arg.chars().enumerate() {
+        match c {
+            '"' | '\\' | '$' => {
+                out.push('\\');
+                out.push(c);
+            }
+            // A leading `~` is expanded to $HOME even inside quotes
+            '~' if i == 0 => out.push_str("\\~"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 || c as u32 == 0x7f => {
+                out.push_str(&format!("\\{:03o}", c as u32));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Read replies off the client's stdout until it exits. Blocks for commands
+/// from other clients or the initial attach have flags 0 and are skipped,
+/// except that the first one signals `ready`.
+async fn read_replies(
+    conn: Arc<Connection>,
+    stdout: tokio::process::ChildStdout,
+    ready: oneshot::Sender<()>,
+) {
+    let _task = super::diagnostics::track("tmux-control");
+    let mut ready = Some(ready);
+    let mut reader = BufReader::new(stdout);
+    let mut raw = Vec::new();
+    // Guard (`<time> <number> <flags>`) and lines of the block being read
+    let mut block: Option<(String, Vec<String>)> = None;
+    loop {
+        raw.clear();
+        match reader.read_until(b'\n', &mut raw).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        let line = String::from_utf8_lossy(&raw);
+        let line = line.trim_end_matches(['\n', '\r']);
+
+        if let Some((guard, lines)) = block.as_mut() {
+            let end = line
+                .strip_prefix("%end ")
+                .map(|g| (g, true))
+                .or_else(|| line.strip_prefix("%error ").map(|g| (g, false)));
+            match end {
+                Some((g, ok)) if g == guard.as_str() => {
+                    let ours = guard.rsplit(' ').next() == Some("1");
+                    let mut text = lines.join("\n");
+                    if !lines.is_empty() {
+                        text.push('\n');
+                    }
+                    block = None;
+                    if ours {
+                        if let Some(reply) = conn.pending.lock().unwrap().pop_front() {
+                            let _ = reply.send(if ok { Ok(text) } else { Err(text) });
+                        }
+                    } else if let Some(ready) = ready.take() {
+                        let _ = ready.send(());
+                    }
+                }
+                _ => lines.push(line.to_string()),
+            }
+        } else if let Some(guard) = line.strip_prefix("%begin ") {
+            block = Some((guard.to_string(), Vec::new()));
+        } else if line == "%exit" || line.starts_with("%exit ") {
+            break;
+        }
+        // Other notifications (%output, %session-changed, ...) are ignored
+    }
+    log::info!("[tmux-control] Connection closed");
+    conn.close();
+}
+
+/// Start a control client on the current server and wait for it to attach
+async fn connect() -> Result<Arc<Connection>, String> {
+    super::tmux::require_feature(super::tmux::Feature::ControlFlags)?;
+    let mut child = super::tmux::tmux_command()
+        .args(["-C", "attach-session"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start tmux -C: {}", e))?;
+    let stdin = child.stdin.take().ok_or("tmux -C has no stdin")?;
+    let stdout = child.stdout.take().ok_or("tmux -C has no stdout")?;
+    let pid = child.id();
+    let conn = Arc::new(Connection {
+        stdin: tokio::sync::Mutex::new(stdin),
+        pending: Mutex::new(VecDeque::new()),
+        alive: AtomicBool::new(true),
+        child: Mutex::new(child),
+        pid,
+    });
+
+    let (ready_tx, ready_rx) = oneshot::channel();
+    tauri::async_runtime::spawn(read_replies(conn.clone(), stdout, ready_tx));
+    match tokio::time::timeout(CONNECT_TIMEOUT, ready_rx).await {
+        Ok(Ok(())) => {}
+        // No session to attach to, or the server isn't running
+        Ok(Err(_)) => return Err("tmux -C exited before attaching".to_string()),
+        Err(_) => {
+            conn.close();
+            return Err("tmux -C did not attach".to_string());
+        }
+    }
+
+    // Pane output is streamed over the PTYs; don't have tmux send it here too
+    if let Err(e) = send(&conn, &["refresh-client", "-f", "no-output"]).await {
+        log::warn!("[tmux-control] Failed to disable output: {}", e);
+    }
+    log::info!("[tmux-control] Connected (pid {:?})", conn.pid);
+    Ok(conn)
+}
+
+/// The live connection for the current server, connecting if needed.
+/// `None` while a recent attempt failed.
+async fn connection() -> Option<Arc<Connection>> {
+    let server = super::tmux::current_server();
+    let usable = |connections: &HashMap<Option<String>, Slot>| match connections.get(&server) {
+        Some(Slot::Connected(conn)) if conn.alive.load(Ordering::SeqCst) => {
+            Some(Some(conn.clone()))
+        }
+        Some(Slot::Failed(at)) if at.elapsed() < RETRY_AFTER => Some(None),
+        _ => None,
+    };
+    if let Some(conn) = usable(&CONNECTIONS.lock().unwrap()) {
+        return conn;
+    }
+
+    let _connecting = CONNECTING.lock().await;
+    if let Some(conn) = usable(&CONNECTIONS.lock().unwrap()) {
+        return conn;
+    }
+    let slot = match connect().await {
+        Ok(conn) => Slot::Connected(conn),
+        Err(e) => {
+            log::debug!("[tmux-control] Not connected: {}", e);
+            Slot::Failed(Instant::now())
+        }
+    };
+    let conn = match &slot {
+        Slot::Connected(conn) => Some(conn.clone()),
+        Slot::Failed(_) => None,
+    };
+    CONNECTIONS.lock().unwrap().insert(server, slot);
+    conn
+}
+
+async fn send(conn: &Connection, args: &[&str]) -> Reply {
+    let mut line = args.iter().map(|a| quote(a)).collect::<Vec<_>>().join(" ");
+    line.push('\n');
+    let (tx, rx) = oneshot::channel();
+    {
+        // Queue the reply and write under the same lock so replies stay in order
+        let mut stdin = conn.stdin.lock().await;
+        conn.pending.lock().unwrap().push_back(tx);
+        let written = async {
+            stdin.write_all(line.as_bytes()).await?;
+            stdin.flush().await
+        };
+        if let Err(e) = written.await {
+            conn.close();
+            return Err(format!("tmux control connection failed: {}", e));
+        }
+    }
+    match tokio::time::timeout(COMMAND_TIMEOUT, rx).await {
+        Ok(Ok(reply)) => reply,
+        Ok(Err(_)) => Err("tmux control connection closed".to_string()),
+        Err(_) => {
+            log::warn!("[tmux-control] No reply to {}; reconnecting", args[0]);
+            conn.close();
+            Err("tmux control command timed out".to_string())
+        }
+    }
+}
+
+/// Run a tmux command over the control connection of the current server.
+/// `None` means it wasn't sent (feature off or no connection) and the
+/// caller should spawn `tmux` instead; errors carry tmux's message.
+pub async fn run(args: &[&str]) -> Option<Reply> {
+    if !features::enabled(Feature::ControlMode) {
+        return None;
+    }
+    let subcommand = args.first()?;
+    if PASSTHROUGH.contains(subcommand) {
+        return None;
+    }
+    let conn = connection().await?;
+    Some(send(&conn, args).await)
+}
+
+/// PIDs of the control clients, so they can be told apart from real terminals
+pub fn client_pids() -> Vec<u32> {
+    CONNECTIONS
+        .lock()
+        .unwrap()
+        .values()
+        .filter_map(|slot| match slot {
+            Slot::Connected(conn) if conn.alive.load(Ordering::SeqCst) => conn.pid,
+            _ => None,
+        })
+        .collect()
+}
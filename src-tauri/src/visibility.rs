@@ -0,0 +1,115 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::pty_manager::session_key;
+
+static STATE: once_cell::sync::Lazy<Mutex<Visibility>> = once_cell::sync::Lazy::new(|| {
+    Mutex::new(Visibility {
+        visible: HashSet::new(),
+        app_focused: true,
+    })
+});
+
+/// Wakes pollers sleeping on a slower pace than the new report needs
+static CHANGED: once_cell::sync::Lazy<tokio::sync::Notify> =
+    once_cell::sync::Lazy::new(tokio::sync::Notify::new);
+
+struct Visibility {
+    /// `session_key`s of the panes on screen, by pane id where known
+    visible: HashSet<String>,
+    app_focused: bool,
+}
+
+/// How often to poll, per `advanced.*PollMs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pace {
+    /// Something involved is on screen
+    Visible,
+    Hidden,
+    /// The app is in the background; overrides the others
+    Unfocused,
+}
+
+fn key_of(server: Option<&str>, target: &str) -> String {
+    let pane = super::tmux::pane_of(target).unwrap_or_else(|| target.to_string());
+    session_key(server, &pane)
+}
+
+/// Record which panes of the current server the frontend shows and whether
+/// the app is focused; replaces the previous report
+pub fn report(visible_targets: &[String], app_focused: bool) {
+    let server = super::tmux::current_server();
+    let visible = visible_targets
+        .iter()
+        .map(|t| key_of(server.as_deref(), t))
+        .collect();
+    let mut state = STATE.lock().unwrap();
+    state.visible = visible;
+    state.app_focused = app_focused;
+    CHANGED.notify_waiters();
+}
+
+/// The native window gained or lost focus
+pub fn set_app_focused(focused: bool) {
+    STATE.lock().unwrap().app_focused = focused;
+    CHANGED.notify_waiters();
+}
+
+/// Pace for polling a server's panes: visible when any of them is on screen
+pub fn pace(server: Option<&str>) -> Pace {
+    let state = STATE.lock().unwrap();
+    if !state.app_focused {
+        return Pace::Unfocused;
+    }
+    let visible = state.visible.iter().any(|key| match server {
+        Some(server) => key.starts_with(&format!("{}/", server)),
+        None => !key.contains('/'),
+    });
+    if visible {
+        Pace::Visible
+    } else {
+        Pace::Hidden
+    }
+}
+
+/// Polling interval for a pace
+pub fn interval(pace: Pace) -> Duration {
+    let advanced = super::settings::get_settings().settings.advanced;
+    let ms = match pace {
+        Pace::Visible => advanced.visible_poll_ms,
+        Pace::Hidden => advanced.hidden_poll_ms,
+        Pace::Unfocused => advanced.unfocused_poll_ms,
+    };
+    Duration::from_millis(ms.max(100))
+}
+
+/// Interval for warming things that aren't on screen by definition
+pub fn background() -> Duration {
+    let focused = STATE.lock().unwrap().app_focused;
+    interval(if focused {
+        Pace::Hidden
+    } else {
+        Pace::Unfocused
+    })
+}
+
+/// Fastest interval any poller needs right now, for loops that pace
+/// servers individually
+pub fn tick() -> Duration {
+    let state = STATE.lock().unwrap();
+    let pace = if !state.app_focused {
+        Pace::Unfocused
+    } else if state.visible.is_empty() {
+        Pace::Hidden
+    } else {
+        Pace::Visible
+    };
+    drop(state);
+    interval(pace)
+}
+
+/// Sleep for `duration`, or until the visibility report changes
+pub async fn sleep(duration: Duration) {
+    let _ = tokio::time::timeout(duration, CHANGED.notified()).await;
+}
@@ -0,0 +1,173 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use super::pty_manager::session_key;
+
+/// Unterminated output kept per watch while waiting for the line to end
+const MAX_PARTIAL: usize = 4096;
+
+/// Screen lines evaluated when a watch starts, so it has a value right away
+const INITIAL_LINES: i32 = 100;
+
+static WATCHES: once_cell::sync::Lazy<Mutex<Watches>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(Watches::default()));
+
+#[derive(Default)]
+struct Watches {
+    next_id: u64,
+    by_id: BTreeMap<u64, Watch>,
+}
+
+struct Watch {
+    target: String,
+    /// `session_key` of the pane id, matching what the pipe and PTY readers feed
+    key: String,
+    regex: regex::Regex,
+    group: usize,
+    value: Option<String>,
+    /// Output after the last line break
+    partial: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchInfo {
+    pub id: u64,
+    pub target: String,
+    pub regex: String,
+    pub group: usize,
+    /// Latest extracted value; `None` until a line matches
+    pub value: Option<String>,
+}
+
+impl Watch {
+    fn info(&self, id: u64) -> WatchInfo {
+        WatchInfo {
+            id,
+            target: self.target.clone(),
+            regex: self.regex.as_str().to_string(),
+            group: self.group,
+            value: self.value.clone(),
+        }
+    }
+
+    /// The value in the last matching line
+    fn extract<'a>(&self, lines: impl DoubleEndedIterator<Item = &'a str>) -> Option<String> {
+        lines.rev().find_map(|line| {
+            let caps = self.regex.captures(line)?;
+            caps.get(self.group).map(|m| m.as_str().to_string())
+        })
+    }
+
+    /// Evaluate the complete lines (ended by `\n` or a progress bar's `\r`)
+    /// of new output; the new value if it changed
+    fn feed(&mut self, text: &str) -> Option<String> {
+        self.partial.push_str(text);
+        let end = self.partial.rfind(['\n', '\r'])?;
+        let complete: String = self.partial.drain(..=end).collect();
+        if self.partial.len() > MAX_PARTIAL {
+            self.partial.clear();
+        }
+        let value = self.extract(complete.split(['\n', '\r']))?;
+        if self.value.as_ref() == Some(&value) {
+            return None;
+        }
+        self.value = Some(value.clone());
+        Some(value)
+    }
+}
+
+/// Evaluate a pane's new output against the watches on it, emitting
+/// `watch-value` for every value that changed
+pub fn observe(key: &str, data: &[u8]) {
+    let mut changed = Vec::new();
+    {
+        let mut watches = WATCHES.lock().unwrap();
+        if !watches.by_id.values().any(|w| w.key == key) {
+            return;
+        }
+        let text = String::from_utf8_lossy(data);
+        let text = super::links::strip_ansi(&text);
+        for (id, watch) in watches.by_id.iter_mut().filter(|(_, w)| w.key == key) {
+            if let Some(value) = watch.feed(&text) {
+                changed.push((*id, watch.target.clone(), value));
+            }
+        }
+    }
+    for (watch_id, target, value) in changed {
+        super::events::emit(super::events::BackendEvent::WatchValue {
+            watch_id,
+            target,
+            value,
+        });
+    }
+}
+
+/// The pane's pipe closed (the pane exited); drop its watches
+pub fn closed(key: &str) {
+    WATCHES.lock().unwrap().by_id.retain(|_, w| w.key != key);
+}
+
+/// Extract capture group `group` of `regex` from every line `target` prints,
+/// streaming changes as `watch-value` events. On tmux the pane is piped so
+/// the watch works while it isn't attached.
+pub async fn watch(target: &str, regex: &str, group: usize) -> Result<WatchInfo, String> {
+    let regex = regex::Regex::new(regex).map_err(|e| format!("Invalid regex: {}", e))?;
+    if group >= regex.captures_len() {
+        return Err(format!(
+            "Regex has {} capture groups",
+            regex.captures_len() - 1
+        ));
+    }
+    let server = super::tmux::current_server();
+    let (key, screen) = if super::multiplexer::current().name() == "tmux" {
+        let pane_id = super::tmux::pane_id(target).await?;
+        let key = session_key(server.as_deref(), &pane_id);
+        if let Err(e) = super::pane_pipe::ensure(&pane_id, &key).await {
+            log::warn!("[watches] Not piping {}: {}", target, e);
+        }
+        let screen = super::tmux::capture_pane_range(target, -INITIAL_LINES, None, false)
+            .await
+            .unwrap_or_default();
+        (key, screen)
+    } else {
+        (session_key(server.as_deref(), target), String::new())
+    };
+
+    let mut watch = Watch {
+        target: target.to_string(),
+        key,
+        regex,
+        group,
+        value: None,
+        partial: String::new(),
+    };
+    watch.value = watch.extract(screen.lines());
+
+    let mut watches = WATCHES.lock().unwrap();
+    watches.next_id += 1;
+    let id = watches.next_id;
+    let info = watch.info(id);
+    watches.by_id.insert(id, watch);
+    Ok(info)
+}
+
+pub fn unwatch(id: u64) -> Result<(), String> {
+    match WATCHES.lock().unwrap().by_id.remove(&id) {
+        Some(_) => Ok(()),
+        None => Err(format!("Watch not found: {}", id)),
+    }
+}
+
+/// Active watches, on `target` only if given
+pub fn list(target: Option<&str>) -> Vec<WatchInfo> {
+    WATCHES
+        .lock()
+        .unwrap()
+        .by_id
+        .iter()
+        .filter(|(_, w)| target.map_or(true, |t| w.target == t))
+        .map(|(id, w)| w.info(*id))
+        .collect()
+}
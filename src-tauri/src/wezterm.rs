@@ -0,0 +1,317 @@
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tokio::process::Command;
+
+use super::capabilities::Tool;
+use super::multiplexer::{AttachCommand, Multiplexer, WEZTERM_SERVER};
+use super::templates::{resolve, TemplateWindow};
+use super::tmux::{TmuxPane, TmuxSession, TmuxWindow};
+
+/// The WezTerm multiplexer through `wezterm cli`. Workspaces are listed as
+/// sessions and tabs as windows; pane targets are WezTerm pane ids.
+pub struct WezTerm;
+
+/// A row of `wezterm cli list --format json`
+#[derive(Debug, Deserialize)]
+struct ListedPane {
+    window_id: u64,
+    tab_id: u64,
+    pane_id: u64,
+    workspace: String,
+    size: PaneSize,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    tab_title: String,
+    /// `file://host/path`
+    #[serde(default)]
+    cwd: String,
+    #[serde(default)]
+    is_active: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaneSize {
+    rows: u32,
+    cols: u32,
+}
+
+/// Path to the wezterm binary: `multiplexer.weztermBinary`, then `$PATH` and
+/// common install prefixes
+pub fn wezterm_binary() -> String {
+    if let Some(binary) = super::settings::get_settings()
+        .settings
+        .multiplexer
+        .wezterm_binary
+        .filter(|b| !b.is_empty())
+    {
+        return super::settings::expand_tilde(&binary)
+            .to_string_lossy()
+            .to_string();
+    }
+    super::capabilities::find_program("wezterm")
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|| "wezterm".to_string())
+}
+
+/// Run `wezterm cli <args>`, returning stdout or wezterm's stderr as the error
+async fn cli(args: &[&str]) -> Result<String, String> {
+    super::capabilities::require(Tool::Wezterm)?;
+    let subcommand = args.first().copied().unwrap_or("cli");
+    let output = Command::new(wezterm_binary())
+        .arg("cli")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run wezterm cli {}: {}", subcommand, e))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(format!(
+            "wezterm cli {} failed: {}",
+            subcommand,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+async fn list_panes() -> Result<Vec<ListedPane>, String> {
+    let stdout = cli(&["list", "--format", "json"]).await?;
+    serde_json::from_str(&stdout).map_err(|e| format!("Failed to parse wezterm cli list: {}", e))
+}
+
+/// Local path of a `file://host/path` cwd
+fn cwd_path(cwd: &str) -> Option<String> {
+    let rest = cwd.strip_prefix("file://")?;
+    let path = &rest[rest.find('/')?..];
+    Some(path.to_string()).filter(|p| !p.is_empty())
+}
+
+fn to_pane(pane: &ListedPane, pane_index: u32) -> TmuxPane {
+    TmuxPane {
+        session_name: pane.workspace.clone(),
+        window_index: pane.tab_id as u32,
+        window_name: pane.tab_title.clone(),
+        pane_index,
+        pane_id: pane.pane_id.to_string(),
+        window_id: pane.window_id.to_string(),
+        session_id: pane.workspace.clone(),
+        target: pane.pane_id.to_string(),
+        active: pane.is_active,
+        cols: pane.size.cols,
+        rows: pane.size.rows,
+        left: 0,
+        top: 0,
+        pid: 0,
+        process: String::new(),
+        dead: false,
+        dead_status: None,
+        title: pane.title.clone(),
+        in_mode: false,
+        alternate_on: false,
+        scroll_position: None,
+        last_output: None,
+        output_rate: None,
+        cpu_percent: None,
+        rss_bytes: None,
+        meta: None,
+        claude_session: None,
+        pair: None,
+    }
+}
+
+fn to_sessions(panes: &[ListedPane]) -> Vec<TmuxSession> {
+    // workspace → tab id → panes
+    let mut workspaces: BTreeMap<&str, BTreeMap<u64, Vec<&ListedPane>>> = BTreeMap::new();
+    for pane in panes {
+        workspaces
+            .entry(&pane.workspace)
+            .or_default()
+            .entry(pane.tab_id)
+            .or_default()
+            .push(pane);
+    }
+    workspaces
+        .into_iter()
+        .map(|(workspace, tabs)| {
+            let path = tabs.values().flatten().find_map(|p| cwd_path(&p.cwd));
+            let windows = tabs
+                .into_iter()
+                .map(|(tab_id, panes)| TmuxWindow {
+                    index: tab_id as u32,
+                    name: panes
+                        .iter()
+                        .map(|p| p.tab_title.as_str())
+                        .find(|t| !t.is_empty())
+                        .or_else(|| panes.first().map(|p| p.title.as_str()))
+                        .unwrap_or_default()
+                        .to_string(),
+                    panes: panes
+                        .iter()
+                        .enumerate()
+                        .map(|(i, p)| to_pane(p, i as u32))
+                        .collect(),
+                })
+                .collect();
+            TmuxSession {
+                name: workspace.to_string(),
+                id: workspace.to_string(),
+                windows,
+                dimensions: None,
+                activity: None,
+                path,
+                inferred_group: None,
+                server: Some(WEZTERM_SERVER.to_string()),
+                session_group: None,
+            }
+        })
+        .collect()
+}
+
+impl Multiplexer for WezTerm {
+    fn name(&self) -> &'static str {
+        "wezterm"
+    }
+
+    fn tool(&self) -> Option<Tool> {
+        Some(Tool::Wezterm)
+    }
+
+    fn list_sessions(&self) -> BoxFuture<'_, Vec<TmuxSession>> {
+        async {
+            match list_panes().await {
+                Ok(panes) => to_sessions(&panes),
+                Err(e) => {
+                    log::debug!("[wezterm] {}", e);
+                    vec![]
+                }
+            }
+        }
+        .boxed()
+    }
+
+    /// A new window in workspace `name`, unless the workspace has panes already
+    fn create_session<'a>(
+        &'a self,
+        name: &'a str,
+        cwd: &'a str,
+        command: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<bool, String>> {
+        async move {
+            if list_panes().await?.iter().any(|p| p.workspace == name) {
+                return Ok(false);
+            }
+            let cwd = super::settings::expand_tilde(cwd);
+            let cwd = cwd.to_string_lossy();
+            let mut args = vec!["spawn", "--new-window", "--workspace", name, "--cwd", &cwd];
+            if let Some(command) = command {
+                args.extend(["--", "sh", "-c", command]);
+            }
+            cli(&args).await?;
+            Ok(true)
+        }
+        .boxed()
+    }
+
+    /// A new tab in the window of workspace `session`; `layout` is ignored
+    fn add_window<'a>(
+        &'a self,
+        session: &'a str,
+        window: &'a TemplateWindow,
+        project: &'a Path,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        async move {
+            let window_id = list_panes()
+                .await?
+                .iter()
+                .find(|p| p.workspace == session)
+                .map(|p| p.window_id.to_string())
+                .ok_or_else(|| format!("Session not found: {}", session))?;
+            let cwd = resolve(project, window.cwd.as_deref());
+            let first_cwd = resolve(&cwd, window.panes.first().and_then(|p| p.cwd.as_deref()));
+            let first_cwd = first_cwd.to_string_lossy();
+            let spawn = ["spawn", "--window-id", &window_id, "--cwd", &first_cwd];
+            let mut pane_id = cli(&spawn).await?.trim().to_string();
+            cli(&["set-tab-title", "--pane-id", &pane_id, &window.name]).await?;
+            for (i, pane) in window.panes.iter().enumerate() {
+                if i > 0 {
+                    let side = if pane.horizontal {
+                        "--right"
+                    } else {
+                        "--bottom"
+                    };
+                    let pane_cwd = resolve(&cwd, pane.cwd.as_deref());
+                    let pane_cwd = pane_cwd.to_string_lossy();
+                    let split = [
+                        "split-pane",
+                        "--pane-id",
+                        &pane_id,
+                        side,
+                        "--cwd",
+                        &pane_cwd,
+                    ];
+                    pane_id = cli(&split).await?.trim().to_string();
+                }
+                if let Some(command) = pane.command.as_deref().filter(|c| !c.is_empty()) {
+                    self.send_input(&pane_id, command).await?;
+                }
+            }
+            Ok(())
+        }
+        .boxed()
+    }
+
+    /// Kill every pane of workspace `name`
+    fn kill_session<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<(), String>> {
+        async move {
+            let panes: Vec<String> = list_panes()
+                .await?
+                .iter()
+                .filter(|p| p.workspace == name)
+                .map(|p| p.pane_id.to_string())
+                .collect();
+            if panes.is_empty() {
+                return Err(format!("Session not found: {}", name));
+            }
+            for pane in panes {
+                cli(&["kill-pane", "--pane-id", &pane]).await?;
+            }
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn send_input<'a>(
+        &'a self,
+        target: &'a str,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        async move {
+            let text = format!("{}\r", text);
+            cli(&["send-text", "--pane-id", target, "--no-paste", &text])
+                .await
+                .map(|_| ())
+        }
+        .boxed()
+    }
+
+    fn pane_info<'a>(&'a self, target: &'a str) -> BoxFuture<'a, Option<TmuxPane>> {
+        async move {
+            let panes = list_panes().await.ok()?;
+            to_sessions(&panes)
+                .into_iter()
+                .flat_map(|s| s.windows)
+                .flat_map(|w| w.panes)
+                .find(|p| p.target == target)
+        }
+        .boxed()
+    }
+
+    fn attach_command(&self, _target: &str, _read_only: bool) -> Result<AttachCommand, String> {
+        Err("WezTerm panes can't be attached from MuxTunnel; open them in WezTerm".to_string())
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// Shown when the format renders empty (nothing focused yet)
+const FALLBACK: &str = "MuxTunnel";
+
+static APP_HANDLE: once_cell::sync::OnceCell<AppHandle> = once_cell::sync::OnceCell::new();
+
+static STATE: once_cell::sync::Lazy<Mutex<TitleState>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(TitleState::default()));
+
+#[derive(Default)]
+struct TitleState {
+    /// Window label → pane it last reported focused
+    focus: HashMap<String, Focus>,
+    /// Claude sessions waiting for the user
+    attention: HashSet<String>,
+    /// Window label → title last set, to skip redundant updates
+    applied: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+struct Focus {
+    target: String,
+    session: String,
+    window: String,
+    process: String,
+}
+
+pub fn init(app_handle: AppHandle) {
+    let _ = APP_HANDLE.set(app_handle);
+}
+
+/// Fill in `window.titleFormat`: `{session}`, `{window}`, `{process}` and
+/// `{target}` describe the focused pane, `{attention}` is ` (N)` while N
+/// Claude sessions need attention and empty otherwise
+fn render(format: &str, focus: Option<&Focus>, attention: usize) -> String {
+    let field = |f: fn(&Focus) -> &str| focus.map(f).unwrap_or("");
+    let attention = if attention > 0 {
+        format!(" ({})", attention)
+    } else {
+        String::new()
+    };
+    let title = format
+        .replace("{session}", field(|f| &f.session))
+        .replace("{window}", field(|f| &f.window))
+        .replace("{process}", field(|f| &f.process))
+        .replace("{target}", field(|f| &f.target))
+        .replace("{attention}", &attention);
+    let title = title.trim();
+    if focus.is_none() || title.is_empty() {
+        format!("{}{}", FALLBACK, attention)
+    } else {
+        title.to_string()
+    }
+}
+
+/// Re-render every window's title; no-op when `window.titleFormat` is empty
+pub fn refresh() {
+    let Some(app) = APP_HANDLE.get() else {
+        return;
+    };
+    let format = super::settings::get_settings().settings.window.title_format;
+    if format.is_empty() {
+        return;
+    }
+    let mut state = STATE.lock().unwrap();
+    for (label, window) in app.webview_windows() {
+        let title = render(&format, state.focus.get(&label), state.attention.len());
+        if state.applied.get(&label) == Some(&title) {
+            continue;
+        }
+        if let Err(e) = window.set_title(&title) {
+            log::debug!("[window-title] Failed to set title of {}: {}", label, e);
+            continue;
+        }
+        state.applied.insert(label, title);
+    }
+}
+
+/// The pane focused in window `label` changed (or blurred, with `None`)
+pub async fn focus(label: &str, target: Option<&str>) {
+    let pane = match target {
+        Some(target) => super::tmux::get_pane_info(target).await,
+        None => None,
+    };
+    {
+        let mut state = STATE.lock().unwrap();
+        match pane {
+            Some(pane) => {
+                state.focus.insert(
+                    label.to_string(),
+                    Focus {
+                        target: pane.target,
+                        session: pane.session_name,
+                        window: pane.window_name,
+                        process: pane.process,
+                    },
+                );
+            }
+            None => {
+                state.focus.remove(label);
+            }
+        }
+    }
+    refresh();
+}
+
+/// A Claude session started or stopped waiting for the user
+pub fn set_attention(session_id: &str, notified: bool) {
+    let changed = {
+        let mut state = STATE.lock().unwrap();
+        if notified {
+            state.attention.insert(session_id.to_string())
+        } else {
+            state.attention.remove(session_id)
+        }
+    };
+    if changed {
+        refresh();
+    }
+}
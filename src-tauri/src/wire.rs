@@ -0,0 +1,350 @@
+use serde::Serialize;
+use tauri::ipc::{InvokeResponseBody, Response};
+
+/// How a command's result is sent to the frontend, negotiated with the
+/// command's `encoding` argument
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// serde JSON, as every other command returns
+    Json,
+    /// MessagePack bytes (an `ArrayBuffer` on the JS side); much cheaper to
+    /// produce for large listings
+    MsgPack,
+}
+
+impl Encoding {
+    pub fn parse(encoding: Option<&str>) -> Result<Encoding, String> {
+        match encoding {
+            None | Some("json") => Ok(Encoding::Json),
+            Some("msgpack") => Ok(Encoding::MsgPack),
+            Some(other) => Err(format!("Unknown encoding: {}", other)),
+        }
+    }
+}
+
+/// Serialize a command result in the requested encoding
+pub fn respond<T: Serialize>(value: &T, encoding: Encoding) -> Result<Response, String> {
+    let body = match encoding {
+        Encoding::Json => InvokeResponseBody::Json(
+            serde_json::to_string(value).map_err(|e| format!("Failed to serialize: {}", e))?,
+        ),
+        Encoding::MsgPack => InvokeResponseBody::Raw(to_msgpack(value)?),
+    };
+    Ok(Response::new(body))
+}
+
+/// How PTY output reaches terminal views, per `terminal.dataTransport`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DataTransport {
+    /// `data` messages with the bytes as a JSON array of numbers
+    Json,
+    /// `data-base64` messages; about a third larger than the output
+    Base64,
+    /// The bytes themselves as an `ArrayBuffer`; other messages stay JSON
+    Raw,
+}
+
+impl DataTransport {
+    pub const ALL: [DataTransport; 3] =
+        [DataTransport::Json, DataTransport::Base64, DataTransport::Raw];
+
+    /// The configured transport; unknown values fall back to JSON
+    pub fn current() -> DataTransport {
+        match super::settings::get_settings()
+            .settings
+            .terminal
+            .data_transport
+            .as_str()
+        {
+            "base64" => DataTransport::Base64,
+            "raw" => DataTransport::Raw,
+            _ => DataTransport::Json,
+        }
+    }
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard, padded base64
+pub fn base64(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Length of `data` without a trailing UTF-8 sequence cut short, which the
+/// next read should complete. Bytes that can't start such a sequence
+/// (binary output, invalid UTF-8) are never held back.
+pub fn utf8_complete_len(data: &[u8]) -> usize {
+    let Some(start) = data
+        .iter()
+        .rev()
+        .take(4)
+        .position(|b| b & 0xc0 != 0x80)
+        .map(|back| data.len() - 1 - back)
+    else {
+        return data.len();
+    };
+    let needed = match data[start] {
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => return data.len(),
+    };
+    if data.len() - start < needed {
+        start
+    } else {
+        data.len()
+    }
+}
+
+/// How much of the output read so far to send: up to any character still
+/// being read, or all of it once the stream has ended
+pub fn flush_len(pending: &[u8], ended: bool) -> usize {
+    if ended {
+        pending.len()
+    } else {
+        utf8_complete_len(pending)
+    }
+}
+
+/// Compression a terminal view accepts for large `data` messages, negotiated
+/// with `pty_connect`'s `compression` argument
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    /// Output smaller than this is sent uncompressed
+    pub threshold: usize,
+}
+
+impl Compression {
+    /// Only "lz4" (an LZ4 block after its little-endian u32 length, as
+    /// `lz4_flex::compress_prepend_size` writes it) is supported; the
+    /// threshold is `terminal.compressionThreshold`
+    pub fn negotiate(requested: Option<&str>) -> Result<Option<Compression>, String> {
+        match requested {
+            None | Some("none") => Ok(None),
+            Some("lz4") => Ok(Some(Compression {
+                threshold: super::settings::get_settings()
+                    .settings
+                    .terminal
+                    .compression_threshold,
+            })),
+            Some(other) => Err(format!("Unsupported compression: {}", other)),
+        }
+    }
+
+    /// `data` compressed, when it is over the threshold and shrinks
+    pub fn compress(&self, data: &[u8]) -> Option<Vec<u8>> {
+        if data.len() < self.threshold {
+            return None;
+        }
+        let compressed = lz4_flex::compress_prepend_size(data);
+        (compressed.len() < data.len()).then_some(compressed)
+    }
+}
+
+/// Cost of sending output in one transport, from `benchmark`
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransportBenchmark {
+    pub transport: DataTransport,
+    pub compressed: bool,
+    /// Bytes handed to the webview per message
+    pub payload_bytes: usize,
+    /// Mean time to encode one message
+    pub encode_micros: f64,
+    /// Output bytes encoded per second
+    pub throughput: f64,
+}
+
+/// Encode `bytes` of colored terminal output as a PTY `data` message in
+/// each transport, with and without compression. Covers the backend's side
+/// only; decoding in the webview is measured by the frontend.
+pub fn benchmark(bytes: usize) -> Result<Vec<TransportBenchmark>, String> {
+    const ROUNDS: u32 = 20;
+    let line = b"\x1b[1;34mdrwxr-xr-x\x1b[0m  12 user  staff   384 Oct 16 09:41 \x1b[32msrc\x1b[0m\r\n";
+    let sample: Vec<u8> = line.iter().copied().cycle().take(bytes.max(1)).collect();
+
+    let lz4 = Compression { threshold: 0 };
+    DataTransport::ALL
+        .iter()
+        .flat_map(|&transport| [(transport, None), (transport, Some(lz4))])
+        .map(|(transport, compression)| {
+            let started = std::time::Instant::now();
+            let mut payload_bytes = 0;
+            for _ in 0..ROUNDS {
+                let message = super::pty_manager::PtyMessage::Data {
+                    data: sample.clone(),
+                };
+                let body = super::pty_manager::encode(message, transport, compression)
+                    .map_err(|e| format!("Failed to serialize: {}", e))?;
+                payload_bytes = match body {
+                    InvokeResponseBody::Json(json) => json.len(),
+                    InvokeResponseBody::Raw(raw) => raw.len(),
+                };
+            }
+            let elapsed = started.elapsed().as_secs_f64() / ROUNDS as f64;
+            Ok(TransportBenchmark {
+                transport,
+                compressed: compression.is_some(),
+                payload_bytes,
+                encode_micros: elapsed * 1e6,
+                throughput: sample.len() as f64 / elapsed.max(1e-9),
+            })
+        })
+        .collect()
+}
+
+pub fn to_msgpack<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    // Named so structs become maps, matching the JSON encoding
+    rmp_serde::to_vec_named(value).map_err(|e| format!("Failed to serialize: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Pane {
+        pane_id: String,
+        pid: Option<u32>,
+        offset: i64,
+        ratio: f64,
+        state: State,
+        tags: Vec<String>,
+    }
+
+    #[derive(serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    enum State {
+        Idle,
+        Busy(u32),
+    }
+
+    /// Decode MessagePack into the JSON value the same data encodes to
+    fn decode(bytes: &[u8]) -> serde_json::Value {
+        rmp_serde::from_slice(bytes).unwrap()
+    }
+
+    #[test]
+    fn msgpack_round_trips_like_json() {
+        let panes = vec![
+            Pane {
+                pane_id: "%1".into(),
+                pid: Some(4242),
+                offset: -70000,
+                ratio: 0.5,
+                state: State::Idle,
+                tags: vec!["claude".into()],
+            },
+            Pane {
+                pane_id: "%2".into(),
+                pid: None,
+                offset: u32::MAX as i64 + 1,
+                ratio: -1.25,
+                state: State::Busy(3),
+                tags: Vec::new(),
+            },
+        ];
+        let bytes = to_msgpack(&panes).unwrap();
+        assert_eq!(decode(&bytes), serde_json::to_value(&panes).unwrap());
+    }
+
+    #[test]
+    fn msgpack_handles_wide_lengths() {
+        let long = "x".repeat(u16::MAX as usize + 1);
+        let many: Vec<u8> = (0..=255).cycle().take(70000).collect();
+        let value = (long, many);
+        let bytes = to_msgpack(&value).unwrap();
+        assert_eq!(decode(&bytes), serde_json::to_value(&value).unwrap());
+    }
+
+    #[test]
+    fn lz4_compresses_only_large_output_that_shrinks() {
+        let compression = Compression { threshold: 64 };
+        assert_eq!(compression.compress(&[b'a'; 32]), None);
+        let noise: Vec<u8> = (0..256u32).map(|i| (i * 7919 % 251) as u8).collect();
+        assert_eq!(compression.compress(&noise), None);
+
+        let output = b"\x1b[32msrc\x1b[0m\r\n".repeat(500);
+        let compressed = compression.compress(&output).unwrap();
+        assert!(compressed.len() < output.len());
+        assert_eq!(
+            lz4_flex::decompress_size_prepended(&compressed).unwrap(),
+            output
+        );
+    }
+
+    /// Every way of splitting `text` into two reads
+    fn check_splits(text: &str) {
+        let bytes = text.as_bytes();
+        for at in 0..=bytes.len() {
+            let (first, rest) = bytes.split_at(at);
+            let split = format!("{:?} split at {}", text, at);
+            let complete = utf8_complete_len(first);
+            assert!(std::str::from_utf8(&first[..complete]).is_ok(), "{}", split);
+            assert!(first.len() - complete < 4);
+
+            let mut second = first[complete..].to_vec();
+            second.extend_from_slice(rest);
+            assert_eq!(utf8_complete_len(&second), second.len(), "{}", split);
+            let rejoined = [&first[..complete], &second[..]].concat();
+            assert_eq!(rejoined, bytes, "{}", split);
+        }
+    }
+
+    #[test]
+    fn utf8_splits_wait_for_the_rest_of_the_character() {
+        check_splits("ab\u{e9}");
+        check_splits("ab\u{20ac}");
+        check_splits("ab\u{1f600}");
+        check_splits("\u{e9}\u{20ac}\u{1f600}x\u{1f600}");
+        assert_eq!(utf8_complete_len(&[b'a', 0xf0, 0x9f, 0x98]), 1);
+        assert_eq!(utf8_complete_len(&[b'a', 0xe2, 0x82]), 1);
+        assert_eq!(utf8_complete_len(&[b'a', 0xc3]), 1);
+        assert_eq!(utf8_complete_len(b""), 0);
+    }
+
+    #[test]
+    fn invalid_bytes_are_not_held_back() {
+        // Bytes that never start a multi-byte character
+        for lead in [0x80, 0xbf, 0xc0, 0xc1, 0xf5, 0xff] {
+            assert_eq!(utf8_complete_len(&[b'a', lead]), 2, "{:#x}", lead);
+        }
+        // More continuation bytes than any character has
+        assert_eq!(utf8_complete_len(&[0xe2, 0x80, 0x80, 0x80, 0x80]), 5);
+        // A lead byte followed by something else is sent with it
+        assert_eq!(utf8_complete_len(&[0xf0, b'x']), 2);
+        assert_eq!(utf8_complete_len(&[0xe2, 0x82, 0x1b]), 3);
+
+        // A truncated lead held from one read goes out with the next
+        let binary = [0x00, 0x13, 0xf0];
+        let complete = utf8_complete_len(&binary);
+        assert_eq!(complete, 2);
+        let next = [&binary[complete..], &[0x00, 0x01][..]].concat();
+        assert_eq!(utf8_complete_len(&next), next.len());
+    }
+
+    #[test]
+    fn end_of_stream_flushes_a_partial_character() {
+        let pending = [b'o', b'k', 0xe2, 0x82];
+        assert_eq!(flush_len(&pending, false), 2);
+        assert_eq!(flush_len(&pending[2..], false), 0);
+        assert_eq!(flush_len(&pending, true), 4);
+        assert_eq!(flush_len(&pending[2..], true), 2);
+    }
+}
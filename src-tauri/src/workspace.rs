@@ -0,0 +1,298 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use tokio::process::Command;
+
+use super::hooks::{Phase, Scope};
+
+/// Shells don't count as a "running command" worth restarting
+const SHELLS: &[&str] = &[
+    "zsh", "bash", "sh", "fish", "tcsh", "csh", "-zsh", "-bash", "-sh",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+    pub created_at: u64,
+    pub sessions: Vec<SessionSnapshot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSnapshot {
+    pub name: String,
+    pub path: Option<String>,
+    pub windows: Vec<WindowSnapshot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowSnapshot {
+    pub index: u32,
+    pub name: String,
+    /// `#{window_layout}`, re-applied once all panes exist
+    pub layout: String,
+    pub panes: Vec<PaneSnapshot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaneSnapshot {
+    pub index: u32,
+    pub cwd: String,
+    /// Command line of the program running in the pane's shell, if any
+    pub command: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreSummary {
+    pub restored: Vec<String>,
+    /// Sessions left alone because one with the same name already exists
+    pub skipped: Vec<String>,
+}
+
+fn snapshots_dir() -> PathBuf {
+    super::paths::data_dir().join("snapshots")
+}
+
+fn snapshot_file(name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() || name.contains('/') || name.starts_with('.') {
+        return Err(format!("Invalid snapshot name: {}", name));
+    }
+    Ok(snapshots_dir().join(format!("{}.json", name)))
+}
+
+/// pid → (ppid, full command line)
+async fn process_args() -> HashMap<u32, (u32, String)> {
+    let output = match Command::new("ps")
+        .args(["-eo", "pid=,ppid=,args="])
+        .output()
+        .await
+    {
+        Ok(o) => o,
+        Err(_) => return HashMap::new(),
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let pid = parts.next()?.parse().ok()?;
+            let ppid = parts.next()?.parse().ok()?;
+            Some((pid, (ppid, parts.collect::<Vec<_>>().join(" "))))
+        })
+        .collect()
+}
+
+fn is_shell(args: &str) -> bool {
+    let program = args.split_whitespace().next().unwrap_or("");
+    let name = program.rsplit('/').next().unwrap_or(program);
+    SHELLS.contains(&name)
+}
+
+/// Command line of the program in the foreground of a pane: the pane's own
+/// process when it was started with a command, otherwise the first child of
+/// its shell. `None` for a shell at its prompt.
+fn pane_command(
+    pane_pid: u32,
+    current_command: &str,
+    table: &HashMap<u32, (u32, String)>,
+) -> Option<String> {
+    if SHELLS.contains(&current_command) {
+        return None;
+    }
+    let (_, own) = table.get(&pane_pid)?;
+    if !is_shell(own) {
+        return Some(own.clone());
+    }
+    table
+        .iter()
+        .filter(|(_, (ppid, _))| *ppid == pane_pid)
+        .min_by_key(|(pid, _)| **pid)
+        .map(|(_, (_, args))| args.clone())
+}
+
+/// Capture every session's windows, panes, layouts, cwds and running commands
+pub async fn capture() -> Result<Snapshot, String> {
+    let (rows, table) = tokio::join!(
+        super::tmux::list_pane_fields(&[
+            "#{session_name}",
+            "#{session_path}",
+            "#{window_index}",
+            "#{window_name}",
+            "#{window_layout}",
+            "#{pane_index}",
+            "#{pane_pid}",
+            "#{pane_current_command}",
+            "#{pane_current_path}",
+        ]),
+        process_args()
+    );
+
+    let mut sessions: BTreeMap<String, SessionSnapshot> = BTreeMap::new();
+    for row in rows? {
+        let [session, path, window_index, window_name, layout, pane_index, pid, current, cwd] =
+            <[String; 9]>::try_from(row).map_err(|_| "Unexpected list-panes output")?;
+        let session = sessions
+            .entry(session.clone())
+            .or_insert_with(|| SessionSnapshot {
+                name: session,
+                path: Some(path).filter(|p| !p.is_empty()),
+                windows: vec![],
+            });
+
+        let window_index: u32 = window_index.parse().unwrap_or(0);
+        if !session.windows.iter().any(|w| w.index == window_index) {
+            session.windows.push(WindowSnapshot {
+                index: window_index,
+                name: window_name,
+                layout,
+                panes: vec![],
+            });
+        }
+        let window = session
+            .windows
+            .iter_mut()
+            .find(|w| w.index == window_index)
+            .expect("window was just inserted");
+        window.panes.push(PaneSnapshot {
+            index: pane_index.parse().unwrap_or(0),
+            cwd,
+            command: pane_command(pid.parse().unwrap_or(0), &current, &table),
+        });
+    }
+
+    let mut sessions: Vec<SessionSnapshot> = sessions.into_values().collect();
+    for session in &mut sessions {
+        session.windows.sort_by_key(|w| w.index);
+        for window in &mut session.windows {
+            window.panes.sort_by_key(|p| p.index);
+        }
+    }
+    Ok(Snapshot {
+        created_at: super::activity::now_unix(),
+        sessions,
+    })
+}
+
+/// Save a snapshot to `<data dir>/snapshots/<name>.json`, named by timestamp by default
+pub async fn save(name: Option<&str>) -> Result<String, String> {
+    let snapshot = capture().await?;
+    let name = name
+        .map(String::from)
+        .unwrap_or_else(|| format!("snapshot-{}", snapshot.created_at));
+    let path = snapshot_file(&name)?;
+    let _ = fs::create_dir_all(snapshots_dir());
+    let json = serde_json::to_string_pretty(&snapshot)
+        .map_err(|e| format!("Failed to serialize snapshot: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to save snapshot: {}", e))?;
+    Ok(name)
+}
+
+/// Saved snapshot names, newest first
+pub fn list() -> Vec<String> {
+    let mut snapshots: Vec<(std::time::SystemTime, String)> = fs::read_dir(snapshots_dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+            Some((modified, path.file_stem()?.to_string_lossy().to_string()))
+        })
+        .collect();
+    snapshots.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    snapshots.into_iter().map(|(_, name)| name).collect()
+}
+
+/// Recreate `window`'s panes and layout in the window `window_id`, and with
+/// `run_commands` type the commands that were running back into the panes
+async fn restore_window(
+    window_id: &str,
+    window: &WindowSnapshot,
+    run_commands: bool,
+) -> Result<(), String> {
+    for pane in window.panes.iter().skip(1) {
+        super::tmux::split_window(window_id, &pane.cwd, false).await?;
+    }
+    if window.panes.len() > 1 {
+        super::tmux::select_layout(window_id, &window.layout).await?;
+    }
+    if !run_commands {
+        return Ok(());
+    }
+    let pane_ids = super::tmux::list_pane_ids(window_id).await?;
+    for (pane_id, pane) in pane_ids.iter().zip(&window.panes) {
+        if let Some(command) = &pane.command {
+            super::tmux::send_keys_literal(pane_id, command).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Recreate a snapshot's sessions (the newest snapshot by default).
+/// Sessions that already exist are skipped rather than merged. Commands that
+/// were running are only started again with `run_commands`.
+pub async fn restore(name: Option<&str>, run_commands: bool) -> Result<RestoreSummary, String> {
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => list()
+            .into_iter()
+            .next()
+            .ok_or_else(|| "No saved snapshots".to_string())?,
+    };
+    let raw = fs::read_to_string(snapshot_file(&name)?)
+        .map_err(|e| format!("Failed to read snapshot {}: {}", name, e))?;
+    let snapshot: Snapshot =
+        serde_json::from_str(&raw).map_err(|e| format!("Invalid snapshot {}: {}", name, e))?;
+    let home = dirs::home_dir()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    super::hooks::run(Scope::Workspace, &name, Phase::Pre, Some(&home)).await;
+
+    let existing: Vec<String> = super::tmux::list_sessions()
+        .await
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+    let mut summary = RestoreSummary {
+        restored: vec![],
+        skipped: vec![],
+    };
+
+    for session in &snapshot.sessions {
+        if existing.contains(&session.name) {
+            summary.skipped.push(session.name.clone());
+            continue;
+        }
+        let Some(first) = session.windows.first() else {
+            continue;
+        };
+        let first_cwd = first.panes.first().map(|p| p.cwd.as_str());
+        let cwd = first_cwd.or(session.path.as_deref()).unwrap_or(&home);
+        super::tmux::create_session(&session.name, cwd).await?;
+
+        let first_id = super::tmux::list_window_ids(&session.name)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("Session has no windows: {}", session.name))?;
+        super::tmux::rename_window(&first_id, &first.name).await?;
+        restore_window(&first_id, first, run_commands).await?;
+
+        for window in session.windows.iter().skip(1) {
+            let cwd = window.panes.first().map(|p| p.cwd.as_str()).unwrap_or(cwd);
+            let window_id = super::tmux::new_window(&session.name, &window.name, cwd).await?;
+            restore_window(&window_id, window, run_commands).await?;
+        }
+        summary.restored.push(session.name.clone());
+    }
+
+    super::hooks::spawn(Scope::Workspace, &name, Phase::Post, Some(home));
+    Ok(summary)
+}
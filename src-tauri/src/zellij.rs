@@ -0,0 +1,243 @@
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use std::path::Path;
+use tokio::process::Command;
+
+use super::capabilities::Tool;
+use super::multiplexer::{AttachCommand, Multiplexer};
+use super::templates::{resolve, TemplateWindow};
+use super::tmux::{TmuxPane, TmuxSession, TmuxWindow};
+
+/// Zellij through its CLI (`zellij list-sessions`, `zellij action`).
+/// Its tabs and panes aren't listed, so each session is one pane whose
+/// target is the session name.
+pub struct Zellij;
+
+/// Path to the zellij binary: `multiplexer.zellijBinary`, then `$PATH` and
+/// common install prefixes
+pub fn zellij_binary() -> String {
+    if let Some(binary) = super::settings::get_settings()
+        .settings
+        .multiplexer
+        .zellij_binary
+        .filter(|b| !b.is_empty())
+    {
+        return super::settings::expand_tilde(&binary)
+            .to_string_lossy()
+            .to_string();
+    }
+    super::capabilities::find_program("zellij")
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|| "zellij".to_string())
+}
+
+/// Run a zellij command, returning stdout or zellij's stderr as the error
+async fn run(args: &[&str], cwd: Option<&str>) -> Result<String, String> {
+    super::capabilities::require(Tool::Zellij)?;
+    let subcommand = args
+        .iter()
+        .find(|a| !a.starts_with('-'))
+        .copied()
+        .unwrap_or("zellij");
+    let mut cmd = Command::new(zellij_binary());
+    cmd.args(args);
+    if let Some(cwd) = cwd {
+        cmd.current_dir(super::settings::expand_tilde(cwd));
+    }
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run zellij {}: {}", subcommand, e))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(format!(
+            "zellij {} failed: {}",
+            subcommand,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// `zellij --session <session> action <args>`
+async fn action(session: &str, args: &[&str]) -> Result<String, String> {
+    run(&[&["--session", session, "action"], args].concat(), None).await
+}
+
+/// The single pane standing in for a session
+fn session_pane(name: &str) -> TmuxPane {
+    TmuxPane {
+        session_name: name.to_string(),
+        window_index: 0,
+        window_name: name.to_string(),
+        pane_index: 0,
+        pane_id: String::new(),
+        window_id: String::new(),
+        session_id: name.to_string(),
+        target: name.to_string(),
+        active: true,
+        cols: 80,
+        rows: 24,
+        left: 0,
+        top: 0,
+        pid: 0,
+        process: "zellij".to_string(),
+        dead: false,
+        dead_status: None,
+        title: String::new(),
+        in_mode: false,
+        alternate_on: false,
+        scroll_position: None,
+        last_output: None,
+        output_rate: None,
+        cpu_percent: None,
+        rss_bytes: None,
+        meta: None,
+        claude_session: None,
+        pair: None,
+    }
+}
+
+/// Names of running sessions. Exited sessions Zellij can resurrect are skipped.
+async fn session_names() -> Vec<String> {
+    // Fails with "No active zellij sessions found" when there are none
+    let Ok(stdout) = run(&["list-sessions", "--no-formatting"], None).await else {
+        return vec![];
+    };
+    stdout
+        .lines()
+        .filter(|line| !line.contains("EXITED"))
+        .filter_map(|line| line.split_whitespace().next())
+        .map(|name| name.to_string())
+        .collect()
+}
+
+impl Multiplexer for Zellij {
+    fn name(&self) -> &'static str {
+        "zellij"
+    }
+
+    fn tool(&self) -> Option<Tool> {
+        Some(Tool::Zellij)
+    }
+
+    fn list_sessions(&self) -> BoxFuture<'_, Vec<TmuxSession>> {
+        async {
+            session_names()
+                .await
+                .into_iter()
+                .map(|name| TmuxSession {
+                    id: name.clone(),
+                    windows: vec![TmuxWindow {
+                        index: 0,
+                        name: name.clone(),
+                        panes: vec![session_pane(&name)],
+                    }],
+                    name,
+                    dimensions: None,
+                    activity: None,
+                    path: None,
+                    inferred_group: None,
+                    server: None,
+                    session_group: None,
+                })
+                .collect()
+        }
+        .boxed()
+    }
+
+    fn create_session<'a>(
+        &'a self,
+        name: &'a str,
+        cwd: &'a str,
+        command: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<bool, String>> {
+        async move {
+            if session_names().await.iter().any(|s| s == name) {
+                return Ok(false);
+            }
+            run(&["attach", "--create-background", name], Some(cwd)).await?;
+            // Zellij only starts sessions with a command through a layout
+            if let Some(command) = command {
+                self.send_input(name, command).await?;
+            }
+            Ok(true)
+        }
+        .boxed()
+    }
+
+    fn kill_session<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<(), String>> {
+        async move { run(&["kill-session", name], None).await.map(|_| ()) }.boxed()
+    }
+
+    /// A new tab; splits and commands go to the focused (newest) pane, and
+    /// `layout` is ignored
+    fn add_window<'a>(
+        &'a self,
+        session: &'a str,
+        window: &'a TemplateWindow,
+        project: &'a Path,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        async move {
+            let cwd = resolve(project, window.cwd.as_deref());
+            let first_cwd = resolve(&cwd, window.panes.first().and_then(|p| p.cwd.as_deref()));
+            let first_cwd = first_cwd.to_string_lossy();
+            action(
+                session,
+                &["new-tab", "--name", &window.name, "--cwd", &first_cwd],
+            )
+            .await?;
+            for (i, pane) in window.panes.iter().enumerate() {
+                if i > 0 {
+                    let direction = if pane.horizontal { "right" } else { "down" };
+                    let pane_cwd = resolve(&cwd, pane.cwd.as_deref());
+                    let pane_cwd = pane_cwd.to_string_lossy();
+                    let options = ["new-pane", "--direction", direction, "--cwd", &pane_cwd];
+                    action(session, &options).await?;
+                }
+                if let Some(command) = pane.command.as_deref().filter(|c| !c.is_empty()) {
+                    self.send_input(session, command).await?;
+                }
+            }
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn send_input<'a>(
+        &'a self,
+        target: &'a str,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        async move {
+            run(&["--session", target, "action", "write-chars", text], None).await?;
+            // Enter
+            run(&["--session", target, "action", "write", "13"], None).await?;
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn pane_info<'a>(&'a self, target: &'a str) -> BoxFuture<'a, Option<TmuxPane>> {
+        async move {
+            session_names()
+                .await
+                .iter()
+                .any(|s| s == target)
+                .then(|| session_pane(target))
+        }
+        .boxed()
+    }
+
+    fn attach_command(&self, target: &str, read_only: bool) -> Result<AttachCommand, String> {
+        if read_only {
+            return Err("Zellij sessions can't be attached read-only".to_string());
+        }
+        Ok(AttachCommand {
+            program: zellij_binary(),
+            args: vec!["attach".to_string(), target.to_string()],
+            env: vec![],
+        })
+    }
+}
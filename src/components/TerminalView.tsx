@@ -398,6 +398,9 @@ export function TerminalView({
         if (wsGenerationRef.current !== generation) return;
         if (msg.type === "session-changed" && msg.session) {
           onSessionChangedRef.current?.(msg.session);
+        } else if (msg.type === "resize" && msg.cols && msg.rows) {
+          // tmux sized the window for another client; match it rather than clip
+          terminalInstanceRef.current?.resize(msg.cols, msg.rows);
         }
       });
 
@@ -16,6 +16,51 @@ const COMMAND_MAP: Record<string, string> = {
   "settings.get": "settings_get",
 };
 
+function base64ToBytes(data: string): Uint8Array {
+  const binary = atob(data);
+  const bytes = new Uint8Array(binary.length);
+  for (let i = 0; i < binary.length; i++) bytes[i] = binary.charCodeAt(i);
+  return bytes;
+}
+
+function readLength(input: Uint8Array, i: number, length: number): [number, number] {
+  if (length === 15) {
+    let byte: number;
+    do {
+      byte = input[i++];
+      length += byte;
+    } while (byte === 255);
+  }
+  return [length, i];
+}
+
+// An LZ4 block after its little-endian u32 decompressed length, as the
+// backend's `lz4_flex::compress_prepend_size` writes it
+function lz4Decode(input: Uint8Array): ArrayBuffer {
+  const size = (input[0] | (input[1] << 8) | (input[2] << 16) | (input[3] << 24)) >>> 0;
+  const out = new Uint8Array(size);
+  let i = 4;
+  let o = 0;
+  while (i < input.length) {
+    const token = input[i++];
+    let literals: number;
+    [literals, i] = readLength(input, i, token >> 4);
+    out.set(input.subarray(i, i + literals), o);
+    i += literals;
+    o += literals;
+    // The last sequence is literals only
+    if (i >= input.length) break;
+    const offset = input[i] | (input[i + 1] << 8);
+    let length: number;
+    [length, i] = readLength(input, i + 2, token & 15);
+    if (offset === 0 || offset > o || o + length + 4 > size) throw new Error("Corrupt LZ4 block");
+    // Byte by byte: a match may overlap the output it copies
+    for (let from = o - offset, end = o + length + 4; o < end; ) out[o++] = out[from++];
+  }
+  if (o !== size) throw new Error("Corrupt LZ4 block");
+  return out.buffer;
+}
+
 export class TauriTransport implements MuxTransport {
   private invoke!: (cmd: string, args?: Record<string, unknown>) => Promise<unknown>;
   private Channel!: new <T>(onMessage: (msg: T) => void) => { onmessage: (msg: T) => void };
@@ -66,14 +111,37 @@ export class TauriTransport implements MuxTransport {
         const channel = new this.Channel<any>((message: any) => {
           if (!message) return;
 
-          if (message.type === "data" && message.data) {
+          if (message instanceof ArrayBuffer) {
+            // terminal.dataTransport "raw": output arrives as-is
+            for (const cb of dataListeners) cb(message);
+          } else if (message.type === "data-lz4" && message.data) {
+            const buffer = lz4Decode(base64ToBytes(message.data));
+            for (const cb of dataListeners) cb(buffer);
+          } else if (message.type === "data-base64" && message.data) {
+            const buffer = base64ToBytes(message.data).buffer;
+            for (const cb of dataListeners) cb(buffer);
+          } else if (message.type === "data" && message.data) {
             // Convert byte array to ArrayBuffer
             const bytes = new Uint8Array(message.data);
             for (const cb of dataListeners) cb(bytes.buffer);
-          } else if (message.type === "pane-info") {
+          } else if (message.type === "ping") {
+            // Unanswered pings tell the backend this view is gone
+            this.invoke("pty_pong", { target }).catch(() => {});
+          } else if (message.type === "pane-info" || message.type === "resize") {
             for (const cb of msgListeners) cb(message);
           } else if (message.type === "exit") {
-            for (const cb of closeListeners) cb(1000, "PTY exited");
+            // A dead tmux server won't come back, and reconnecting to an
+            // evicted PTY would evict another; leave both to the user
+            const final = message.reason === "server-exited" || message.reason === "evicted";
+            const code = final ? 4000 : 1000;
+            const reason = message.reason === "server-exited"
+              ? "tmux server exited"
+              : message.reason === "evicted"
+                ? "too many terminals open (terminal.maxPtys)"
+                : message.signal
+                ? `PTY killed by ${message.signal}`
+                : `PTY exited (${message.reason ?? `code ${message.code}`})`;
+            for (const cb of closeListeners) cb(code, reason);
           } else if (message.type === "error") {
             for (const cb of errorListeners)
               cb(new Event(message.message || "PTY error"));
@@ -85,6 +153,8 @@ export class TauriTransport implements MuxTransport {
           target,
           cols,
           rows,
+          // Large redraws arrive LZ4-compressed
+          options: { compression: "lz4" },
           onData: channel,
         });
 